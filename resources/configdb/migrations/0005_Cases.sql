@@ -0,0 +1,33 @@
+-- Cases (investigations) bundle related events together.
+CREATE TABLE cases (
+  id          INTEGER PRIMARY KEY,
+  title       STRING NOT NULL,
+  description STRING NOT NULL DEFAULT '',
+  severity    STRING NOT NULL DEFAULT 'medium',
+  status      STRING NOT NULL DEFAULT 'open',
+  created_by  STRING,
+  created_at  INTEGER NOT NULL,
+  updated_at  INTEGER NOT NULL
+);
+
+-- Timeline of notes on a case.
+CREATE TABLE case_notes (
+  id         INTEGER PRIMARY KEY,
+  case_id    INTEGER NOT NULL,
+  username   STRING,
+  timestamp  INTEGER NOT NULL,
+  note       STRING NOT NULL,
+  FOREIGN KEY (case_id) REFERENCES cases(id) ON DELETE CASCADE
+);
+
+CREATE INDEX case_notes_case_id_index ON case_notes (case_id);
+
+-- Events linked to a case, by datastore event ID.
+CREATE TABLE case_events (
+  case_id    INTEGER NOT NULL,
+  event_id   STRING NOT NULL,
+  added_by   STRING,
+  added_at   INTEGER NOT NULL,
+  UNIQUE (case_id, event_id),
+  FOREIGN KEY (case_id) REFERENCES cases(id) ON DELETE CASCADE
+);
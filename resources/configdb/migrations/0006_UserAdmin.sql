@@ -0,0 +1,4 @@
+-- Administrators may act on other users' data, such as editing or
+-- deleting their comments. Existing users are not administrators,
+-- use "evebox config users admin <username>" to grant it.
+ALTER TABLE users ADD COLUMN admin INTEGER NOT NULL DEFAULT 0;
@@ -0,0 +1,11 @@
+-- Audit log of user actions.
+CREATE TABLE audit (
+  id          INTEGER PRIMARY KEY,
+  timestamp   INTEGER NOT NULL,
+  username    STRING,
+  remote_addr STRING,
+  action      STRING NOT NULL,
+  details     JSON
+);
+
+CREATE INDEX audit_timestamp_index ON audit (timestamp);
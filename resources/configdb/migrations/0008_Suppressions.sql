@@ -0,0 +1,12 @@
+-- Analyst defined suppression of alerts, applied at ingest.
+CREATE TABLE suppressions (
+  id           INTEGER PRIMARY KEY,
+  signature_id INTEGER NOT NULL,
+  src_ip       STRING,
+  dest_ip      STRING,
+  sensor       STRING,
+  comment      STRING,
+  expires_at   INTEGER,
+  created_by   STRING,
+  created_at   INTEGER NOT NULL
+);
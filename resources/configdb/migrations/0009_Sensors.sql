@@ -0,0 +1,10 @@
+-- Sensors seen sending events, for detecting silent sensors.
+CREATE TABLE sensors (
+  name          STRING PRIMARY KEY,
+  first_seen    INTEGER NOT NULL,
+  last_seen     INTEGER NOT NULL,
+  event_count   INTEGER NOT NULL DEFAULT 0,
+  event_rate    REAL NOT NULL DEFAULT 0,
+  agent_version STRING,
+  silent        INTEGER NOT NULL DEFAULT 0
+);
@@ -0,0 +1,5 @@
+-- Triage status of an event: new, in-progress, true-positive,
+-- false-positive, benign or closed.
+ALTER TABLE events
+      ADD COLUMN status TEXT NOT NULL
+      default 'new';
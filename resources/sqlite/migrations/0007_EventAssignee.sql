@@ -0,0 +1,4 @@
+-- Username of the analyst an event is assigned to, NULL if
+-- unassigned.
+ALTER TABLE events
+      ADD COLUMN assignee TEXT;
@@ -0,0 +1,20 @@
+-- The events each comment was added to, so a comment can be edited
+-- or deleted without scanning the history of every event.
+CREATE TABLE event_comments (
+       id TEXT NOT NULL,
+       event_id INTEGER NOT NULL,
+       PRIMARY KEY (id, event_id)
+);
+
+CREATE INDEX event_comments_event_id_index ON event_comments (event_id);
+
+INSERT OR IGNORE INTO event_comments (id, event_id)
+       SELECT json_extract(history.value, '$.id'), events.rowid
+       FROM events, json_each(events.history) AS history
+       WHERE json_extract(history.value, '$.action') = 'comment'
+         AND json_extract(history.value, '$.id') IS NOT NULL;
+
+CREATE TRIGGER event_comments_delete AFTER DELETE ON events
+BEGIN
+       DELETE FROM event_comments WHERE event_id = old.rowid;
+END;
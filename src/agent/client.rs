@@ -38,7 +38,11 @@ impl Client {
         let request = self
             .get_http_client()?
             .post(url)
-            .header("Content-Type", "application/json");
+            .header("Content-Type", "application/json")
+            .header(
+                "User-Agent",
+                format!("EveBox-Agent/{}", crate::version::version()),
+            );
         let request = if let Some(username) = &self.username {
             request.basic_auth(username, self.password.clone())
         } else {
@@ -5,8 +5,9 @@ use crate::agent::client::Client;
 use crate::agent::importer::EveBoxEventSink;
 use crate::bookmark;
 use crate::config::Config;
-use crate::eve::filters::{AddRuleFilter, EveFilter};
+use crate::eve::filters::{AddRuleFilter, EveFilter, RuleMetadataFilter};
 use crate::importer::EventSink;
+use crate::syslog::{SyslogConfig, SyslogEventSink};
 use clap::{CommandFactory, Parser};
 use futures::stream::FuturesUnordered;
 use futures::StreamExt;
@@ -151,9 +152,20 @@ pub async fn main(args_matches: &clap::ArgMatches) -> anyhow::Result<()> {
                 map: rule_collection.clone(),
             },
         ));
+        filters.push(crate::eve::filters::EveFilter::RuleMetadataFilter(
+            RuleMetadataFilter {
+                map: rule_collection.clone(),
+            },
+        ));
         crate::rules::watch_rules(rule_collection);
     }
 
+    if config.get_bool("input.community-id")? {
+        let seed: u16 = config.get_value("input.community-id-seed")?.unwrap_or(0);
+        info!("Adding Community ID to events with seed {}", seed);
+        filters.push(crate::eve::filters::CommunityIdFilter::new(seed).into());
+    }
+
     if let Some(custom_fields) = additional_fields {
         for (field, value) in custom_fields {
             info!("Adding custom field: {} -> {:?}", field, value);
@@ -167,6 +179,16 @@ pub async fn main(args_matches: &clap::ArgMatches) -> anyhow::Result<()> {
 
     let mut log_runners: HashMap<String, bool> = HashMap::new();
 
+    let server_enabled = config.get_value::<bool>("server.enabled")?.unwrap_or(true);
+    let mut syslog = config
+        .get_value::<SyslogConfig>("syslog")?
+        .filter(|syslog| syslog.enabled)
+        .map(|syslog| {
+            info!("Sending events to syslog server: {}", syslog.address);
+            SyslogEventSink::start(syslog).map(EventSink::Syslog)
+        })
+        .transpose()?;
+
     let importer = if config.get_bool("elasticsearch.enabled")? {
         let url = config.get_string("elasticsearch.url").unwrap();
         let mut client = crate::elastic::ClientBuilder::new(&url);
@@ -183,6 +205,11 @@ pub async fn main(args_matches: &clap::ArgMatches) -> anyhow::Result<()> {
         let importer =
             crate::elastic::importer::ElasticEventSink::new(client.build(), &index, nodate);
         EventSink::Elastic(importer)
+    } else if !server_enabled {
+        match syslog.take() {
+            Some(syslog) => syslog,
+            None => bail!("No output enabled, enable the server, Elasticsearch or syslog"),
+        }
     } else {
         let client = Client::new(
             &server_url,
@@ -193,6 +220,10 @@ pub async fn main(args_matches: &clap::ArgMatches) -> anyhow::Result<()> {
         info!("Sending events to EveBox server: {server_url}");
         EventSink::EveBox(EveBoxEventSink::new(client))
     };
+    let importer = match syslog {
+        Some(syslog) => EventSink::Multi(vec![importer, syslog]),
+        None => importer,
+    };
 
     let bookmark_directory = config.get_string("bookmark-directory");
     if bookmark_directory.is_some() {
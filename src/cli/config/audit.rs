@@ -0,0 +1,73 @@
+// SPDX-FileCopyrightText: (C) 2024 Jason Ish <jason@codemonkey.net>
+// SPDX-License-Identifier: MIT
+
+use anyhow::Result;
+use clap::FromArgMatches;
+use clap::Parser;
+
+use crate::datetime::DateTime;
+use crate::sqlite::configrepo::audit::AuditQuery;
+
+use super::users::open_config_repo;
+
+#[derive(Parser, Debug)]
+#[command(name = "audit", about = "Show the audit log")]
+pub(crate) struct AuditArgs {
+    /// Only show entries newer than this duration, e.g. 24h
+    #[arg(long)]
+    since: Option<String>,
+    /// Only show entries at or after this time
+    #[arg(long)]
+    from: Option<String>,
+    /// Only show entries at or before this time
+    #[arg(long)]
+    to: Option<String>,
+    /// Only show entries for this username
+    #[arg(long, short)]
+    username: Option<String>,
+    /// Only show entries where the action contains this string
+    #[arg(long, short)]
+    action: Option<String>,
+    /// Maximum number of entries to show
+    #[arg(long, default_value = "1000")]
+    limit: u64,
+
+    #[arg(from_global, id = "data-directory")]
+    data_directory: Option<String>,
+}
+
+pub(crate) async fn main(args: &clap::ArgMatches) -> Result<()> {
+    let args = AuditArgs::from_arg_matches(args)?;
+    let repo = open_config_repo(args.data_directory.as_deref()).await?;
+
+    let mut from = args
+        .from
+        .as_deref()
+        .map(|from| crate::datetime::parse(from, None))
+        .transpose()?;
+    if let Some(since) = &args.since {
+        let since = chrono::Duration::from_std(humantime::parse_duration(since)?)?;
+        from = Some(DateTime::now().sub(since));
+    }
+    let to = args
+        .to
+        .as_deref()
+        .map(|to| crate::datetime::parse(to, None))
+        .transpose()?;
+
+    let query = AuditQuery {
+        from,
+        to,
+        username: args.username,
+        action: args.action,
+        limit: Some(args.limit),
+    };
+
+    // Print oldest first, like a log.
+    let mut entries = repo.get_audit(&query).await?;
+    entries.reverse();
+    for entry in entries {
+        println!("{}", serde_json::to_string(&entry)?);
+    }
+    Ok(())
+}
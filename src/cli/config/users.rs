@@ -10,6 +10,7 @@ use clap::Subcommand;
 use tracing::info;
 
 use crate::sqlite::configrepo;
+use crate::sqlite::configrepo::audit::action;
 use crate::sqlite::configrepo::ConfigRepo;
 
 #[derive(Parser, Debug)]
@@ -41,6 +42,15 @@ enum UsersCommands {
         #[arg(from_global, id = "data-directory")]
         data_directory: Option<String>,
     },
+    /// Grant or revoke administrator rights
+    Admin {
+        username: String,
+        /// Revoke administrator rights
+        #[arg(long)]
+        revoke: bool,
+        #[arg(from_global, id = "data-directory")]
+        data_directory: Option<String>,
+    },
 }
 
 #[derive(Parser, Debug)]
@@ -49,6 +59,9 @@ struct AddArgs {
     username: Option<String>,
     #[arg(long, short)]
     password: Option<String>,
+    /// Make the user an administrator
+    #[arg(long)]
+    admin: bool,
 
     #[arg(from_global, id = "data-directory")]
     data_directory: Option<String>,
@@ -67,10 +80,23 @@ pub(crate) async fn main(args: &clap::ArgMatches) -> Result<()> {
             username,
             data_directory,
         } => password(username, data_directory).await,
+        UsersCommands::Admin {
+            username,
+            revoke,
+            data_directory,
+        } => admin(username, !revoke, data_directory).await,
     }
 }
 
-async fn open_config_repo<P: AsRef<Path>>(data_directory: Option<P>) -> Result<ConfigRepo> {
+/// Record a user management action in the audit log.
+async fn audit(repo: &ConfigRepo, action: &str, details: serde_json::Value) -> Result<()> {
+    repo.add_audit(None, None, action, Some(details)).await?;
+    Ok(())
+}
+
+pub(super) async fn open_config_repo<P: AsRef<Path>>(
+    data_directory: Option<P>,
+) -> Result<ConfigRepo> {
     let data_directory = data_directory
         .map(|p| PathBuf::from(p.as_ref()))
         .or_else(crate::path::data_directory);
@@ -119,6 +145,15 @@ async fn add(args: AddArgs) -> Result<()> {
     };
 
     repo.add_user(&username, &password).await?;
+    if args.admin {
+        repo.set_admin(&username, true).await?;
+    }
+    audit(
+        &repo,
+        action::USER_ADD,
+        json!({"username": username, "admin": args.admin, "source": "cli"}),
+    )
+    .await?;
     println!("User added: username=\"{username}\"");
 
     Ok(())
@@ -129,6 +164,12 @@ async fn remove(username: String, dir: Option<String>) -> Result<()> {
     if repo.remove_user(&username).await? == 0 {
         return Err(anyhow!("user does not exist"));
     }
+    audit(
+        &repo,
+        action::USER_REMOVE,
+        json!({"username": username, "source": "cli"}),
+    )
+    .await?;
     println!("User removed: username=\"{username}\"");
     Ok(())
 }
@@ -142,9 +183,32 @@ async fn password(username: String, data_directory: Option<String>) -> Result<()
         .with_validator(inquire::required!())
         .prompt()?;
     if repo.update_password_by_id(&user.uuid, &password).await? {
+        audit(
+            &repo,
+            action::USER_PASSWORD,
+            json!({"username": username, "source": "cli"}),
+        )
+        .await?;
         println!("Password has been updated.");
         Ok(())
     } else {
         Err(anyhow!("Failed to update password, user does not exist"))
     }
 }
+
+async fn admin(username: String, admin: bool, data_directory: Option<String>) -> Result<()> {
+    let repo = open_config_repo(data_directory).await?;
+    repo.set_admin(&username, admin).await?;
+    audit(
+        &repo,
+        action::USER_ADMIN,
+        json!({"username": username, "admin": admin, "source": "cli"}),
+    )
+    .await?;
+    if admin {
+        println!("User is now an administrator: username=\"{username}\"");
+    } else {
+        println!("User is no longer an administrator: username=\"{username}\"");
+    }
+    Ok(())
+}
@@ -308,9 +308,12 @@ async fn optimize(args: &OptimizeArgs) -> Result<()> {
     let gte = datetime::DateTime::now().sub(chrono::Duration::days(1));
     repo.alerts(AlertQueryOptions {
         timestamp_gte: Some(gte),
+        timestamp_lte: None,
         query_string: None,
         tags: vec![],
         sensor: None,
+        group_by: vec![],
+        username: None,
     })
     .await
     .map_err(|err| anyhow::anyhow!(format!("{}", err)))?;
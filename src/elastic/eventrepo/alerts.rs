@@ -11,8 +11,28 @@ use crate::{
 
 use super::{ElasticEventRepo, MINIMUM_SHOULD_MATCH};
 
+/// Number of alert groups to fetch per composite aggregation request.
+const COMPOSITE_PAGE_SIZE: usize = 1000;
+
+/// Maximum number of composite aggregation requests per inbox query,
+/// limiting the number of alert groups returned.
+const MAX_COMPOSITE_PAGES: usize = 10;
+
 impl ElasticEventRepo {
     pub fn build_inbox_query(&self, options: AlertQueryOptions) -> serde_json::Value {
+        // One composite source per grouping key, in the order given.
+        let sources: Vec<serde_json::Value> = options
+            .group_keys()
+            .iter()
+            .map(|key| {
+                json!({
+                    key.as_str(): {
+                        "terms": {"field": self.map_field(key.field())}
+                    }
+                })
+            })
+            .collect();
+
         let mut filters = Vec::new();
         let mut should = Vec::new();
         let mut must_not = Vec::new();
@@ -23,7 +43,8 @@ impl ElasticEventRepo {
         if let Some(q) = &options.query_string {
             // TODO: Need client tz_offset here.
             match queryparser::parse(q, None) {
-                Ok(elements) => {
+                Ok(mut elements) => {
+                    queryparser::resolve_assignee_me(&mut elements, options.username.as_deref());
                     self.apply_query_string(&elements, &mut filters, &mut should, &mut must_not);
                     has_min_timestamp = elements
                         .iter()
@@ -51,6 +72,10 @@ impl ElasticEventRepo {
             }
         }
 
+        if let Some(ts) = options.timestamp_lte {
+            filters.push(json!({"range": {"@timestamp": {"lte": ts.to_elastic()}}}));
+        }
+
         for tag in options.tags {
             if let Some(tag) = tag.strip_prefix('-') {
                 let j = json!({"term": {"tags": tag}});
@@ -82,6 +107,8 @@ impl ElasticEventRepo {
                 "http.hostname",
                 "quic.sni",
                 "tls.sni",
+                "evebox.status",
+                "evebox.assignee",
             ])
         };
 
@@ -93,60 +120,50 @@ impl ElasticEventRepo {
                 }
             },
             "sort": [{"@timestamp": {"order": "desc"}}],
+            "size": 0,
             "aggs": {
-                "signatures": {
-                    "terms": {"field": self.map_field("alert.signature_id"), "size": 2000},
+                "groups": {
+                    "composite": {
+                        "size": COMPOSITE_PAGE_SIZE,
+                        "sources": sources,
+                    },
                     "aggs": {
-                        "sources": {
-                            "terms": {"field": self.map_field("src_ip"), "size": 1000},
-                            "aggs": {
-                                "destinations": {
-                                    "terms": {
-                                        "field": self.map_field("dest_ip"),
-                                        "size": 500
-                                    },
-                                    "aggs": {
-                                        "escalated": {
-                                            "filter": {
-                                                "term": {
-                                                    "tags": "evebox.escalated",
-                                                }
-                                            }
-                                        },
-                                        "newest": {
-                                            "top_hits": {
-                                                "size": 1,
-                                                "sort": [
-                                                    {
-                                                        self.map_field("timestamp"): {"order": "desc"}
-                                                    }
-                                                ],
-                                                "_source": source,
-                                            }
-                                        },
-                                        "oldest": {
-                                            "top_hits": {
-                                                "size": 1,
-                                                "sort": [
-                                                    {
-                                                        self.map_field("timestamp"): {"order": "asc"}
-                                                    }
-                                                ],
-                                                // We only need the
-                                                // timestamp from the
-                                                // oldest event.
-                                                "_source": [
-                                                    "timestamp",
-
-                                                    // ECS doesn't have timestamp.
-                                                    "@timestamp",
-                                                ]
-                                            }
-                                        }
-                                    },
-                                },
-                            },
+                        "escalated": {
+                            "filter": {
+                                "term": {
+                                    "tags": "evebox.escalated",
+                                }
+                            }
                         },
+                        "newest": {
+                            "top_hits": {
+                                "size": 1,
+                                "sort": [
+                                    {
+                                        self.map_field("timestamp"): {"order": "desc"}
+                                    }
+                                ],
+                                "_source": source,
+                            }
+                        },
+                        "oldest": {
+                            "top_hits": {
+                                "size": 1,
+                                "sort": [
+                                    {
+                                        self.map_field("timestamp"): {"order": "asc"}
+                                    }
+                                ],
+                                // We only need the timestamp from the
+                                // oldest event.
+                                "_source": [
+                                    "timestamp",
+
+                                    // ECS doesn't have timestamp.
+                                    "@timestamp",
+                                ]
+                            }
+                        }
                     },
                 }
             }
@@ -161,117 +178,142 @@ impl ElasticEventRepo {
     }
 
     pub async fn alerts(&self, options: AlertQueryOptions) -> Result<AlertsResult, DatastoreError> {
+        let group_by = options.group_keys().to_vec();
         let mut query = self.build_inbox_query(options);
         query["timeout"] = "3s".into();
         let start = std::time::Instant::now();
-        let body = self.search(&query).await?.text().await?;
-        let response: ElasticResponse = serde_json::from_str(&body)?;
-        if let Some(error) = &response.error {
-            return Err(DatastoreError::ElasticSearchError(error.first_reason()));
-        }
-
-        debug!(
-            "Elasticsearch alert query took {:?}, es-time: {}, response-size: {}, timed-out: {}",
-            start.elapsed(),
-            response.took,
-            body.len(),
-            response.timed_out,
-        );
 
         let mut alerts: Vec<AggAlert> = vec![];
-        if let Some(aggregrations) = response.aggregations {
-            if let serde_json::Value::Array(buckets) = &aggregrations["signatures"]["buckets"] {
-                for bucket in buckets {
-                    if let serde_json::Value::Array(buckets) = &bucket["sources"]["buckets"] {
-                        for bucket in buckets {
-                            if let serde_json::Value::Array(buckets) =
-                                &bucket["destinations"]["buckets"]
-                            {
-                                for bucket in buckets {
-                                    let mut newest = bucket["newest"]["hits"]["hits"][0].clone();
-                                    let oldest = bucket["oldest"]["hits"]["hits"][0].clone();
-
-                                    if self.ecs {
-                                        self.transform_ecs(&mut newest);
-                                    }
+        let mut took = 0;
+        let mut timed_out = false;
+        let mut truncated = false;
 
-                                    let escalated = &bucket["escalated"]["doc_count"]
-                                        .as_u64()
-                                        .ok_or_else(|| anyhow!("Missing escalated doc_count"))?;
+        // Page through the composite aggregation until all groups
+        // have been fetched, or the page limit is reached.
+        for page in 1.. {
+            let body = self.search(&query).await?.text().await?;
+            let response: ElasticResponse = serde_json::from_str(&body)?;
+            if let Some(error) = &response.error {
+                return Err(DatastoreError::ElasticSearchError(error.first_reason()));
+            }
 
-                                    let min_timestamp = if oldest["_source"]["timestamp"]
-                                        .is_string()
-                                    {
-                                        &oldest["_source"]["timestamp"]
-                                    } else {
-                                        &oldest["_source"]["@timestamp"]
-                                    }
-                                    .as_str()
-                                    .ok_or_else(|| {
-                                        anyhow!("No timestamp field on _source or not a string")
-                                    })?;
+            debug!(
+                "Elasticsearch alert query took {:?}, es-time: {}, response-size: {}, timed-out: {}",
+                start.elapsed(),
+                response.took,
+                body.len(),
+                response.timed_out,
+            );
 
-                                    let min_timestamp =
-                                        crate::datetime::parse(min_timestamp, None)?;
+            took += response.took;
+            timed_out |= response.timed_out;
 
-                                    let max_timestamp = if newest["_source"]["timestamp"]
-                                        .is_string()
-                                    {
-                                        &newest["_source"]["timestamp"]
-                                    } else {
-                                        &newest["_source"]["@timestamp"]
-                                    }
-                                    .as_str()
-                                    .ok_or_else(|| {
-                                        anyhow!("No timestamp field on _source or not a string")
-                                    })?;
-
-                                    let max_timestamp =
-                                        crate::datetime::parse(max_timestamp, None)?;
-
-                                    let count = bucket["doc_count"]
-                                        .as_u64()
-                                        .ok_or_else(|| anyhow!("doc_count field missing"))?;
-
-                                    let id = newest["_id"]
-                                        .as_str()
-                                        .ok_or_else(|| anyhow!("_id field missing"))?
-                                        .to_string();
-                                    let source = newest["_source"].take();
-
-                                    // TODO: Do something with whats
-                                    // left in newest. Perhaps an
-                                    // "_elastic" field in the
-                                    // response.
-
-                                    let alert = AggAlert {
-                                        id,
-                                        source,
-                                        metadata: AggAlertMetadata {
-                                            count,
-                                            escalated_count: *escalated,
-                                            min_timestamp,
-                                            max_timestamp,
-                                        },
-                                    };
-                                    alerts.push(alert);
-                                }
-                            }
-                        }
-                    }
+            let aggregations = match response.aggregations {
+                Some(aggregations) => aggregations,
+                None => {
+                    warn!("Elasticsearch response has no aggregations");
+                    break;
                 }
+            };
+
+            let buckets = match aggregations["groups"]["buckets"].as_array() {
+                Some(buckets) => buckets,
+                None => break,
+            };
+            for bucket in buckets {
+                alerts.push(self.alert_from_bucket(bucket)?);
             }
-        } else {
-            warn!("Elasticsearch response has no aggregations");
+
+            let after_key = &aggregations["groups"]["after_key"];
+            if timed_out || buckets.len() < COMPOSITE_PAGE_SIZE || !after_key.is_object() {
+                break;
+            }
+            if page == MAX_COMPOSITE_PAGES {
+                warn!(
+                    "Alert query truncated at {} groups",
+                    MAX_COMPOSITE_PAGES * COMPOSITE_PAGE_SIZE
+                );
+                truncated = true;
+                break;
+            }
+            query["aggs"]["groups"]["composite"]["after"] = after_key.clone();
         }
 
+        // Composite buckets are ordered by key, return the most recent
+        // groups first.
+        alerts.sort_by(|a, b| {
+            b.metadata
+                .max_timestamp
+                .to_nanos()
+                .cmp(&a.metadata.max_timestamp.to_nanos())
+        });
+
         let response = AlertsResult {
             ecs: self.ecs,
             events: alerts,
-            took: response.took,
-            timed_out: response.timed_out,
+            took,
+            timed_out,
+            truncated,
+            group_by,
         };
 
         Ok(response)
     }
+
+    fn alert_from_bucket(&self, bucket: &serde_json::Value) -> Result<AggAlert, DatastoreError> {
+        let mut newest = bucket["newest"]["hits"]["hits"][0].clone();
+        let oldest = bucket["oldest"]["hits"]["hits"][0].clone();
+
+        if self.ecs {
+            self.transform_ecs(&mut newest);
+        }
+
+        let escalated = &bucket["escalated"]["doc_count"]
+            .as_u64()
+            .ok_or_else(|| anyhow!("Missing escalated doc_count"))?;
+
+        let min_timestamp = if oldest["_source"]["timestamp"].is_string() {
+            &oldest["_source"]["timestamp"]
+        } else {
+            &oldest["_source"]["@timestamp"]
+        }
+        .as_str()
+        .ok_or_else(|| anyhow!("No timestamp field on _source or not a string"))?;
+
+        let min_timestamp = crate::datetime::parse(min_timestamp, None)?;
+
+        let max_timestamp = if newest["_source"]["timestamp"].is_string() {
+            &newest["_source"]["timestamp"]
+        } else {
+            &newest["_source"]["@timestamp"]
+        }
+        .as_str()
+        .ok_or_else(|| anyhow!("No timestamp field on _source or not a string"))?;
+
+        let max_timestamp = crate::datetime::parse(max_timestamp, None)?;
+
+        let count = bucket["doc_count"]
+            .as_u64()
+            .ok_or_else(|| anyhow!("doc_count field missing"))?;
+
+        let id = newest["_id"]
+            .as_str()
+            .ok_or_else(|| anyhow!("_id field missing"))?
+            .to_string();
+        let source = newest["_source"].take();
+
+        // TODO: Do something with whats left in newest. Perhaps an
+        // "_elastic" field in the response.
+
+        Ok(AggAlert {
+            id,
+            source,
+            metadata: AggAlertMetadata {
+                count,
+                escalated_count: *escalated,
+                min_timestamp,
+                max_timestamp,
+            },
+        })
+    }
 }
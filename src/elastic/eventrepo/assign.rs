@@ -0,0 +1,93 @@
+// SPDX-FileCopyrightText: (C) 2024 Jason Ish <jason@codemonkey.net>
+// SPDX-License-Identifier: MIT
+
+//! Assignment of events to users.
+//!
+//! Like the triage status, the assignee is stored in `evebox.assignee`
+//! for display, and as a tag of the form `evebox.assignee.<username>`
+//! for querying.
+
+use std::sync::Arc;
+
+use serde_json::json;
+
+use super::{id_query, ElasticEventRepo};
+use crate::elastic::{HistoryEntry, HistoryEntryBuilder};
+use crate::eventrepo::DatastoreError;
+use crate::server::api;
+use crate::server::session::Session;
+
+const ASSIGNEE_TAG_PREFIX: &str = "evebox.assignee.";
+
+impl ElasticEventRepo {
+    /// Assign an event to a user, or unassign it if the assignee is
+    /// `None`.
+    pub async fn set_assignee_by_id(
+        &self,
+        event_id: &str,
+        assignee: Option<String>,
+        session: Arc<Session>,
+    ) -> Result<(), DatastoreError> {
+        let action = HistoryEntryBuilder::new_assign(assignee.clone())
+            .username(session.username.clone())
+            .build();
+        if self
+            .set_assignee_by_query(id_query(event_id), assignee, &action)
+            .await?
+            == 0
+        {
+            return Err(DatastoreError::EventNotFound);
+        }
+        Ok(())
+    }
+
+    pub async fn set_assignee_by_alert_group(
+        &self,
+        alert_group: api::AlertGroupSpec,
+        assignee: Option<String>,
+        session: Arc<Session>,
+    ) -> Result<(), DatastoreError> {
+        let current = assignee_query(assignee.as_deref().unwrap_or("none"));
+        let query = json!({
+            "bool": {
+                "filter": self.build_alert_group_filter(&alert_group)?,
+                "must_not": [current],
+            }
+        });
+        let action = HistoryEntryBuilder::new_assign(assignee.clone())
+            .username(session.username.clone())
+            .build();
+        self.set_assignee_by_query(query, assignee, &action).await?;
+        Ok(())
+    }
+
+    async fn set_assignee_by_query(
+        &self,
+        query: serde_json::Value,
+        assignee: Option<String>,
+        action: &HistoryEntry,
+    ) -> Result<u64, DatastoreError> {
+        let tag = assignee
+            .as_ref()
+            .map(|assignee| format!("{ASSIGNEE_TAG_PREFIX}{assignee}"));
+        self.set_field_by_query(
+            query,
+            ASSIGNEE_TAG_PREFIX,
+            tag,
+            "assignee",
+            json!(assignee),
+            action,
+        )
+        .await
+    }
+}
+
+/// A query matching events assigned to the given user, where `none`
+/// matches unassigned events.
+pub(super) fn assignee_query(assignee: &str) -> serde_json::Value {
+    if assignee == "none" {
+        json!({"bool": {"must_not": {"prefix": {"tags": ASSIGNEE_TAG_PREFIX}}}})
+    } else {
+        json!({"term": {"tags": format!("{ASSIGNEE_TAG_PREFIX}{assignee}")}})
+    }
+}
@@ -0,0 +1,115 @@
+// SPDX-FileCopyrightText: (C) 2024 Jason Ish <jason@codemonkey.net>
+// SPDX-License-Identifier: MIT
+
+use std::sync::Arc;
+
+use serde_json::json;
+use tracing::info;
+
+use super::{ElasticEventRepo, MINIMUM_SHOULD_MATCH};
+use crate::elastic::{
+    request, HistoryEntryBuilder, TAGS_ARCHIVED, TAGS_ESCALATED, TAG_ARCHIVED, TAG_ESCALATED,
+};
+use crate::eventrepo::{BulkAction, BulkActionParams, DatastoreError};
+use crate::server::session::Session;
+use crate::LOG_QUERIES;
+
+impl ElasticEventRepo {
+    pub async fn bulk_action(
+        &self,
+        params: &BulkActionParams,
+        action: &BulkAction,
+        session: Arc<Session>,
+    ) -> Result<u64, DatastoreError> {
+        let mut filters = vec![
+            request::exists_filter(&self.map_field("event_type")),
+            request::term_filter(&self.map_field("event_type"), "alert"),
+        ];
+        let mut should = vec![];
+        let mut must_not = vec![];
+
+        match action {
+            BulkAction::Archive => must_not.push(json!({"term": {"tags": TAG_ARCHIVED}})),
+            BulkAction::Unarchive => filters.push(json!({"term": {"tags": TAG_ARCHIVED}})),
+            BulkAction::Escalate => must_not.push(json!({"term": {"tags": TAG_ESCALATED}})),
+            BulkAction::Deescalate => filters.push(json!({"term": {"tags": TAG_ESCALATED}})),
+            BulkAction::Comment(_) => {}
+        }
+
+        self.apply_query_string(
+            &params.query_string,
+            &mut filters,
+            &mut should,
+            &mut must_not,
+        );
+
+        let mut query = json!({
+            "bool": {
+                "filter": filters,
+                "must_not": must_not,
+            }
+        });
+        if !should.is_empty() {
+            query["bool"]["should"] = should.into();
+            query["bool"][MINIMUM_SHOULD_MATCH] = 1.into();
+        }
+
+        if *LOG_QUERIES {
+            info!("{}", &query);
+        }
+
+        if params.dry_run {
+            let response: serde_json::Value = self
+                .post("_count", &json!({"query": query}))
+                .await?
+                .json()
+                .await?;
+            if response["error"].is_object() {
+                return Err(DatastoreError::ElasticSearchError(
+                    response["error"].to_string(),
+                ));
+            }
+            return Ok(response["count"].as_u64().unwrap_or_default());
+        }
+
+        let entry = action.history_entry(session.username.clone());
+        let n = match action {
+            BulkAction::Archive => {
+                self.add_tags_by_query(query, &TAGS_ARCHIVED, &entry)
+                    .await?
+            }
+            BulkAction::Unarchive => {
+                self.remove_tags_by_query(query, &TAGS_ARCHIVED, &entry)
+                    .await?
+            }
+            BulkAction::Escalate => {
+                self.add_tags_by_query(query, &TAGS_ESCALATED, &entry)
+                    .await?
+            }
+            BulkAction::Deescalate => {
+                self.remove_tags_by_query(query, &TAGS_ESCALATED, &entry)
+                    .await?
+            }
+            BulkAction::Comment(_) => self.add_tags_by_query(query, &[], &entry).await?,
+        };
+        info!(?action, "Bulk action updated {n} events");
+        Ok(n)
+    }
+
+    /// Archive the events with the given IDs in one update, returning
+    /// the number of events archived. Events already archived are not
+    /// updated.
+    pub async fn archive_events_by_id(&self, event_ids: &[String]) -> Result<u64, DatastoreError> {
+        if event_ids.is_empty() {
+            return Ok(0);
+        }
+        let query = json!({
+            "bool": {
+                "filter": {"terms": {"_id": event_ids}},
+                "must_not": {"term": {"tags": TAG_ARCHIVED}},
+            }
+        });
+        let action = HistoryEntryBuilder::new_archive().build();
+        self.add_tags_by_query(query, &TAGS_ARCHIVED, &action).await
+    }
+}
@@ -0,0 +1,92 @@
+// SPDX-FileCopyrightText: (C) 2024 Jason Ish <jason@codemonkey.net>
+// SPDX-License-Identifier: MIT
+
+use std::sync::Arc;
+
+use serde_json::json;
+
+use super::ElasticEventRepo;
+use crate::datetime::DateTime;
+use crate::elastic::{ElasticResponse, HistoryEntryBuilder};
+use crate::eventrepo::DatastoreError;
+use crate::server::api;
+use crate::server::session::Session;
+
+impl ElasticEventRepo {
+    pub async fn comment_by_alert_group(
+        &self,
+        alert_group: api::AlertGroupSpec,
+        comment: String,
+        session: Arc<Session>,
+    ) -> Result<(), DatastoreError> {
+        let query = json!({
+            "bool": {
+                "filter": self.build_alert_group_filter(&alert_group)?,
+            }
+        });
+        let action = HistoryEntryBuilder::new_comment()
+            .username(session.username.clone())
+            .comment(comment)
+            .build();
+        self.add_tags_by_query(query, &[], &action).await?;
+        Ok(())
+    }
+
+    /// Edit a comment, or delete it if `comment` is `None`. If an
+    /// owner is given, only comments made by that user are changed.
+    /// Returns the number of events updated.
+    pub async fn update_comment(
+        &self,
+        comment_id: &str,
+        comment: Option<String>,
+        owner: Option<&str>,
+    ) -> Result<u64, DatastoreError> {
+        let query = json!({
+            "bool": {
+                "filter": {
+                    "match_phrase": {"evebox.history.id": comment_id}
+                }
+            }
+        });
+        // Events where the comment is not matched, for example
+        // because it was made by another user, are left untouched.
+        let script = json!({
+            "lang": "painless",
+            "inline": "
+                boolean found = false;
+                if (ctx._source.evebox != null && ctx._source.evebox.history != null) {
+                    for (entry in ctx._source.evebox.history) {
+                        if (entry.id == params.id &&
+                                (params.owner == null || entry.username == params.owner)) {
+                            found = true;
+                            if (params.comment != null) {
+                                entry.comment = params.comment;
+                                entry.edited = params.edited;
+                            }
+                        }
+                    }
+                    if (found && params.comment == null) {
+                        ctx._source.evebox.history.removeIf(entry -> entry.id == params.id &&
+                            (params.owner == null || entry.username == params.owner));
+                    }
+                }
+                if (!found) {
+                    ctx.op = 'noop';
+                }
+            ",
+            "params": {
+                "id": comment_id,
+                "owner": owner,
+                "comment": comment,
+                "edited": DateTime::now().to_rfc3339_utc(),
+            },
+        });
+        let body = json!({
+            "query": query,
+            "script": script,
+        });
+        let path = "_update_by_query?refresh=true&conflicts=proceed";
+        let response: ElasticResponse = self.post(path, &body).await?.json().await?;
+        Ok(response.updated.unwrap_or_default())
+    }
+}
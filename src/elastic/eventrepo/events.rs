@@ -3,7 +3,9 @@
 
 use super::ElasticEventRepo;
 use crate::elastic::request;
-use crate::eventrepo::{self, DatastoreError};
+use crate::eventrepo::{
+    self, DatastoreError, EventCursor, EventExportParams, EventPage, MAX_EVENTS_SIZE,
+};
 use crate::LOG_QUERIES;
 use serde_json::json;
 use tracing::info;
@@ -11,6 +13,9 @@ use tracing::warn;
 
 const MINIMUM_SHOULD_MATCH: &str = "minimum_should_match";
 
+/// How long to keep a point in time open between pages.
+const PIT_KEEP_ALIVE: &str = "5m";
+
 impl ElasticEventRepo {
     pub async fn events(
         &self,
@@ -44,7 +49,7 @@ impl ElasticEventRepo {
 
         let sort_by = params.sort_by.unwrap_or_else(|| "@timestamp".to_string());
         let sort_order = params.order.unwrap_or_else(|| "desc".to_string());
-        let size = params.size.unwrap_or(500);
+        let size = params.size.unwrap_or(MAX_EVENTS_SIZE).max(1);
 
         let mut body = json!({
             "query": {
@@ -66,6 +71,25 @@ impl ElasticEventRepo {
             body["query"]["bool"][MINIMUM_SHOULD_MATCH] = 1.into();
         }
 
+        if params.paginate {
+            let sort_order = if sort_order.eq_ignore_ascii_case("asc") {
+                "asc"
+            } else {
+                "desc"
+            };
+            let page = self
+                .pit_search(body, sort_order, size, params.cursor)
+                .await?;
+            let mut response = json!({
+                "ecs": self.ecs,
+                "events": page.events,
+            });
+            if let Some(cursor) = page.cursor {
+                response["next_cursor"] = cursor.encode().into();
+            }
+            return Ok(response);
+        }
+
         if *LOG_QUERIES {
             info!("{}", &body);
         }
@@ -119,4 +143,164 @@ impl ElasticEventRepo {
 
         Ok(response)
     }
+
+    /// Fetch a page of events for export.
+    pub async fn export_page(
+        &self,
+        params: &EventExportParams,
+        cursor: Option<EventCursor>,
+    ) -> Result<EventPage, DatastoreError> {
+        let mut filters = vec![request::exists_filter(&self.map_field("event_type"))];
+        let mut should = vec![];
+        let mut must_not = vec![];
+
+        if let Some(event_type) = &params.event_type {
+            filters.push(request::term_filter(
+                &self.map_field("event_type"),
+                event_type,
+            ));
+        }
+
+        self.apply_query_string(
+            &params.query_string,
+            &mut filters,
+            &mut should,
+            &mut must_not,
+        );
+
+        let mut body = json!({
+            "query": {
+                "bool": {
+                    "filter": filters,
+                    "must_not": must_not,
+                }
+            },
+        });
+
+        if self.runtime_mappings_supported {
+            body["runtime_mappings"] = self.runtime_mappings();
+        }
+
+        if !should.is_empty() {
+            body["query"]["bool"]["should"] = should.into();
+            body["query"]["bool"][MINIMUM_SHOULD_MATCH] = 1.into();
+        }
+
+        self.pit_search(body, "asc", params.size, cursor).await
+    }
+
+    /// Run a search for a page of events sorted by timestamp.
+    ///
+    /// A point in time is opened on the first page and used with
+    /// search_after for the following pages so paging sees a
+    /// consistent view of the index, and `_shard_doc` can be used as a
+    /// tie-breaker for events with the same timestamp. The point in
+    /// time is closed once the last page has been fetched, otherwise
+    /// it will expire after `PIT_KEEP_ALIVE`.
+    async fn pit_search(
+        &self,
+        mut body: serde_json::Value,
+        order: &str,
+        size: u64,
+        cursor: Option<EventCursor>,
+    ) -> Result<EventPage, DatastoreError> {
+        let pit = match cursor.as_ref().and_then(|c| c.pit.clone()) {
+            Some(pit) => pit,
+            None => self.open_pit().await?,
+        };
+
+        body["pit"] = json!({
+            "id": pit,
+            "keep_alive": PIT_KEEP_ALIVE,
+        });
+        body["sort"] = json!([
+            {"@timestamp": {"order": order}},
+            {"_shard_doc": {"order": order}},
+        ]);
+        body["size"] = size.into();
+
+        if let Some(cursor) = &cursor {
+            body["search_after"] = json!([cursor.timestamp, cursor.id]);
+        }
+
+        if *LOG_QUERIES {
+            info!("{}", &body);
+        }
+
+        // Searches with a point in time must not specify an index.
+        let response: serde_json::Value = self
+            .client
+            .post("_search")?
+            .json(&body)
+            .send()
+            .await?
+            .json()
+            .await?;
+        if response["error"].is_object() {
+            return Err(DatastoreError::ElasticSearchError(
+                response["error"].to_string(),
+            ));
+        }
+
+        // The point in time ID may change between requests.
+        let pit = response["pit_id"].as_str().map(String::from).unwrap_or(pit);
+
+        let mut page = EventPage::default();
+        let mut last = None;
+        if let Some(hits) = response["hits"]["hits"].as_array() {
+            for hit in hits {
+                let mut hit = hit.clone();
+                last = match (hit["sort"][0].as_i64(), hit["sort"][1].as_i64()) {
+                    (Some(timestamp), Some(id)) => Some((timestamp, id)),
+                    _ => None,
+                };
+                if self.ecs {
+                    self.transform_ecs(&mut hit);
+                }
+                page.events.push(hit);
+            }
+        }
+
+        match last {
+            Some((timestamp, id)) if page.events.len() as u64 == size => {
+                page.cursor = Some(EventCursor {
+                    timestamp,
+                    id,
+                    pit: Some(pit),
+                });
+            }
+            _ => {
+                self.close_pit(&pit).await;
+            }
+        }
+
+        Ok(page)
+    }
+
+    async fn open_pit(&self) -> Result<String, DatastoreError> {
+        let path = format!("{}/_pit?keep_alive={}", self.index_pattern, PIT_KEEP_ALIVE);
+        let response: serde_json::Value = self.client.post(&path)?.send().await?.json().await?;
+        if let Some(id) = response["id"].as_str() {
+            Ok(id.to_string())
+        } else {
+            Err(DatastoreError::ElasticSearchError(format!(
+                "failed to open point in time: {}",
+                response
+            )))
+        }
+    }
+
+    async fn close_pit(&self, pit: &str) {
+        let body = json!({"id": pit});
+        match self.client.delete("_pit") {
+            Ok(request) => {
+                if let Err(err) = request.json(&body).send().await {
+                    warn!("Failed to close point in time: {:?}", err);
+                }
+            }
+            Err(err) => {
+                warn!("Failed to close point in time: {:?}", err);
+            }
+        }
+    }
 }
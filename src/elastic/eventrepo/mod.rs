@@ -26,12 +26,28 @@ use tracing::error;
 use tracing::warn;
 
 mod alerts;
+mod assign;
+mod bulk;
+mod comments;
 mod dhcp;
 mod events;
 mod stats;
+mod status;
+mod tags;
 
 const MINIMUM_SHOULD_MATCH: &str = "minimum_should_match";
 
+/// A query matching a single event by ID.
+fn id_query(event_id: &str) -> serde_json::Value {
+    json!({
+        "bool": {
+            "filter": {
+                "term": {"_id": event_id}
+            }
+        }
+    })
+}
+
 /// Elasticsearch eventstore - for searching events.
 #[derive(Debug, Clone)]
 pub(crate) struct ElasticEventRepo {
@@ -159,7 +175,8 @@ impl ElasticEventRepo {
         tag: &str,
         action: &HistoryEntry,
     ) -> Result<(), DatastoreError> {
-        self.add_tags_by_query(query, &[tag], action).await
+        self.add_tags_by_query(query, &[tag], action).await?;
+        Ok(())
     }
 
     async fn add_tags_by_query(
@@ -167,7 +184,7 @@ impl ElasticEventRepo {
         query: serde_json::Value,
         tags: &[&str],
         action: &HistoryEntry,
-    ) -> Result<(), DatastoreError> {
+    ) -> Result<u64, DatastoreError> {
         let script = json!({
             "lang": "painless",
             "inline": "
@@ -210,7 +227,7 @@ impl ElasticEventRepo {
             );
         }
 
-        Ok(())
+        Ok(updated)
     }
 
     async fn remove_tag_by_query(
@@ -219,7 +236,8 @@ impl ElasticEventRepo {
         tag: &str,
         action: &HistoryEntry,
     ) -> Result<(), DatastoreError> {
-        self.remove_tags_by_query(query, &[tag], action).await
+        self.remove_tags_by_query(query, &[tag], action).await?;
+        Ok(())
     }
 
     async fn remove_tags_by_query(
@@ -227,7 +245,7 @@ impl ElasticEventRepo {
         query: serde_json::Value,
         tags: &[&str],
         action: &HistoryEntry,
-    ) -> Result<(), DatastoreError> {
+    ) -> Result<u64, DatastoreError> {
         let script = json!({
             "lang": "painless",
             "inline": "
@@ -254,8 +272,8 @@ impl ElasticEventRepo {
             "script": script,
         });
         let path = "_update_by_query?refresh=true&conflicts=proceed";
-        let _reponse = self.post(path, &body).await?.bytes().await?;
-        Ok(())
+        let response: ElasticResponse = self.post(path, &body).await?.json().await?;
+        Ok(response.updated.unwrap_or_default())
     }
 
     async fn add_tags_by_alert_group(
@@ -271,12 +289,13 @@ impl ElasticEventRepo {
 
         let query = json!({
             "bool": {
-                "filter": self.build_alert_group_filter(&alert_group),
+                "filter": self.build_alert_group_filter(&alert_group)?,
                 "must_not": must_not,
             }
         });
 
-        self.add_tags_by_query(query, tags, action).await
+        self.add_tags_by_query(query, tags, action).await?;
+        Ok(())
     }
 
     async fn remove_tags_by_alert_group(
@@ -285,7 +304,7 @@ impl ElasticEventRepo {
         tags: &[&str],
         action: &HistoryEntry,
     ) -> Result<(), DatastoreError> {
-        let mut filters = self.build_alert_group_filter(&alert_group);
+        let mut filters = self.build_alert_group_filter(&alert_group)?;
         for tag in tags {
             filters.push(json!({"term": {"tags": tag}}));
         }
@@ -294,7 +313,8 @@ impl ElasticEventRepo {
                 "filter": filters,
             }
         });
-        self.remove_tags_by_query(query, tags, action).await
+        self.remove_tags_by_query(query, tags, action).await?;
+        Ok(())
     }
 
     pub async fn archive_event_by_id(&self, event_id: &str) -> Result<(), DatastoreError> {
@@ -309,6 +329,18 @@ impl ElasticEventRepo {
         self.add_tag_by_query(query, TAG_ARCHIVED, &action).await
     }
 
+    pub async fn unarchive_event_by_id(&self, event_id: &str) -> Result<(), DatastoreError> {
+        let query = json!({
+            "bool": {
+                "filter": {
+                    "term": {"_id": event_id}
+                }
+            }
+        });
+        let action = HistoryEntryBuilder::new_unarchive().build();
+        self.remove_tag_by_query(query, TAG_ARCHIVED, &action).await
+    }
+
     pub async fn escalate_event_by_id(&self, event_id: &str) -> Result<(), DatastoreError> {
         let query = json!({
             "bool": {
@@ -351,7 +383,8 @@ impl ElasticEventRepo {
             .username(session.username.clone())
             .comment(comment)
             .build();
-        self.add_tags_by_query(query, &[], &action).await
+        self.add_tags_by_query(query, &[], &action).await?;
+        Ok(())
     }
 
     pub async fn get_event_by_id(
@@ -401,7 +434,7 @@ impl ElasticEventRepo {
     ) {
         for el in q {
             match &el.value {
-                queryparser::QueryValue::String(v) => {
+                queryparser::QueryValue::String(v) | queryparser::QueryValue::Fts(v) => {
                     if el.negated {
                         must_not.push(query_string_query(v));
                     } else {
@@ -409,6 +442,27 @@ impl ElasticEventRepo {
                     }
                 }
                 queryparser::QueryValue::KeyValue(k, v) => match k.as_ref() {
+                    "tag" => {
+                        if el.negated {
+                            must_not.push(json!({"term": {"tags": v}}));
+                        } else {
+                            filter.push(json!({"term": {"tags": v}}));
+                        }
+                    }
+                    "assignee" => {
+                        if el.negated {
+                            must_not.push(assign::assignee_query(v));
+                        } else {
+                            filter.push(assign::assignee_query(v));
+                        }
+                    }
+                    "status" => {
+                        if el.negated {
+                            must_not.push(status::status_query(v));
+                        } else {
+                            filter.push(status::status_query(v));
+                        }
+                    }
                     "@mac" => {
                         filter.push(json!({
                             "multi_match": {
@@ -553,6 +607,15 @@ impl ElasticEventRepo {
             .await
     }
 
+    pub async fn unarchive_by_alert_group(
+        &self,
+        alert_group: api::AlertGroupSpec,
+    ) -> Result<(), DatastoreError> {
+        let action = HistoryEntryBuilder::new_unarchive().build();
+        self.remove_tags_by_alert_group(alert_group, &TAGS_ARCHIVED, &action)
+            .await
+    }
+
     pub async fn escalate_by_alert_group(
         &self,
         alert_group: api::AlertGroupSpec,
@@ -817,7 +880,10 @@ impl ElasticEventRepo {
         }
     }
 
-    fn build_alert_group_filter(&self, request: &api::AlertGroupSpec) -> Vec<serde_json::Value> {
+    fn build_alert_group_filter(
+        &self,
+        request: &api::AlertGroupSpec,
+    ) -> Result<Vec<serde_json::Value>, DatastoreError> {
         let mut filter = Vec::new();
         filter.push(json!({"exists": {"field": self.map_field("event_type")}}));
         filter.push(json!({"term": {self.map_field("event_type"): "alert"}}));
@@ -829,10 +895,10 @@ impl ElasticEventRepo {
                 }
             }
         }));
-        filter.push(json!({"term": {self.map_field("src_ip"): request.src_ip}}));
-        filter.push(json!({"term": {self.map_field("dest_ip"): request.dest_ip}}));
-        filter.push(json!({"term": {self.map_field("alert.signature_id"): request.signature_id}}));
-        filter
+        for (key, value) in request.key_values()? {
+            filter.push(json!({"term": {self.map_field(key.field()): value}}));
+        }
+        Ok(filter)
     }
 
     pub async fn get_sensors(&self) -> anyhow::Result<Vec<String>> {
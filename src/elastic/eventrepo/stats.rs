@@ -2,7 +2,11 @@
 // SPDX-License-Identifier: MIT
 
 use super::ElasticEventRepo;
-use crate::{datetime::DateTime, eventrepo::StatsAggQueryParams, util};
+use crate::{
+    datetime::DateTime,
+    eventrepo::{SensorStatsParams, StatsAggQueryParams, StatsCounters, StatsSample},
+    util,
+};
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
 
@@ -155,4 +159,87 @@ impl ElasticEventRepo {
         });
         Ok(response)
     }
+
+    /// Return the last stats event of each sensor per interval.
+    pub async fn stats_samples(&self, params: &SensorStatsParams) -> Result<Vec<StatsSample>> {
+        let start_time = params.start_time.to_rfc3339_utc();
+        let mut filters = vec![];
+        filters.push(json!({"term": {self.map_field("event_type"): "stats"}}));
+        filters.push(json!({"range": {"@timestamp": {"gte": start_time}}}));
+        if let Some(sensor_name) = &params.sensor_name {
+            filters.push(json!({"term": {self.map_field("host"): sensor_name}}));
+        }
+        let stats_field = self.map_field("stats");
+        let query = json!({
+          "query": {
+            "bool": {
+              "filter": filters,
+            }
+          },
+          "size": 0,
+          "aggs": {
+            "sensors": {
+              "terms": {
+                "field": self.map_field("host"),
+                "size": 1000,
+              },
+              "aggs": {
+                "histogram": {
+                  "date_histogram": {
+                    "field": "@timestamp",
+                    "fixed_interval": format!("{}s", params.interval.max(1)),
+                    "min_doc_count": 1,
+                  },
+                  "aggs": {
+                    "last": {
+                      "top_hits": {
+                        "size": 1,
+                        "sort": [{"@timestamp": {"order": "desc"}}],
+                        "_source": [&stats_field],
+                      }
+                    }
+                  }
+                }
+              }
+            }
+          }
+        });
+
+        let response = self.search(&query).await?;
+        if response.status() != 200 {
+            let error_text = response.text().await?;
+            anyhow::bail!(error_text);
+        }
+        let response: serde_json::Value = response.json().await?;
+
+        let mut samples = vec![];
+        let sensors = response["aggregations"]["sensors"]["buckets"]
+            .as_array()
+            .cloned()
+            .unwrap_or_default();
+        for sensor in sensors {
+            let Some(name) = sensor["key"].as_str() else {
+                continue;
+            };
+            let buckets = sensor["histogram"]["buckets"]
+                .as_array()
+                .cloned()
+                .unwrap_or_default();
+            for bucket in buckets {
+                let hit = &bucket["last"]["hits"]["hits"][0];
+                let Some(millis) = hit["sort"][0].as_i64() else {
+                    continue;
+                };
+                let stats = stats_field
+                    .split('.')
+                    .fold(&hit["_source"], |value, key| &value[key]);
+                samples.push(StatsSample {
+                    sensor: name.to_string(),
+                    timestamp: DateTime::from_nanos(millis * 1_000_000),
+                    counters: StatsCounters::from_stats(stats),
+                });
+            }
+        }
+        Ok(samples)
+    }
 }
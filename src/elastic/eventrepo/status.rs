@@ -0,0 +1,151 @@
+// SPDX-FileCopyrightText: (C) 2024 Jason Ish <jason@codemonkey.net>
+// SPDX-License-Identifier: MIT
+
+//! Triage status of events.
+//!
+//! The status is stored in `evebox.status` for display, and as a tag
+//! of the form `evebox.status.<status>` which is used for querying as
+//! tags are mapped as keywords. Events without a status tag are new.
+
+use std::sync::Arc;
+
+use serde_json::json;
+use tracing::warn;
+
+use super::{id_query, ElasticEventRepo};
+use crate::elastic::{ElasticResponse, HistoryEntry, HistoryEntryBuilder};
+use crate::eventrepo::{DatastoreError, TriageStatus};
+use crate::server::api;
+use crate::server::session::Session;
+
+const STATUS_TAG_PREFIX: &str = "evebox.status.";
+
+impl ElasticEventRepo {
+    pub async fn set_status_by_id(
+        &self,
+        event_id: &str,
+        status: TriageStatus,
+        reason: Option<String>,
+        session: Arc<Session>,
+    ) -> Result<(), DatastoreError> {
+        let action = HistoryEntryBuilder::new_status(status, reason)
+            .username(session.username.clone())
+            .build();
+        if self
+            .set_status_by_query(id_query(event_id), status, &action)
+            .await?
+            == 0
+        {
+            return Err(DatastoreError::EventNotFound);
+        }
+        Ok(())
+    }
+
+    pub async fn set_status_by_alert_group(
+        &self,
+        alert_group: api::AlertGroupSpec,
+        status: TriageStatus,
+        reason: Option<String>,
+        session: Arc<Session>,
+    ) -> Result<(), DatastoreError> {
+        let query = json!({
+            "bool": {
+                "filter": self.build_alert_group_filter(&alert_group)?,
+                "must_not": [status_query(status.as_str())],
+            }
+        });
+        let action = HistoryEntryBuilder::new_status(status, reason)
+            .username(session.username.clone())
+            .build();
+        self.set_status_by_query(query, status, &action).await?;
+        Ok(())
+    }
+
+    async fn set_status_by_query(
+        &self,
+        query: serde_json::Value,
+        status: TriageStatus,
+        action: &HistoryEntry,
+    ) -> Result<u64, DatastoreError> {
+        let tag = match status {
+            TriageStatus::New => None,
+            _ => Some(format!("{STATUS_TAG_PREFIX}{status}")),
+        };
+        self.set_field_by_query(
+            query,
+            STATUS_TAG_PREFIX,
+            tag,
+            "status",
+            json!(status),
+            action,
+        )
+        .await
+    }
+
+    /// Set `evebox.<field>` on the events matching `query`, removing it
+    /// if `value` is null, replace the tags starting with `prefix` with
+    /// `tag`, and record the action in the history. Returns the number
+    /// of events updated.
+    pub(super) async fn set_field_by_query(
+        &self,
+        query: serde_json::Value,
+        prefix: &str,
+        tag: Option<String>,
+        field: &str,
+        value: serde_json::Value,
+        action: &HistoryEntry,
+    ) -> Result<u64, DatastoreError> {
+        let script = json!({
+            "lang": "painless",
+            "inline": "
+                if (ctx._source.tags == null) {
+                    ctx._source.tags = new ArrayList();
+                }
+                ctx._source.tags.removeIf(entry -> entry.startsWith(params.prefix));
+                if (params.tag != null) {
+                    ctx._source.tags.add(params.tag);
+                }
+                if (ctx._source.evebox == null) {
+                    ctx._source.evebox = new HashMap();
+                }
+                if (params.value != null) {
+                    ctx._source.evebox[params.field] = params.value;
+                } else {
+                    ctx._source.evebox.remove(params.field);
+                }
+                if (ctx._source.evebox.history == null) {
+                    ctx._source.evebox.history = new ArrayList();
+                }
+                ctx._source.evebox.history.add(params.action);
+            ",
+            "params": {
+                "prefix": prefix,
+                "tag": tag,
+                "field": field,
+                "value": value,
+                "action": action,
+            },
+        });
+        let body = json!({
+            "query": query,
+            "script": script,
+        });
+
+        let path = "_update_by_query?refresh=true&conflicts=proceed";
+        let response: ElasticResponse = self.post(path, &body).await?.json().await?;
+        let updated = response.updated.unwrap_or_default();
+        if updated == 0 {
+            warn!(?response, "No events updated when setting {field}");
+        }
+        Ok(updated)
+    }
+}
+
+/// A query matching events with the given status.
+pub(super) fn status_query(status: &str) -> serde_json::Value {
+    if status == TriageStatus::New.as_str() {
+        json!({"bool": {"must_not": {"prefix": {"tags": STATUS_TAG_PREFIX}}}})
+    } else {
+        json!({"term": {"tags": format!("{STATUS_TAG_PREFIX}{status}")}})
+    }
+}
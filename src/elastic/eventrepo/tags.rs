@@ -0,0 +1,106 @@
+// SPDX-FileCopyrightText: (C) 2024 Jason Ish <jason@codemonkey.net>
+// SPDX-License-Identifier: MIT
+
+//! User tags on events, stored in the `tags` array alongside the
+//! `evebox.` tags.
+
+use std::sync::Arc;
+
+use serde_json::json;
+
+use super::{id_query, ElasticEventRepo, MINIMUM_SHOULD_MATCH};
+use crate::elastic::HistoryEntryBuilder;
+use crate::eventrepo::DatastoreError;
+use crate::server::api;
+use crate::server::session::Session;
+
+fn tag_terms(tags: &[&str]) -> Vec<serde_json::Value> {
+    tags.iter()
+        .map(|tag| json!({"term": {"tags": tag}}))
+        .collect()
+}
+
+impl ElasticEventRepo {
+    pub async fn add_user_tags_by_id(
+        &self,
+        event_id: &str,
+        tags: Vec<String>,
+        session: Arc<Session>,
+    ) -> Result<(), DatastoreError> {
+        let action = HistoryEntryBuilder::new_add_tags(tags.clone())
+            .username(session.username.clone())
+            .build();
+        let tags: Vec<&str> = tags.iter().map(String::as_str).collect();
+        if self
+            .add_tags_by_query(id_query(event_id), &tags, &action)
+            .await?
+            == 0
+        {
+            return Err(DatastoreError::EventNotFound);
+        }
+        Ok(())
+    }
+
+    pub async fn remove_user_tags_by_id(
+        &self,
+        event_id: &str,
+        tags: Vec<String>,
+        session: Arc<Session>,
+    ) -> Result<(), DatastoreError> {
+        let action = HistoryEntryBuilder::new_remove_tags(tags.clone())
+            .username(session.username.clone())
+            .build();
+        let tags: Vec<&str> = tags.iter().map(String::as_str).collect();
+        if self
+            .remove_tags_by_query(id_query(event_id), &tags, &action)
+            .await?
+            == 0
+        {
+            return Err(DatastoreError::EventNotFound);
+        }
+        Ok(())
+    }
+
+    pub async fn add_user_tags_by_alert_group(
+        &self,
+        alert_group: api::AlertGroupSpec,
+        tags: Vec<String>,
+        session: Arc<Session>,
+    ) -> Result<(), DatastoreError> {
+        let action = HistoryEntryBuilder::new_add_tags(tags.clone())
+            .username(session.username.clone())
+            .build();
+        let tags: Vec<&str> = tags.iter().map(String::as_str).collect();
+        // Skip events that already have all the tags.
+        let query = json!({
+            "bool": {
+                "filter": self.build_alert_group_filter(&alert_group)?,
+                "must_not": [{"bool": {"filter": tag_terms(&tags)}}],
+            }
+        });
+        self.add_tags_by_query(query, &tags, &action).await?;
+        Ok(())
+    }
+
+    pub async fn remove_user_tags_by_alert_group(
+        &self,
+        alert_group: api::AlertGroupSpec,
+        tags: Vec<String>,
+        session: Arc<Session>,
+    ) -> Result<(), DatastoreError> {
+        let action = HistoryEntryBuilder::new_remove_tags(tags.clone())
+            .username(session.username.clone())
+            .build();
+        let tags: Vec<&str> = tags.iter().map(String::as_str).collect();
+        // Only update events that have at least one of the tags.
+        let query = json!({
+            "bool": {
+                "filter": self.build_alert_group_filter(&alert_group)?,
+                "should": tag_terms(&tags),
+                MINIMUM_SHOULD_MATCH: 1,
+            }
+        });
+        self.remove_tags_by_query(query, &tags, &action).await?;
+        Ok(())
+    }
+}
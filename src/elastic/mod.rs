@@ -6,7 +6,7 @@ use serde_json::json;
 use thiserror::Error;
 
 use crate::datetime::DateTime;
-use crate::eventrepo::DatastoreError;
+use crate::eventrepo::{AlertGroupKey, DatastoreError, TriageStatus};
 
 pub(crate) use client::Version;
 pub(crate) use client::{Client, ClientBuilder};
@@ -26,18 +26,30 @@ pub(crate) const TAGS_ARCHIVED: [&str; 1] = [TAG_ARCHIVED];
 
 pub(crate) enum HistoryType {
     Archived,
+    Unarchived,
     Escalated,
     Deescalated,
     Comment,
+    StatusChanged,
+    Assigned,
+    Unassigned,
+    TagsAdded,
+    TagsRemoved,
 }
 
 impl std::fmt::Display for HistoryType {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         match self {
             HistoryType::Archived => write!(f, "archived"),
+            HistoryType::Unarchived => write!(f, "un-archived"),
             HistoryType::Escalated => write!(f, "escalated"),
             HistoryType::Deescalated => write!(f, "de-escalated"),
             HistoryType::Comment => write!(f, "comment"),
+            HistoryType::StatusChanged => write!(f, "status"),
+            HistoryType::Assigned => write!(f, "assigned"),
+            HistoryType::Unassigned => write!(f, "unassigned"),
+            HistoryType::TagsAdded => write!(f, "tags-added"),
+            HistoryType::TagsRemoved => write!(f, "tags-removed"),
         }
     }
 }
@@ -59,19 +71,44 @@ impl From<reqwest::Error> for DatastoreError {
 #[derive(Default, Debug, Clone)]
 pub(crate) struct AlertQueryOptions {
     pub timestamp_gte: Option<DateTime>,
+    pub timestamp_lte: Option<DateTime>,
     pub query_string: Option<String>,
     pub tags: Vec<String>,
     pub sensor: Option<String>,
+    /// Keys to group alerts on, if empty the default grouping is used.
+    pub group_by: Vec<AlertGroupKey>,
+    /// The requesting user, used to resolve `assignee:me`.
+    pub username: Option<String>,
+}
+
+impl AlertQueryOptions {
+    pub fn group_keys(&self) -> &[AlertGroupKey] {
+        if self.group_by.is_empty() {
+            &AlertGroupKey::DEFAULT
+        } else {
+            &self.group_by
+        }
+    }
 }
 
 #[derive(Serialize)]
 pub(crate) struct HistoryEntry {
+    /// Unique ID of comment entries, used to edit or delete the
+    /// comment. Shared by all events a comment was added to at once.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub username: Option<String>,
     pub timestamp: String,
     pub action: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub comment: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub status: Option<TriageStatus>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub assignee: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tags: Option<Vec<String>>,
 }
 
 impl HistoryEntry {
@@ -81,19 +118,27 @@ impl HistoryEntry {
 }
 
 pub(crate) struct HistoryEntryBuilder {
+    id: Option<String>,
     timestamp: DateTime,
     action: String,
     username: Option<String>,
     comment: Option<String>,
+    status: Option<TriageStatus>,
+    assignee: Option<String>,
+    tags: Option<Vec<String>>,
 }
 
 impl HistoryEntryBuilder {
     fn new(action: HistoryType) -> Self {
         Self {
+            id: None,
             action: action.to_string(),
             timestamp: DateTime::now(),
             username: None,
             comment: None,
+            status: None,
+            assignee: None,
+            tags: None,
         }
     }
 
@@ -101,6 +146,10 @@ impl HistoryEntryBuilder {
         Self::new(HistoryType::Archived)
     }
 
+    pub fn new_unarchive() -> Self {
+        Self::new(HistoryType::Unarchived)
+    }
+
     pub fn new_escalate() -> Self {
         Self::new(HistoryType::Escalated)
     }
@@ -110,7 +159,43 @@ impl HistoryEntryBuilder {
     }
 
     pub fn new_comment() -> Self {
-        Self::new(HistoryType::Comment)
+        let mut builder = Self::new(HistoryType::Comment);
+        builder.id = Some(uuid::Uuid::new_v4().to_string());
+        builder
+    }
+
+    /// A status change, the reason for the change is recorded as the
+    /// comment.
+    pub fn new_status(status: TriageStatus, reason: Option<String>) -> Self {
+        let mut builder = Self::new(HistoryType::StatusChanged);
+        builder.status = Some(status);
+        builder.comment = reason;
+        builder
+    }
+
+    /// An assignment to a user, or an unassignment if the assignee is
+    /// `None`.
+    pub fn new_assign(assignee: Option<String>) -> Self {
+        let mut builder = match assignee {
+            Some(_) => Self::new(HistoryType::Assigned),
+            None => Self::new(HistoryType::Unassigned),
+        };
+        builder.assignee = assignee;
+        builder
+    }
+
+    /// User tags added to an event.
+    pub fn new_add_tags(tags: Vec<String>) -> Self {
+        let mut builder = Self::new(HistoryType::TagsAdded);
+        builder.tags = Some(tags);
+        builder
+    }
+
+    /// User tags removed from an event.
+    pub fn new_remove_tags(tags: Vec<String>) -> Self {
+        let mut builder = Self::new(HistoryType::TagsRemoved);
+        builder.tags = Some(tags);
+        builder
     }
 
     pub fn username(mut self, username: Option<impl Into<String>>) -> Self {
@@ -125,10 +210,14 @@ impl HistoryEntryBuilder {
 
     pub fn build(self) -> HistoryEntry {
         HistoryEntry {
+            id: self.id,
             username: self.username,
             timestamp: self.timestamp.to_rfc3339_utc(),
             action: self.action,
             comment: self.comment,
+            status: self.status,
+            assignee: self.assignee,
+            tags: self.tags,
         }
     }
 }
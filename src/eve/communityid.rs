@@ -0,0 +1,206 @@
+// SPDX-FileCopyrightText: (C) 2024 Jason Ish <jason@codemonkey.net>
+// SPDX-License-Identifier: MIT
+
+//! Community ID v1 flow hashing.
+//!
+//! See https://github.com/corelight/community-id-spec.
+
+use base64::prelude::*;
+use sha1::{Digest, Sha1};
+use std::net::IpAddr;
+
+const PROTO_ICMP: u8 = 1;
+const PROTO_TCP: u8 = 6;
+const PROTO_UDP: u8 = 17;
+const PROTO_ICMPV6: u8 = 58;
+const PROTO_SCTP: u8 = 132;
+
+/// Compute the Community ID v1 for a flow.
+///
+/// For ICMP and ICMPv6 the source and destination port are the ICMP
+/// type and code.
+pub(crate) fn community_id_v1(
+    seed: u16,
+    proto: u8,
+    src_ip: IpAddr,
+    dest_ip: IpAddr,
+    src_port: u16,
+    dest_port: u16,
+) -> String {
+    let (src_port, dest_port, one_way) = match proto {
+        PROTO_ICMP => icmp_ports(src_port, dest_port, icmp_equivalent),
+        PROTO_ICMPV6 => icmp_ports(src_port, dest_port, icmp6_equivalent),
+        _ => (src_port, dest_port, false),
+    };
+
+    let ordered = one_way
+        || ip_bytes(&src_ip) < ip_bytes(&dest_ip)
+        || (src_ip == dest_ip && src_port < dest_port);
+    let (src_ip, dest_ip, src_port, dest_port) = if ordered {
+        (src_ip, dest_ip, src_port, dest_port)
+    } else {
+        (dest_ip, src_ip, dest_port, src_port)
+    };
+
+    let mut hasher = Sha1::new();
+    hasher.update(seed.to_be_bytes());
+    hasher.update(ip_bytes(&src_ip));
+    hasher.update(ip_bytes(&dest_ip));
+    hasher.update([proto, 0]);
+    if matches!(
+        proto,
+        PROTO_ICMP | PROTO_TCP | PROTO_UDP | PROTO_ICMPV6 | PROTO_SCTP
+    ) {
+        hasher.update(src_port.to_be_bytes());
+        hasher.update(dest_port.to_be_bytes());
+    }
+
+    format!("1:{}", BASE64_STANDARD.encode(hasher.finalize()))
+}
+
+/// Compute the Community ID for an EVE event. Returns `None` if the
+/// event doesn't have the required fields.
+pub(crate) fn community_id_for_eve(seed: u16, event: &serde_json::Value) -> Option<String> {
+    let proto = parse_proto(event["proto"].as_str()?)?;
+    let src_ip: IpAddr = event["src_ip"].as_str()?.parse().ok()?;
+    let dest_ip: IpAddr = event["dest_ip"].as_str()?.parse().ok()?;
+    let (src_port, dest_port) = match proto {
+        PROTO_ICMP | PROTO_ICMPV6 => (
+            event["icmp_type"].as_u64()? as u16,
+            event["icmp_code"].as_u64()? as u16,
+        ),
+        PROTO_TCP | PROTO_UDP | PROTO_SCTP => (
+            event["src_port"].as_u64()? as u16,
+            event["dest_port"].as_u64()? as u16,
+        ),
+        _ => (0, 0),
+    };
+    Some(community_id_v1(
+        seed, proto, src_ip, dest_ip, src_port, dest_port,
+    ))
+}
+
+/// Parse the protocol as logged by Suricata, either a name or a
+/// number.
+fn parse_proto(proto: &str) -> Option<u8> {
+    match proto.to_ascii_uppercase().as_str() {
+        "ICMP" => Some(PROTO_ICMP),
+        "TCP" => Some(PROTO_TCP),
+        "UDP" => Some(PROTO_UDP),
+        "IPV6-ICMP" | "ICMPV6" => Some(PROTO_ICMPV6),
+        "SCTP" => Some(PROTO_SCTP),
+        other => other.parse().ok(),
+    }
+}
+
+fn ip_bytes(addr: &IpAddr) -> Vec<u8> {
+    match addr {
+        IpAddr::V4(addr) => addr.octets().to_vec(),
+        IpAddr::V6(addr) => addr.octets().to_vec(),
+    }
+}
+
+/// Map an ICMP type and code to ports. Types with a request/response
+/// counterpart use the counterpart type as the destination port,
+/// otherwise the flow is considered one-way and the code is used.
+fn icmp_ports(icmp_type: u16, code: u16, equivalent: fn(u16) -> Option<u16>) -> (u16, u16, bool) {
+    match equivalent(icmp_type) {
+        Some(other) => (icmp_type, other, false),
+        None => (icmp_type, code, true),
+    }
+}
+
+fn icmp_equivalent(icmp_type: u16) -> Option<u16> {
+    match icmp_type {
+        // Echo request/reply.
+        8 => Some(0),
+        0 => Some(8),
+        // Router solicitation/advertisement.
+        10 => Some(9),
+        9 => Some(10),
+        // Timestamp request/reply.
+        13 => Some(14),
+        14 => Some(13),
+        // Information request/reply.
+        15 => Some(16),
+        16 => Some(15),
+        // Address mask request/reply.
+        17 => Some(18),
+        18 => Some(17),
+        _ => None,
+    }
+}
+
+fn icmp6_equivalent(icmp_type: u16) -> Option<u16> {
+    match icmp_type {
+        // Echo request/reply.
+        128 => Some(129),
+        129 => Some(128),
+        // Multicast listener query/report.
+        130 => Some(131),
+        131 => Some(130),
+        // Router solicitation/advertisement.
+        133 => Some(134),
+        134 => Some(133),
+        // Neighbor solicitation/advertisement.
+        135 => Some(136),
+        136 => Some(135),
+        // Node information query/response.
+        139 => Some(140),
+        140 => Some(139),
+        // Home agent address discovery request/reply.
+        144 => Some(145),
+        145 => Some(144),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_community_id_v1() {
+        let id = community_id_v1(
+            0,
+            PROTO_TCP,
+            "128.232.110.120".parse().unwrap(),
+            "66.35.250.204".parse().unwrap(),
+            34855,
+            80,
+        );
+        assert_eq!(id, "1:LQU9qZlK+B5F3KDmev6m5PMibrg=");
+
+        // Same flow in the other direction.
+        let id = community_id_v1(
+            0,
+            PROTO_TCP,
+            "66.35.250.204".parse().unwrap(),
+            "128.232.110.120".parse().unwrap(),
+            80,
+            34855,
+        );
+        assert_eq!(id, "1:LQU9qZlK+B5F3KDmev6m5PMibrg=");
+    }
+
+    #[test]
+    fn test_community_id_icmp() {
+        let request = json!({
+            "src_ip": "192.168.0.89",
+            "dest_ip": "192.168.0.1",
+            "proto": "ICMP",
+            "icmp_type": 8,
+            "icmp_code": 0,
+        });
+        let reply = json!({
+            "src_ip": "192.168.0.1",
+            "dest_ip": "192.168.0.89",
+            "proto": "ICMP",
+            "icmp_type": 0,
+            "icmp_code": 0,
+        });
+        let id = community_id_for_eve(0, &request).unwrap();
+        assert_eq!(id, "1:X0snYXpgwiv9TZtqg64sgzUn6Dk=");
+        assert_eq!(community_id_for_eve(0, &reply).unwrap(), id);
+    }
+}
@@ -1,9 +1,11 @@
 // SPDX-FileCopyrightText: (C) 2020 Jason Ish <jason@codemonkey.net>
 // SPDX-License-Identifier: MIT
 
+use crate::datetime::DateTime;
 use crate::rules::RuleMap;
+use crate::sqlite::configrepo::suppressions::Suppression;
 use serde_json::json;
-use std::sync::Arc;
+use std::sync::{Arc, RwLock};
 use tracing::{trace, warn};
 
 #[derive(Clone)]
@@ -12,8 +14,13 @@ pub(crate) enum EveFilter {
     EveBoxMetadataFilter(EveBoxMetadataFilter),
     CustomFieldFilter(CustomFieldFilter),
     AddRuleFilter(AddRuleFilter),
+    RuleMetadataFilter(RuleMetadataFilter),
     AutoArchiveFilter(AutoArchiveFilter),
     AddFieldFilter(AddFieldFilter),
+    CommunityIdFilter(CommunityIdFilter),
+    SuppressionFilter(SuppressionFilter),
+    Notifier(crate::notifications::Notifier),
+    SensorTracker(crate::sensors::SensorTracker),
 }
 
 impl EveFilter {
@@ -34,9 +41,24 @@ impl EveFilter {
             EveFilter::AddRuleFilter(filter) => {
                 filter.run(event);
             }
+            EveFilter::RuleMetadataFilter(filter) => {
+                filter.run(event);
+            }
             EveFilter::AutoArchiveFilter(filter) => {
                 filter.run(event);
             }
+            EveFilter::CommunityIdFilter(filter) => {
+                filter.run(event);
+            }
+            EveFilter::SuppressionFilter(filter) => {
+                filter.run(event);
+            }
+            EveFilter::Notifier(notifier) => {
+                notifier.run(event);
+            }
+            EveFilter::SensorTracker(tracker) => {
+                tracker.run(event);
+            }
         }
     }
 }
@@ -135,6 +157,26 @@ impl AddRuleFilter {
     }
 }
 
+/// Adds the metadata, classtype and references of the rule to alerts,
+/// so they can be queried even if the rule didn't log them.
+#[derive(Clone)]
+pub(crate) struct RuleMetadataFilter {
+    pub map: Arc<RuleMap>,
+}
+
+impl RuleMetadataFilter {
+    pub fn run(&self, event: &mut serde_json::Value) {
+        if event["event_type"].as_str() != Some("alert") {
+            return;
+        }
+        if let Some(sid) = event["alert"]["signature_id"].as_u64() {
+            if !self.map.enrich(sid, event) {
+                trace!("Failed to find rule for SID {}", sid);
+            }
+        }
+    }
+}
+
 #[derive(Default, Clone, Debug)]
 pub(crate) struct AutoArchiveFilter {}
 
@@ -169,3 +211,82 @@ impl AutoArchiveFilter {
         }
     }
 }
+
+/// Add a Community ID to events that don't already have one.
+#[derive(Default, Clone, Debug)]
+pub(crate) struct CommunityIdFilter {
+    pub seed: u16,
+}
+
+impl CommunityIdFilter {
+    pub fn new(seed: u16) -> Self {
+        Self { seed }
+    }
+
+    pub fn run(&self, event: &mut serde_json::Value) {
+        if !event["community_id"].is_null() {
+            return;
+        }
+        if let Some(community_id) = super::communityid::community_id_for_eve(self.seed, event) {
+            event["community_id"] = community_id.into();
+        }
+    }
+}
+
+impl From<CommunityIdFilter> for EveFilter {
+    fn from(filter: CommunityIdFilter) -> Self {
+        EveFilter::CommunityIdFilter(filter)
+    }
+}
+
+/// Archive alerts matching an analyst defined suppression.
+///
+/// The suppressions are shared between clones of the filter so they
+/// can be replaced when changed through the API.
+#[derive(Default, Clone)]
+pub(crate) struct SuppressionFilter {
+    suppressions: Arc<RwLock<Vec<Suppression>>>,
+}
+
+impl SuppressionFilter {
+    pub fn set(&self, suppressions: Vec<Suppression>) {
+        *self.suppressions.write().unwrap() = suppressions;
+    }
+
+    pub fn run(&self, event: &mut serde_json::Value) {
+        if event["event_type"].as_str() != Some("alert") {
+            return;
+        }
+        let suppressions = self.suppressions.read().unwrap();
+        if suppressions.is_empty() {
+            return;
+        }
+        let now = DateTime::now();
+        if !suppressions
+            .iter()
+            .any(|s| !s.is_expired(&now) && s.matches(event))
+        {
+            return;
+        }
+        drop(suppressions);
+
+        if event["tags"].is_null() {
+            event["tags"] = json!([]);
+        }
+        if let serde_json::Value::Array(tags) = &mut event["tags"] {
+            for tag in ["evebox.archived", "evebox.suppressed"] {
+                if !tags.iter().any(|t| t == tag) {
+                    tags.push(tag.into());
+                }
+            }
+        } else {
+            warn!("Unable to suppress event, event has incompatible tags entry");
+        }
+    }
+}
+
+impl From<SuppressionFilter> for EveFilter {
+    fn from(filter: SuppressionFilter) -> Self {
+        EveFilter::SuppressionFilter(filter)
+    }
+}
@@ -3,6 +3,7 @@
 
 #[allow(clippy::module_inception)]
 pub(crate) mod eve;
+pub(crate) mod communityid;
 pub(crate) mod filters;
 pub(crate) mod processor;
 pub(crate) mod reader;
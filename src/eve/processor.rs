@@ -12,6 +12,7 @@ use crate::bookmark;
 use crate::eve::filters::EveFilter;
 use crate::eve::reader::EveReader;
 use crate::importer::EventSink;
+use crate::metrics::METRICS;
 use std::path::PathBuf;
 use std::sync::Arc;
 use std::time::Duration;
@@ -113,6 +114,7 @@ impl Processor {
                 }
                 Ok(None) => {
                     eofs += 1;
+                    self.update_lag();
                     if self.importer.pending() > 0 {
                         self.commit().await;
                         commits += 1;
@@ -165,6 +167,7 @@ impl Processor {
             match self.importer.commit().await {
                 Ok(_n) => {
                     self.write_bookmark();
+                    self.update_lag();
                     break;
                 }
                 Err(err) => {
@@ -175,6 +178,12 @@ impl Processor {
         }
     }
 
+    /// Record how far the reader is behind the end of the file.
+    fn update_lag(&mut self) {
+        let lag = self.reader.file_size().saturating_sub(self.reader.offset());
+        METRICS.reader_lag(&self.reader.filename.display().to_string(), lag);
+    }
+
     fn write_bookmark(&mut self) {
         if let Some(bookmark_filename) = &self.bookmark_filename {
             if let Some(meta) = self.reader.metadata() {
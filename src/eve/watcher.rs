@@ -5,9 +5,43 @@ use tracing::{info, warn};
 
 use super::{filters::EveFilter, EveReader, Processor};
 use crate::{eve::filters::EveBoxMetadataFilter, importer::EventSink};
-use std::time::Duration;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
 use std::{collections::HashSet, path::PathBuf, sync::Arc};
 
+/// How often the watcher checks for new files.
+pub(crate) const CHECK_INTERVAL: Duration = Duration::from_secs(15);
+
+/// The state of a watcher, shared so it can be reported on, for
+/// example by the readiness check.
+#[derive(Clone, Default)]
+pub(crate) struct WatcherStatus {
+    inner: Arc<Mutex<WatcherStatusInner>>,
+}
+
+#[derive(Default)]
+struct WatcherStatusInner {
+    files: Vec<String>,
+    last_check: Option<Instant>,
+}
+
+impl WatcherStatus {
+    /// Files being read.
+    pub fn files(&self) -> Vec<String> {
+        self.inner.lock().unwrap().files.clone()
+    }
+
+    /// Time since the watcher last checked for files, None if it
+    /// hasn't run yet.
+    pub fn since_last_check(&self) -> Option<Duration> {
+        self.inner
+            .lock()
+            .unwrap()
+            .last_check
+            .map(|last_check| last_check.elapsed())
+    }
+}
+
 /// Watches a collection of filename patterns and starts a new EVE
 /// pipeline when a new file is found.
 pub(crate) struct EvePatternWatcher {
@@ -18,6 +52,7 @@ pub(crate) struct EvePatternWatcher {
     end: bool,
     bookmark_directory: Option<String>,
     data_directory: Option<String>,
+    status: WatcherStatus,
 }
 
 impl EvePatternWatcher {
@@ -37,9 +72,14 @@ impl EvePatternWatcher {
             end,
             bookmark_directory,
             data_directory,
+            status: WatcherStatus::default(),
         }
     }
 
+    pub fn status(&self) -> WatcherStatus {
+        self.status.clone()
+    }
+
     pub fn check(&mut self) {
         for pattern in &self.patterns {
             // This is for error reporting to the user, in the case
@@ -59,11 +99,18 @@ impl EvePatternWatcher {
                     if !self.filenames.contains(&path) {
                         info!("Found EVE input file {}", path.display());
                         self.start_file(&path);
+                        self.status
+                            .inner
+                            .lock()
+                            .unwrap()
+                            .files
+                            .push(path.display().to_string());
                         self.filenames.insert(path);
                     }
                 }
             }
         }
+        self.status.inner.lock().unwrap().last_check = Some(Instant::now());
     }
 
     fn start_file(&self, filename: &PathBuf) {
@@ -105,7 +152,7 @@ impl EvePatternWatcher {
         tokio::spawn(async move {
             loop {
                 self.check();
-                tokio::time::sleep(std::time::Duration::from_secs(15)).await;
+                tokio::time::sleep(CHECK_INTERVAL).await;
             }
         });
     }
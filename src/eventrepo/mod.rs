@@ -7,21 +7,148 @@ use crate::server::api;
 use crate::server::session::Session;
 use crate::sqlite::eventrepo::SqliteEventRepo;
 use crate::{elastic, queryparser};
-use serde::Serialize;
+use base64::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
 use std::sync::Arc;
 use thiserror::Error;
 
 mod stats;
 
+pub(crate) use stats::{SensorStatsParams, StatsCounters, StatsSample};
+
+/// The default number of events returned by an events query. Larger
+/// sizes requested through the API are reduced to it.
+pub(crate) const MAX_EVENTS_SIZE: u64 = 500;
+
 #[derive(Default, Debug)]
 pub(crate) struct EventQueryParams {
     pub order: Option<String>,
     pub min_timestamp: Option<DateTime>,
     pub max_timestamp: Option<DateTime>,
     pub event_type: Option<String>,
+    /// Defaults to `MAX_EVENTS_SIZE`, a size of 0 is treated as 1.
     pub size: Option<u64>,
     pub sort_by: Option<String>,
     pub query_string: Vec<queryparser::QueryElement>,
+    /// Return a cursor to the next page of events. Events are then
+    /// sorted by timestamp with a tie-breaker so no events are skipped
+    /// or repeated between pages.
+    pub paginate: bool,
+    /// Continue from the position of a previous page.
+    pub cursor: Option<EventCursor>,
+}
+
+/// Parameters for exporting all events matching a query.
+#[derive(Default, Debug, Clone)]
+pub(crate) struct EventExportParams {
+    pub event_type: Option<String>,
+    pub query_string: Vec<queryparser::QueryElement>,
+    /// The number of events to fetch per page.
+    pub size: u64,
+}
+
+/// The triage status of an event.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub(crate) enum TriageStatus {
+    New,
+    InProgress,
+    TruePositive,
+    FalsePositive,
+    Benign,
+    Closed,
+}
+
+impl TriageStatus {
+    pub(crate) fn as_str(&self) -> &'static str {
+        match self {
+            TriageStatus::New => "new",
+            TriageStatus::InProgress => "in-progress",
+            TriageStatus::TruePositive => "true-positive",
+            TriageStatus::FalsePositive => "false-positive",
+            TriageStatus::Benign => "benign",
+            TriageStatus::Closed => "closed",
+        }
+    }
+}
+
+impl std::fmt::Display for TriageStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+/// An action to apply to all alerts matching a query.
+#[derive(Debug, Clone)]
+pub(crate) enum BulkAction {
+    Archive,
+    Unarchive,
+    Escalate,
+    Deescalate,
+    Comment(String),
+}
+
+impl BulkAction {
+    /// The history entry to record on each event the action is
+    /// applied to.
+    pub(crate) fn history_entry(&self, username: Option<String>) -> elastic::HistoryEntry {
+        let builder = match self {
+            BulkAction::Archive => elastic::HistoryEntryBuilder::new_archive(),
+            BulkAction::Unarchive => elastic::HistoryEntryBuilder::new_unarchive(),
+            BulkAction::Escalate => elastic::HistoryEntryBuilder::new_escalate(),
+            BulkAction::Deescalate => elastic::HistoryEntryBuilder::new_deescalate(),
+            BulkAction::Comment(comment) => {
+                elastic::HistoryEntryBuilder::new_comment().comment(comment)
+            }
+        };
+        builder.username(username).build()
+    }
+}
+
+/// Parameters for a bulk action on alerts.
+#[derive(Default, Debug, Clone)]
+pub(crate) struct BulkActionParams {
+    pub query_string: Vec<queryparser::QueryElement>,
+    /// Only count the alerts the action would be applied to.
+    pub dry_run: bool,
+}
+
+/// The position of the last event returned in a page of events,
+/// used to fetch the next page.
+///
+/// For SQLite this is the timestamp in nanoseconds and the rowid. For
+/// Elasticsearch it is the sort values of the last hit along with the
+/// point in time ID the query is running against.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct EventCursor {
+    #[serde(rename = "t")]
+    pub timestamp: i64,
+    #[serde(rename = "i")]
+    pub id: i64,
+    #[serde(rename = "p", skip_serializing_if = "Option::is_none")]
+    pub pit: Option<String>,
+}
+
+impl EventCursor {
+    /// Encode the cursor as an opaque string for API clients.
+    pub fn encode(&self) -> String {
+        let json = serde_json::to_vec(self).unwrap();
+        BASE64_URL_SAFE_NO_PAD.encode(json)
+    }
+
+    pub fn decode(input: &str) -> anyhow::Result<Self> {
+        let json = BASE64_URL_SAFE_NO_PAD.decode(input)?;
+        Ok(serde_json::from_slice(&json)?)
+    }
+}
+
+/// A page of events. The cursor will be `None` if there are no more
+/// events.
+#[derive(Debug, Default)]
+pub(crate) struct EventPage {
+    pub events: Vec<serde_json::Value>,
+    pub cursor: Option<EventCursor>,
 }
 
 pub enum EventRepo {
@@ -68,6 +195,10 @@ pub(crate) struct AlertsResult {
     pub(crate) events: Vec<AggAlert>,
     pub(crate) took: u64,
     pub(crate) timed_out: bool,
+    /// Set if there were more alert groups than returned.
+    pub(crate) truncated: bool,
+    /// The keys the alerts were grouped on.
+    pub(crate) group_by: Vec<AlertGroupKey>,
 }
 
 #[derive(Debug, Serialize)]
@@ -89,6 +220,70 @@ pub(crate) struct AggAlertMetadata {
     pub(crate) max_timestamp: DateTime,
 }
 
+/// A field alerts can be grouped on in the inbox.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum AlertGroupKey {
+    SignatureId,
+    SrcIp,
+    DestIp,
+}
+
+impl AlertGroupKey {
+    /// The default grouping of signature, source and destination.
+    pub(crate) const DEFAULT: [AlertGroupKey; 3] = [
+        AlertGroupKey::SignatureId,
+        AlertGroupKey::SrcIp,
+        AlertGroupKey::DestIp,
+    ];
+
+    /// The name of the key as used in the API.
+    pub(crate) fn as_str(&self) -> &'static str {
+        match self {
+            AlertGroupKey::SignatureId => "signature_id",
+            AlertGroupKey::SrcIp => "src_ip",
+            AlertGroupKey::DestIp => "dest_ip",
+        }
+    }
+
+    /// The EVE field the key groups on.
+    pub(crate) fn field(&self) -> &'static str {
+        match self {
+            AlertGroupKey::SignatureId => "alert.signature_id",
+            AlertGroupKey::SrcIp => "src_ip",
+            AlertGroupKey::DestIp => "dest_ip",
+        }
+    }
+
+    /// Parse a comma separated list of keys, dropping duplicates.
+    pub(crate) fn parse_list(s: &str) -> Result<Vec<AlertGroupKey>, String> {
+        let mut keys = vec![];
+        for part in s.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+            let key = AlertGroupKey::from_str(part)?;
+            if !keys.contains(&key) {
+                keys.push(key);
+            }
+        }
+        if keys.is_empty() {
+            return Err("no alert group keys".to_string());
+        }
+        Ok(keys)
+    }
+}
+
+impl FromStr for AlertGroupKey {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "signature_id" => Ok(AlertGroupKey::SignatureId),
+            "src_ip" => Ok(AlertGroupKey::SrcIp),
+            "dest_ip" => Ok(AlertGroupKey::DestIp),
+            _ => Err(format!("invalid alert group key: {s}")),
+        }
+    }
+}
+
 #[allow(unreachable_patterns)]
 impl EventRepo {
     pub fn get_importer(&self) -> Option<EventSink> {
@@ -106,6 +301,22 @@ impl EventRepo {
         }
     }
 
+    pub async fn archive_events_by_id(&self, event_ids: &[String]) -> Result<u64, DatastoreError> {
+        match self {
+            EventRepo::Elastic(ds) => ds.archive_events_by_id(event_ids).await,
+            EventRepo::SQLite(ds) => ds.archive_events_by_id(event_ids).await,
+            _ => Err(DatastoreError::Unimplemented),
+        }
+    }
+
+    pub async fn unarchive_event_by_id(&self, event_id: &str) -> Result<(), DatastoreError> {
+        match self {
+            EventRepo::Elastic(ds) => ds.unarchive_event_by_id(event_id).await,
+            EventRepo::SQLite(ds) => ds.unarchive_event_by_id(event_id).await,
+            _ => Err(DatastoreError::Unimplemented),
+        }
+    }
+
     pub async fn escalate_event_by_id(&self, event_id: &str) -> Result<(), DatastoreError> {
         match self {
             EventRepo::Elastic(ds) => ds.escalate_event_by_id(event_id).await,
@@ -155,6 +366,17 @@ impl EventRepo {
         }
     }
 
+    pub async fn unarchive_by_alert_group(
+        &self,
+        alert_group: api::AlertGroupSpec,
+    ) -> Result<(), DatastoreError> {
+        match self {
+            EventRepo::Elastic(ds) => ds.unarchive_by_alert_group(alert_group).await,
+            EventRepo::SQLite(ds) => ds.unarchive_by_alert_group(alert_group).await,
+            _ => Err(DatastoreError::Unimplemented),
+        }
+    }
+
     pub async fn escalate_by_alert_group(
         &self,
         alert_group: api::AlertGroupSpec,
@@ -190,6 +412,20 @@ impl EventRepo {
         }
     }
 
+    /// Fetch the next page of events for an export, in ascending
+    /// timestamp order.
+    pub async fn export_page(
+        &self,
+        params: &EventExportParams,
+        cursor: Option<EventCursor>,
+    ) -> Result<EventPage, DatastoreError> {
+        match self {
+            EventRepo::Elastic(ds) => ds.export_page(params, cursor).await,
+            EventRepo::SQLite(ds) => ds.export_page(params, cursor).await,
+            _ => Err(DatastoreError::Unimplemented),
+        }
+    }
+
     pub async fn comment_event_by_id(
         &self,
         event_id: &str,
@@ -203,6 +439,187 @@ impl EventRepo {
         }
     }
 
+    pub async fn comment_by_alert_group(
+        &self,
+        alert_group: api::AlertGroupSpec,
+        comment: String,
+        session: Arc<Session>,
+    ) -> Result<(), DatastoreError> {
+        match self {
+            EventRepo::Elastic(ds) => {
+                ds.comment_by_alert_group(alert_group, comment, session)
+                    .await
+            }
+            EventRepo::SQLite(ds) => {
+                ds.comment_by_alert_group(alert_group, comment, session)
+                    .await
+            }
+            _ => Err(DatastoreError::Unimplemented),
+        }
+    }
+
+    /// Edit a comment, or delete it if `comment` is `None`. If an
+    /// owner is given, only comments made by that user are changed.
+    /// Returns the number of events updated.
+    pub async fn update_comment(
+        &self,
+        comment_id: &str,
+        comment: Option<String>,
+        owner: Option<&str>,
+    ) -> Result<u64, DatastoreError> {
+        match self {
+            EventRepo::Elastic(ds) => ds.update_comment(comment_id, comment, owner).await,
+            EventRepo::SQLite(ds) => ds.update_comment(comment_id, comment, owner).await,
+            _ => Err(DatastoreError::Unimplemented),
+        }
+    }
+
+    pub async fn set_status_by_id(
+        &self,
+        event_id: &str,
+        status: TriageStatus,
+        reason: Option<String>,
+        session: Arc<Session>,
+    ) -> Result<(), DatastoreError> {
+        match self {
+            EventRepo::Elastic(ds) => ds.set_status_by_id(event_id, status, reason, session).await,
+            EventRepo::SQLite(ds) => ds.set_status_by_id(event_id, status, reason, session).await,
+            _ => Err(DatastoreError::Unimplemented),
+        }
+    }
+
+    pub async fn set_status_by_alert_group(
+        &self,
+        alert_group: api::AlertGroupSpec,
+        status: TriageStatus,
+        reason: Option<String>,
+        session: Arc<Session>,
+    ) -> Result<(), DatastoreError> {
+        match self {
+            EventRepo::Elastic(ds) => {
+                ds.set_status_by_alert_group(alert_group, status, reason, session)
+                    .await
+            }
+            EventRepo::SQLite(ds) => {
+                ds.set_status_by_alert_group(alert_group, status, reason, session)
+                    .await
+            }
+            _ => Err(DatastoreError::Unimplemented),
+        }
+    }
+
+    pub async fn set_assignee_by_id(
+        &self,
+        event_id: &str,
+        assignee: Option<String>,
+        session: Arc<Session>,
+    ) -> Result<(), DatastoreError> {
+        match self {
+            EventRepo::Elastic(ds) => ds.set_assignee_by_id(event_id, assignee, session).await,
+            EventRepo::SQLite(ds) => ds.set_assignee_by_id(event_id, assignee, session).await,
+            _ => Err(DatastoreError::Unimplemented),
+        }
+    }
+
+    pub async fn set_assignee_by_alert_group(
+        &self,
+        alert_group: api::AlertGroupSpec,
+        assignee: Option<String>,
+        session: Arc<Session>,
+    ) -> Result<(), DatastoreError> {
+        match self {
+            EventRepo::Elastic(ds) => {
+                ds.set_assignee_by_alert_group(alert_group, assignee, session)
+                    .await
+            }
+            EventRepo::SQLite(ds) => {
+                ds.set_assignee_by_alert_group(alert_group, assignee, session)
+                    .await
+            }
+            _ => Err(DatastoreError::Unimplemented),
+        }
+    }
+
+    pub async fn add_user_tags_by_id(
+        &self,
+        event_id: &str,
+        tags: Vec<String>,
+        session: Arc<Session>,
+    ) -> Result<(), DatastoreError> {
+        match self {
+            EventRepo::Elastic(ds) => ds.add_user_tags_by_id(event_id, tags, session).await,
+            EventRepo::SQLite(ds) => ds.add_user_tags_by_id(event_id, tags, session).await,
+            _ => Err(DatastoreError::Unimplemented),
+        }
+    }
+
+    pub async fn add_user_tags_by_alert_group(
+        &self,
+        alert_group: api::AlertGroupSpec,
+        tags: Vec<String>,
+        session: Arc<Session>,
+    ) -> Result<(), DatastoreError> {
+        match self {
+            EventRepo::Elastic(ds) => {
+                ds.add_user_tags_by_alert_group(alert_group, tags, session)
+                    .await
+            }
+            EventRepo::SQLite(ds) => {
+                ds.add_user_tags_by_alert_group(alert_group, tags, session)
+                    .await
+            }
+            _ => Err(DatastoreError::Unimplemented),
+        }
+    }
+
+    pub async fn remove_user_tags_by_id(
+        &self,
+        event_id: &str,
+        tags: Vec<String>,
+        session: Arc<Session>,
+    ) -> Result<(), DatastoreError> {
+        match self {
+            EventRepo::Elastic(ds) => ds.remove_user_tags_by_id(event_id, tags, session).await,
+            EventRepo::SQLite(ds) => ds.remove_user_tags_by_id(event_id, tags, session).await,
+            _ => Err(DatastoreError::Unimplemented),
+        }
+    }
+
+    pub async fn remove_user_tags_by_alert_group(
+        &self,
+        alert_group: api::AlertGroupSpec,
+        tags: Vec<String>,
+        session: Arc<Session>,
+    ) -> Result<(), DatastoreError> {
+        match self {
+            EventRepo::Elastic(ds) => {
+                ds.remove_user_tags_by_alert_group(alert_group, tags, session)
+                    .await
+            }
+            EventRepo::SQLite(ds) => {
+                ds.remove_user_tags_by_alert_group(alert_group, tags, session)
+                    .await
+            }
+            _ => Err(DatastoreError::Unimplemented),
+        }
+    }
+
+    /// Apply an action to all alerts matching the query, returning
+    /// the number of alerts updated, or that would be updated on a
+    /// dry run.
+    pub async fn bulk_action(
+        &self,
+        params: &BulkActionParams,
+        action: &BulkAction,
+        session: Arc<Session>,
+    ) -> Result<u64, DatastoreError> {
+        match self {
+            EventRepo::Elastic(ds) => ds.bulk_action(params, action, session).await,
+            EventRepo::SQLite(ds) => ds.bulk_action(params, action, session).await,
+            _ => Err(DatastoreError::Unimplemented),
+        }
+    }
+
     pub async fn agg(
         &self,
         field: &str,
@@ -3,7 +3,10 @@
 
 use super::EventRepo;
 use super::StatsAggQueryParams;
+use crate::datetime::DateTime;
 use anyhow::Result;
+use serde::Serialize;
+use std::collections::BTreeMap;
 
 impl EventRepo {
     pub async fn stats_agg(&self, params: &StatsAggQueryParams) -> Result<serde_json::Value> {
@@ -19,4 +22,228 @@ impl EventRepo {
             EventRepo::SQLite(ds) => ds.stats_agg_diff(params).await,
         }
     }
+
+    pub async fn sensor_stats(&self, params: &SensorStatsParams) -> Result<Vec<SensorStats>> {
+        let samples = match self {
+            EventRepo::Elastic(ds) => ds.stats_samples(params).await?,
+            EventRepo::SQLite(ds) => ds.stats_samples(params).await?,
+        };
+        Ok(sensor_stats(&samples))
+    }
+}
+
+/// Parameters for the per-sensor stats summary.
+#[derive(Debug, Clone)]
+pub(crate) struct SensorStatsParams {
+    pub sensor_name: Option<String>,
+    pub start_time: DateTime,
+    /// Bucket size in seconds. Only the last stats event of each
+    /// sensor in a bucket is used.
+    pub interval: u64,
+}
+
+/// The counters of interest from the `stats` object of a stats event.
+/// Suricata counters are cumulative since it started.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize)]
+pub(crate) struct StatsCounters {
+    pub kernel_packets: u64,
+    pub kernel_drops: u64,
+    pub packets: u64,
+    pub bytes: u64,
+    /// Sum of the flow and TCP memcap counters.
+    pub memcap_hits: u64,
+    pub flows: u64,
+}
+
+impl StatsCounters {
+    pub fn from_stats(stats: &serde_json::Value) -> Self {
+        let get = |path: &str| {
+            path.split('.')
+                .fold(stats, |value, key| &value[key])
+                .as_u64()
+                .unwrap_or(0)
+        };
+        let flows = if stats["flow"]["total"].is_u64() {
+            get("flow.total")
+        } else {
+            ["flow.tcp", "flow.udp", "flow.icmpv4", "flow.icmpv6"]
+                .iter()
+                .map(|path| get(path))
+                .sum()
+        };
+        Self {
+            kernel_packets: get("capture.kernel_packets"),
+            kernel_drops: get("capture.kernel_drops"),
+            packets: get("decoder.pkts"),
+            bytes: get("decoder.bytes"),
+            memcap_hits: get("flow.memcap")
+                + get("tcp.ssn_memcap_drop")
+                + get("tcp.segment_memcap_drop"),
+            flows,
+        }
+    }
+
+    /// The change from a previous sample. A counter lower than before
+    /// means Suricata was restarted, so the new value is the change.
+    fn delta(&self, previous: &Self) -> Self {
+        let delta = |current: u64, previous: u64| {
+            if current >= previous {
+                current - previous
+            } else {
+                current
+            }
+        };
+        Self {
+            kernel_packets: delta(self.kernel_packets, previous.kernel_packets),
+            kernel_drops: delta(self.kernel_drops, previous.kernel_drops),
+            packets: delta(self.packets, previous.packets),
+            bytes: delta(self.bytes, previous.bytes),
+            memcap_hits: delta(self.memcap_hits, previous.memcap_hits),
+            flows: delta(self.flows, previous.flows),
+        }
+    }
+
+    fn add(&mut self, other: &Self) {
+        self.kernel_packets += other.kernel_packets;
+        self.kernel_drops += other.kernel_drops;
+        self.packets += other.packets;
+        self.bytes += other.bytes;
+        self.memcap_hits += other.memcap_hits;
+        self.flows += other.flows;
+    }
+
+    fn kernel_drop_pct(&self) -> f64 {
+        if self.kernel_packets == 0 {
+            0.0
+        } else {
+            self.kernel_drops as f64 / self.kernel_packets as f64 * 100.0
+        }
+    }
+}
+
+/// A stats event as returned by the datastore.
+#[derive(Debug, Clone)]
+pub(crate) struct StatsSample {
+    pub sensor: String,
+    pub timestamp: DateTime,
+    pub counters: StatsCounters,
+}
+
+#[derive(Debug, Serialize)]
+pub(crate) struct SensorStatsPoint {
+    pub timestamp: String,
+    /// Seconds since the previous sample.
+    pub seconds: f64,
+    pub packets_per_sec: f64,
+    pub bytes_per_sec: f64,
+    pub flows_per_sec: f64,
+    pub kernel_drop_pct: f64,
+    /// Changes since the previous sample.
+    pub delta: StatsCounters,
+}
+
+#[derive(Debug, Serialize)]
+pub(crate) struct SensorStats {
+    pub sensor: String,
+    pub points: Vec<SensorStatsPoint>,
+    /// Totals over the time range.
+    pub totals: StatsCounters,
+    pub kernel_drop_pct: f64,
+}
+
+/// Compute rates and deltas from consecutive samples of each sensor.
+/// Samples must be ordered by timestamp within each sensor.
+pub(crate) fn sensor_stats(samples: &[StatsSample]) -> Vec<SensorStats> {
+    let mut by_sensor: BTreeMap<&str, Vec<&StatsSample>> = BTreeMap::new();
+    for sample in samples {
+        by_sensor.entry(&sample.sensor).or_default().push(sample);
+    }
+
+    let mut sensors = vec![];
+    for (sensor, samples) in by_sensor {
+        let mut points = vec![];
+        let mut totals = StatsCounters::default();
+        for pair in samples.windows(2) {
+            let (previous, current) = (pair[0], pair[1]);
+            let seconds = (current.timestamp.to_nanos() - previous.timestamp.to_nanos()) as f64
+                / 1_000_000_000.0;
+            if seconds <= 0.0 {
+                continue;
+            }
+            let delta = current.counters.delta(&previous.counters);
+            totals.add(&delta);
+            points.push(SensorStatsPoint {
+                timestamp: current.timestamp.to_rfc3339_utc(),
+                seconds,
+                packets_per_sec: delta.packets as f64 / seconds,
+                bytes_per_sec: delta.bytes as f64 / seconds,
+                flows_per_sec: delta.flows as f64 / seconds,
+                kernel_drop_pct: delta.kernel_drop_pct(),
+                delta,
+            });
+        }
+        sensors.push(SensorStats {
+            sensor: sensor.to_string(),
+            points,
+            kernel_drop_pct: totals.kernel_drop_pct(),
+            totals,
+        });
+    }
+    sensors
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(sensor: &str, seconds: i64, packets: u64, drops: u64) -> StatsSample {
+        StatsSample {
+            sensor: sensor.to_string(),
+            timestamp: DateTime::from_seconds(seconds),
+            counters: StatsCounters::from_stats(&json!({
+                "capture": {"kernel_packets": packets, "kernel_drops": drops},
+                "decoder": {"pkts": packets, "bytes": packets * 100},
+                "flow": {"tcp": 2, "udp": packets / 10, "memcap": 0},
+            })),
+        }
+    }
+
+    #[test]
+    fn test_sensor_stats() {
+        let samples = vec![
+            sample("a", 0, 1000, 0),
+            sample("a", 10, 2000, 100),
+            // Restart.
+            sample("a", 20, 500, 0),
+            sample("b", 0, 0, 0),
+        ];
+        let stats = sensor_stats(&samples);
+        assert_eq!(stats.len(), 2);
+
+        let a = &stats[0];
+        assert_eq!(a.sensor, "a");
+        assert_eq!(a.points.len(), 2);
+        assert_eq!(a.points[0].packets_per_sec, 100.0);
+        assert_eq!(a.points[0].bytes_per_sec, 10000.0);
+        assert_eq!(a.points[0].kernel_drop_pct, 10.0);
+        assert_eq!(a.points[0].delta.flows, 100);
+        assert_eq!(a.points[1].delta.packets, 500);
+        assert_eq!(a.totals.packets, 1500);
+        assert_eq!(a.totals.kernel_drops, 100);
+        assert_eq!(a.kernel_drop_pct, 100.0 / 1500.0 * 100.0);
+
+        let b = &stats[1];
+        assert!(b.points.is_empty());
+        assert_eq!(b.kernel_drop_pct, 0.0);
+    }
+
+    #[test]
+    fn test_flow_total() {
+        let counters = StatsCounters::from_stats(&json!({
+            "flow": {"total": 50, "tcp": 10, "udp": 10, "memcap": 1},
+            "tcp": {"ssn_memcap_drop": 2, "segment_memcap_drop": 3},
+        }));
+        assert_eq!(counters.flows, 50);
+        assert_eq!(counters.memcap_hits, 6);
+    }
 }
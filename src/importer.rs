@@ -3,7 +3,11 @@
 
 use crate::agent::importer::EveBoxEventSink;
 use crate::elastic::ElasticEventSink;
+use crate::live::LiveEventSink;
+use crate::metrics::METRICS;
 use crate::sqlite::importer::SqliteEventSink;
+use crate::syslog::SyslogEventSink;
+use tracing::warn;
 
 /// The importer interface, an enum wrapper around various implementations of an importer for Eve events.
 #[derive(Clone)]
@@ -11,6 +15,13 @@ pub(crate) enum EventSink {
     EveBox(EveBoxEventSink),
     Elastic(ElasticEventSink),
     SQLite(SqliteEventSink),
+    Syslog(SyslogEventSink),
+    /// Publish events to live subscribers.
+    Live(LiveEventSink),
+    /// Send events to multiple sinks, for example a datastore and a
+    /// syslog server. The first sink is the primary, the others are
+    /// best effort.
+    Multi(Vec<EventSink>),
 }
 
 impl EventSink {
@@ -20,19 +31,76 @@ impl EventSink {
     ) -> Result<bool, Box<dyn std::error::Error + Send + Sync>> {
         match self {
             EventSink::EveBox(importer) => importer.submit(event).await,
-            EventSink::Elastic(importer) => importer.submit(event).await,
-            EventSink::SQLite(importer) => match importer.submit(event).await {
-                Ok(commit) => Ok(commit),
-                Err(err) => Err(Box::new(err)),
-            },
+            EventSink::Elastic(importer) => {
+                METRICS.event_ingested(&event);
+                importer.submit(event).await
+            }
+            EventSink::SQLite(importer) => {
+                METRICS.event_ingested(&event);
+                match importer.submit(event).await {
+                    Ok(commit) => Ok(commit),
+                    Err(err) => Err(Box::new(err)),
+                }
+            }
+            EventSink::Syslog(importer) => importer.submit(event).await,
+            EventSink::Live(importer) => importer.submit(event).await,
+            EventSink::Multi(sinks) => {
+                // Only clone the event for the sinks that want it,
+                // the last of them takes the event itself.
+                let Some(last) = sinks.iter().rposition(EventSink::wants_events) else {
+                    return Ok(false);
+                };
+                let (others, last) = sinks[..=last].split_at_mut(last);
+                let mut commit = false;
+                for sink in others.iter_mut().filter(|sink| sink.wants_events()) {
+                    commit |= Box::pin(sink.submit(event.clone())).await?;
+                }
+                commit |= Box::pin(last[0].submit(event)).await?;
+                Ok(commit)
+            }
         }
     }
 
     pub async fn commit(&mut self) -> anyhow::Result<usize> {
         match self {
-            EventSink::EveBox(importer) => importer.commit().await,
-            EventSink::Elastic(importer) => importer.commit().await,
-            EventSink::SQLite(importer) => importer.commit().await,
+            EventSink::EveBox(importer) => METRICS.commit("evebox", importer.commit()).await,
+            EventSink::Elastic(importer) => METRICS.commit("elastic", importer.commit()).await,
+            EventSink::SQLite(importer) => METRICS.commit("sqlite", importer.commit()).await,
+            EventSink::Syslog(importer) => METRICS.commit("syslog", importer.commit()).await,
+            EventSink::Live(importer) => importer.commit().await,
+            EventSink::Multi(sinks) => {
+                // Commit the primary first, and return its error
+                // before committing the others so they only see
+                // events the primary has stored, the events stay
+                // queued in every sink for a retry. Errors from the
+                // others are only logged so a failing syslog server
+                // can't cause events to be stored twice.
+                let Some((primary, others)) = sinks.split_first_mut() else {
+                    return Ok(0);
+                };
+                let mut n = 0;
+                if primary.pending() > 0 {
+                    n = Box::pin(primary.commit()).await?;
+                }
+                for sink in others {
+                    if sink.pending() > 0 {
+                        if let Err(err) = Box::pin(sink.commit()).await {
+                            warn!("{:#}", err);
+                        }
+                    }
+                }
+                Ok(n)
+            }
+        }
+    }
+
+    /// Whether submitted events are used, so the event doesn't have to
+    /// be copied for a sink that would drop it.
+    fn wants_events(&self) -> bool {
+        match self {
+            EventSink::Live(importer) => importer.has_subscribers(),
+            EventSink::Multi(sinks) => sinks.iter().any(|sink| sink.wants_events()),
+            _ => true,
         }
     }
 
@@ -41,6 +109,9 @@ impl EventSink {
             EventSink::EveBox(importer) => importer.pending(),
             EventSink::Elastic(importer) => importer.pending(),
             EventSink::SQLite(importer) => importer.pending(),
+            EventSink::Syslog(importer) => importer.pending(),
+            EventSink::Live(importer) => importer.pending(),
+            EventSink::Multi(sinks) => sinks.iter().map(|sink| sink.pending()).max().unwrap_or(0),
         }
     }
 }
@@ -18,6 +18,9 @@ mod eventrepo;
 mod file;
 mod geoip;
 mod importer;
+mod live;
+mod metrics;
+mod notifications;
 mod packet;
 mod path;
 mod pcap;
@@ -25,7 +28,9 @@ mod prelude;
 mod queryparser;
 mod resource;
 mod rules;
+mod sensors;
 mod sqlite;
+mod syslog;
 mod util;
 
 #[macro_use]
@@ -0,0 +1,118 @@
+// SPDX-FileCopyrightText: (C) 2024 Jason Ish <jason@codemonkey.net>
+// SPDX-License-Identifier: MIT
+
+//! Live events: newly imported events published to subscribed
+//! clients, such as the live tail API.
+
+use std::sync::Arc;
+
+use tokio::sync::broadcast;
+
+/// Number of events buffered per subscriber. A subscriber that falls
+/// further behind than this misses the oldest events rather than
+/// holding up the importer.
+const CAPACITY: usize = 1024;
+
+/// The publisher side of the live event stream.
+#[derive(Clone)]
+pub(crate) struct LiveEvents {
+    tx: broadcast::Sender<Arc<serde_json::Value>>,
+}
+
+impl Default for LiveEvents {
+    fn default() -> Self {
+        let (tx, _) = broadcast::channel(CAPACITY);
+        Self { tx }
+    }
+}
+
+impl LiveEvents {
+    pub(crate) fn subscribe(&self) -> broadcast::Receiver<Arc<serde_json::Value>> {
+        self.tx.subscribe()
+    }
+
+    pub(crate) fn sink(&self) -> LiveEventSink {
+        LiveEventSink {
+            tx: self.tx.clone(),
+            queue: Vec::new(),
+        }
+    }
+}
+
+/// An event sink publishing events to live subscribers on commit, so
+/// only events that have been imported are published.
+#[derive(Clone)]
+pub(crate) struct LiveEventSink {
+    tx: broadcast::Sender<Arc<serde_json::Value>>,
+    queue: Vec<serde_json::Value>,
+}
+
+impl LiveEventSink {
+    pub async fn submit(
+        &mut self,
+        event: serde_json::Value,
+    ) -> Result<bool, Box<dyn std::error::Error + Send + Sync>> {
+        // Don't bother queuing events if nobody is listening.
+        if self.has_subscribers() {
+            self.queue.push(event);
+        }
+        Ok(false)
+    }
+
+    pub fn has_subscribers(&self) -> bool {
+        self.tx.receiver_count() > 0
+    }
+
+    pub async fn commit(&mut self) -> anyhow::Result<usize> {
+        let n = self.queue.len();
+        for event in self.queue.drain(..) {
+            // Only fails if all subscribers have gone away.
+            let _ = self.tx.send(Arc::new(event));
+        }
+        Ok(n)
+    }
+
+    pub fn pending(&self) -> usize {
+        self.queue.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_live_sink() {
+        let live = LiveEvents::default();
+        let mut sink = live.sink();
+
+        // No subscribers, nothing is queued.
+        sink.submit(json!({"event_type": "dns"})).await.unwrap();
+        assert_eq!(sink.pending(), 0);
+
+        let mut rx = live.subscribe();
+        sink.submit(json!({"event_type": "alert"})).await.unwrap();
+        assert_eq!(sink.pending(), 1);
+        assert!(rx.try_recv().is_err());
+        assert_eq!(sink.commit().await.unwrap(), 1);
+        assert_eq!(rx.recv().await.unwrap()["event_type"], "alert");
+    }
+
+    #[tokio::test]
+    async fn test_slow_subscriber() {
+        let live = LiveEvents::default();
+        let mut sink = live.sink();
+        let mut rx = live.subscribe();
+        for i in 0..CAPACITY + 10 {
+            sink.submit(json!({"n": i})).await.unwrap();
+        }
+        sink.commit().await.unwrap();
+
+        // The oldest events are dropped for the slow subscriber.
+        assert!(matches!(
+            rx.recv().await,
+            Err(broadcast::error::RecvError::Lagged(10))
+        ));
+        assert_eq!(rx.recv().await.unwrap()["n"], 10);
+    }
+}
@@ -0,0 +1,306 @@
+// SPDX-FileCopyrightText: (C) 2024 Jason Ish <jason@codemonkey.net>
+// SPDX-License-Identifier: MIT
+
+//! Internal metrics, rendered in the Prometheus text format.
+//!
+//! Metrics are recorded into a global registry as they happen, from
+//! the importers, EVE readers and the retention job. The server
+//! exposes them at `/metrics`.
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::future::Future;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, RwLock};
+use std::time::{Duration, Instant};
+
+lazy_static! {
+    pub(crate) static ref METRICS: Metrics = Metrics::default();
+}
+
+/// Histogram buckets in seconds.
+const BUCKETS: &[f64] = &[
+    0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
+];
+
+#[derive(Debug, Default, Clone)]
+struct Histogram {
+    buckets: [u64; BUCKETS.len()],
+    sum: f64,
+    count: u64,
+}
+
+impl Histogram {
+    fn observe(&mut self, duration: Duration) {
+        let secs = duration.as_secs_f64();
+        for (i, le) in BUCKETS.iter().enumerate() {
+            if secs <= *le {
+                self.buckets[i] += 1;
+            }
+        }
+        self.sum += secs;
+        self.count += 1;
+    }
+
+    fn render(&self, out: &mut String, name: &str, labels: &str) {
+        let sep = if labels.is_empty() { "" } else { "," };
+        for (le, count) in BUCKETS.iter().zip(self.buckets.iter()) {
+            let _ = writeln!(out, "{name}_bucket{{{labels}{sep}le=\"{le}\"}} {count}");
+        }
+        let _ = writeln!(
+            out,
+            "{name}_bucket{{{labels}{sep}le=\"+Inf\"}} {}",
+            self.count
+        );
+        let _ = writeln!(out, "{name}_sum{{{labels}}} {}", self.sum);
+        let _ = writeln!(out, "{name}_count{{{labels}}} {}", self.count);
+    }
+}
+
+#[derive(Debug, Default)]
+struct Commits {
+    latency: Histogram,
+    failures: u64,
+}
+
+#[derive(Debug, Default)]
+pub(crate) struct Metrics {
+    /// Events ingested by sensor and event type. Counters are only
+    /// added under the write lock, incremented under the read lock.
+    events: RwLock<BTreeMap<String, BTreeMap<String, AtomicU64>>>,
+    /// Importer commits by sink.
+    commits: Mutex<BTreeMap<&'static str, Commits>>,
+    /// Bytes not yet read by EVE filename.
+    reader_lag: Mutex<BTreeMap<String, u64>>,
+    /// Events deleted by the retention job.
+    retention_deleted: AtomicU64,
+    /// HTTP requests by (method, route, status).
+    http_requests: Mutex<BTreeMap<(String, String, u16), u64>>,
+    /// HTTP request latency by route.
+    http_latency: Mutex<BTreeMap<String, Histogram>>,
+}
+
+impl Metrics {
+    pub(crate) fn event_ingested(&self, event: &serde_json::Value) {
+        let sensor = event["host"].as_str().unwrap_or_default();
+        let event_type = event["event_type"].as_str().unwrap_or_default();
+        if let Some(counter) = self
+            .events
+            .read()
+            .unwrap()
+            .get(sensor)
+            .and_then(|event_types| event_types.get(event_type))
+        {
+            counter.fetch_add(1, Ordering::Relaxed);
+            return;
+        }
+        self.events
+            .write()
+            .unwrap()
+            .entry(sensor.to_string())
+            .or_default()
+            .entry(event_type.to_string())
+            .or_default()
+            .fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Time an importer commit, recording the latency and any
+    /// failure.
+    pub(crate) async fn commit<F, T>(&self, sink: &'static str, commit: F) -> anyhow::Result<T>
+    where
+        F: Future<Output = anyhow::Result<T>>,
+    {
+        let timer = Instant::now();
+        let result = commit.await;
+        let mut commits = self.commits.lock().unwrap();
+        let commits = commits.entry(sink).or_default();
+        commits.latency.observe(timer.elapsed());
+        if result.is_err() {
+            commits.failures += 1;
+        }
+        result
+    }
+
+    pub(crate) fn reader_lag(&self, filename: &str, lag: u64) {
+        self.reader_lag
+            .lock()
+            .unwrap()
+            .insert(filename.to_string(), lag);
+    }
+
+    pub(crate) fn retention_deleted(&self, n: u64) {
+        self.retention_deleted.fetch_add(n, Ordering::Relaxed);
+    }
+
+    pub(crate) fn http_request(&self, method: &str, route: &str, status: u16, latency: Duration) {
+        *self
+            .http_requests
+            .lock()
+            .unwrap()
+            .entry((method.to_string(), route.to_string(), status))
+            .or_default() += 1;
+        self.http_latency
+            .lock()
+            .unwrap()
+            .entry(route.to_string())
+            .or_default()
+            .observe(latency);
+    }
+
+    pub(crate) fn render(&self, out: &mut String) {
+        header(
+            out,
+            "evebox_events_ingested_total",
+            "counter",
+            "Events ingested by sensor and event type.",
+        );
+        for (sensor, event_types) in self.events.read().unwrap().iter() {
+            for (event_type, count) in event_types {
+                let _ = writeln!(
+                    out,
+                    "evebox_events_ingested_total{{sensor=\"{}\",event_type=\"{}\"}} {}",
+                    escape(sensor),
+                    escape(event_type),
+                    count.load(Ordering::Relaxed)
+                );
+            }
+        }
+
+        let commits = self.commits.lock().unwrap();
+        header(
+            out,
+            "evebox_importer_commit_seconds",
+            "histogram",
+            "Importer commit latency.",
+        );
+        for (sink, commit) in commits.iter() {
+            commit.latency.render(
+                out,
+                "evebox_importer_commit_seconds",
+                &format!("sink=\"{sink}\""),
+            );
+        }
+        header(
+            out,
+            "evebox_importer_commit_failures_total",
+            "counter",
+            "Failed importer commits.",
+        );
+        for (sink, commit) in commits.iter() {
+            let _ = writeln!(
+                out,
+                "evebox_importer_commit_failures_total{{sink=\"{sink}\"}} {}",
+                commit.failures
+            );
+        }
+        drop(commits);
+
+        header(
+            out,
+            "evebox_reader_lag_bytes",
+            "gauge",
+            "Bytes of an EVE file not yet read.",
+        );
+        for (filename, lag) in self.reader_lag.lock().unwrap().iter() {
+            let _ = writeln!(
+                out,
+                "evebox_reader_lag_bytes{{filename=\"{}\"}} {lag}",
+                escape(filename)
+            );
+        }
+
+        header(
+            out,
+            "evebox_retention_deleted_events_total",
+            "counter",
+            "Events deleted by the retention job.",
+        );
+        let _ = writeln!(
+            out,
+            "evebox_retention_deleted_events_total {}",
+            self.retention_deleted.load(Ordering::Relaxed)
+        );
+
+        header(
+            out,
+            "evebox_http_requests_total",
+            "counter",
+            "HTTP requests by method, route and status.",
+        );
+        for ((method, route, status), count) in self.http_requests.lock().unwrap().iter() {
+            let _ = writeln!(
+                out,
+                "evebox_http_requests_total{{method=\"{}\",route=\"{}\",status=\"{status}\"}} {count}",
+                escape(method),
+                escape(route)
+            );
+        }
+        header(
+            out,
+            "evebox_http_request_duration_seconds",
+            "histogram",
+            "HTTP request latency by route.",
+        );
+        for (route, latency) in self.http_latency.lock().unwrap().iter() {
+            latency.render(
+                out,
+                "evebox_http_request_duration_seconds",
+                &format!("route=\"{}\"", escape(route)),
+            );
+        }
+    }
+}
+
+/// Write the HELP and TYPE lines for a metric.
+pub(crate) fn header(out: &mut String, name: &str, kind: &str, help: &str) {
+    let _ = writeln!(out, "# HELP {name} {help}");
+    let _ = writeln!(out, "# TYPE {name} {kind}");
+}
+
+/// Escape a label value.
+pub(crate) fn escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_render() {
+        let metrics = Metrics::default();
+        metrics.event_ingested(&json!({"host": "sensor1", "event_type": "alert"}));
+        metrics.event_ingested(&json!({"host": "sensor1", "event_type": "alert"}));
+        metrics.event_ingested(&json!({"host": "sensor\"2", "event_type": "dns"}));
+        let _ = metrics
+            .commit("sqlite", async { Ok::<_, anyhow::Error>(1) })
+            .await;
+        let _ = metrics
+            .commit("sqlite", async { Err::<usize, _>(anyhow!("failed")) })
+            .await;
+        metrics.reader_lag("/var/log/suricata/eve.json", 100);
+        metrics.retention_deleted(5);
+        metrics.http_request("GET", "/api/1/alerts", 200, Duration::from_millis(20));
+
+        let mut out = String::new();
+        metrics.render(&mut out);
+        let lines: Vec<&str> = out.lines().collect();
+        for expected in [
+            "evebox_events_ingested_total{sensor=\"sensor1\",event_type=\"alert\"} 2",
+            "evebox_events_ingested_total{sensor=\"sensor\\\"2\",event_type=\"dns\"} 1",
+            "evebox_importer_commit_seconds_count{sink=\"sqlite\"} 2",
+            "evebox_importer_commit_failures_total{sink=\"sqlite\"} 1",
+            "evebox_reader_lag_bytes{filename=\"/var/log/suricata/eve.json\"} 100",
+            "evebox_retention_deleted_events_total 5",
+            "evebox_http_requests_total{method=\"GET\",route=\"/api/1/alerts\",status=\"200\"} 1",
+            "evebox_http_request_duration_seconds_bucket{route=\"/api/1/alerts\",le=\"0.01\"} 0",
+            "evebox_http_request_duration_seconds_bucket{route=\"/api/1/alerts\",le=\"0.025\"} 1",
+            "evebox_http_request_duration_seconds_bucket{route=\"/api/1/alerts\",le=\"+Inf\"} 1",
+        ] {
+            assert!(lines.contains(&expected), "missing: {expected}");
+        }
+    }
+}
@@ -0,0 +1,467 @@
+// SPDX-FileCopyrightText: (C) 2024 Jason Ish <jason@codemonkey.net>
+// SPDX-License-Identifier: MIT
+
+//! Webhook notifications for alerts.
+//!
+//! Alerts are checked against the configured webhooks as they are
+//! received. Matching alerts are rendered with the webhook template
+//! and queued for delivery by a background task, so a slow or failing
+//! endpoint does not hold up ingest.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use serde::Deserialize;
+use tokio::sync::{mpsc, Semaphore};
+use tracing::{debug, error, warn};
+
+use crate::queryparser::{self, QueryElement};
+use crate::sqlite::configrepo::sensors::Sensor;
+
+/// Maximum number of notifications waiting to be delivered. Further
+/// notifications are dropped.
+const QUEUE_SIZE: usize = 1000;
+
+/// Maximum number of notifications being delivered at once.
+const MAX_CONCURRENT: usize = 8;
+
+const DEFAULT_RETRIES: u32 = 3;
+
+/// Delay before the first retry, doubled for each further retry.
+const RETRY_DELAY: Duration = Duration::from_secs(1);
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Webhook configuration, from the `notifications.webhooks` list of
+/// the server configuration.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub(crate) struct WebhookConfig {
+    pub name: Option<String>,
+    pub url: String,
+    /// Only notify for alerts matching this query string.
+    #[serde(default)]
+    pub query: String,
+    /// Only notify for alerts of this severity or higher. As with
+    /// Suricata 1 is the highest severity.
+    pub min_severity: Option<u64>,
+    /// Notify at most once per signature in this period, e.g. "5m".
+    pub throttle: Option<String>,
+    /// JSON template of the request body. See `render`.
+    pub template: Option<serde_json::Value>,
+    #[serde(default)]
+    pub headers: HashMap<String, String>,
+    pub retries: Option<u32>,
+    /// Also notify when a sensor stops sending events.
+    #[serde(default)]
+    pub sensors: bool,
+}
+
+struct Webhook {
+    name: String,
+    url: String,
+    query: Vec<QueryElement>,
+    min_severity: Option<u64>,
+    throttle: Option<Duration>,
+    template: serde_json::Value,
+    headers: HashMap<String, String>,
+    retries: u32,
+    sensors: bool,
+    /// Time of the last notification by signature ID.
+    last_sent: Mutex<HashMap<u64, Instant>>,
+}
+
+impl Webhook {
+    fn new(config: WebhookConfig) -> anyhow::Result<Self> {
+        let name = config.name.unwrap_or_else(|| config.url.clone());
+        let query = queryparser::parse(&config.query, None)
+            .map_err(|err| anyhow!("webhook {}: bad query: {}", name, err))?;
+        let throttle = config
+            .throttle
+            .map(|throttle| humantime::parse_duration(&throttle))
+            .transpose()
+            .map_err(|err| anyhow!("webhook {}: bad throttle: {}", name, err))?;
+        Ok(Self {
+            name,
+            url: config.url,
+            query,
+            min_severity: config.min_severity,
+            throttle,
+            template: config.template.unwrap_or_else(default_template),
+            headers: config.headers,
+            retries: config.retries.unwrap_or(DEFAULT_RETRIES),
+            sensors: config.sensors,
+            last_sent: Mutex::new(HashMap::new()),
+        })
+    }
+
+    fn matches(&self, event: &serde_json::Value) -> bool {
+        if let Some(min_severity) = self.min_severity {
+            match event["alert"]["severity"].as_u64() {
+                Some(severity) if severity <= min_severity => {}
+                _ => return false,
+            }
+        }
+        queryparser::matches(&self.query, event)
+    }
+
+    /// Check if a notification for the event should be sent, recording
+    /// the time if so.
+    fn throttle_ok(&self, event: &serde_json::Value, now: Instant) -> bool {
+        let Some(throttle) = self.throttle else {
+            return true;
+        };
+        let signature_id = event["alert"]["signature_id"].as_u64().unwrap_or(0);
+        let mut last_sent = self.last_sent.lock().unwrap();
+        if let Some(last) = last_sent.get(&signature_id) {
+            if now.duration_since(*last) < throttle {
+                return false;
+            }
+        }
+        last_sent.insert(signature_id, now);
+        true
+    }
+}
+
+fn default_template() -> serde_json::Value {
+    json!({
+        "text": "EveBox alert: {{alert.signature}} [severity {{alert.severity}}] {{src_ip}} -> {{dest_ip}} on {{host}}",
+        "event": "{{raw}}",
+    })
+}
+
+/// Render a template for an event.
+///
+/// `{{field}}` in strings is replaced with the value of the field in
+/// dotted notation, and `{{raw}}` with the complete event. A string
+/// that is only a single placeholder is replaced with the JSON value
+/// itself, so objects and numbers keep their type.
+fn render(template: &serde_json::Value, event: &serde_json::Value) -> serde_json::Value {
+    let lookup = |name: &str| -> serde_json::Value {
+        match name {
+            "raw" => event.clone(),
+            _ => name
+                .split('.')
+                .fold(event, |value, key| &value[key])
+                .clone(),
+        }
+    };
+    match template {
+        serde_json::Value::String(s) => {
+            if let Some(name) = s
+                .strip_prefix("{{")
+                .and_then(|s| s.strip_suffix("}}"))
+                .filter(|name| !name.contains("{{"))
+            {
+                return lookup(name.trim());
+            }
+            let mut output = String::new();
+            let mut rest = s.as_str();
+            while let Some(start) = rest.find("{{") {
+                let Some(end) = rest[start..].find("}}") else {
+                    break;
+                };
+                output.push_str(&rest[..start]);
+                match lookup(rest[start + 2..start + end].trim()) {
+                    serde_json::Value::String(s) => output.push_str(&s),
+                    serde_json::Value::Null => {}
+                    value => output.push_str(&value.to_string()),
+                }
+                rest = &rest[start + end + 2..];
+            }
+            output.push_str(rest);
+            output.into()
+        }
+        serde_json::Value::Array(values) => values.iter().map(|v| render(v, event)).collect(),
+        serde_json::Value::Object(map) => map
+            .iter()
+            .map(|(k, v)| (k.clone(), render(v, event)))
+            .collect::<serde_json::Map<_, _>>()
+            .into(),
+        _ => template.clone(),
+    }
+}
+
+struct Notification {
+    webhook: Arc<Webhook>,
+    body: serde_json::Value,
+}
+
+/// Checks alerts against the configured webhooks and queues
+/// notifications for delivery.
+#[derive(Clone)]
+pub(crate) struct Notifier {
+    webhooks: Vec<Arc<Webhook>>,
+    tx: mpsc::Sender<Notification>,
+}
+
+impl Notifier {
+    /// Create a notifier and start the delivery task.
+    pub fn start(configs: Vec<WebhookConfig>) -> anyhow::Result<Self> {
+        let webhooks = configs
+            .into_iter()
+            .map(|config| Webhook::new(config).map(Arc::new))
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        let client = reqwest::Client::builder()
+            .timeout(REQUEST_TIMEOUT)
+            .build()?;
+        let (tx, rx) = mpsc::channel(QUEUE_SIZE);
+        tokio::spawn(delivery_task(client, rx, RETRY_DELAY));
+        Ok(Self { webhooks, tx })
+    }
+
+    pub fn run(&self, event: &serde_json::Value) {
+        if event["event_type"].as_str() != Some("alert") {
+            return;
+        }
+
+        // Don't notify for alerts archived on ingest, for example by a
+        // suppression.
+        if event["tags"]
+            .as_array()
+            .is_some_and(|tags| tags.iter().any(|tag| tag == "evebox.archived"))
+        {
+            return;
+        }
+
+        let now = Instant::now();
+        for webhook in &self.webhooks {
+            if !webhook.matches(event) || !webhook.throttle_ok(event, now) {
+                continue;
+            }
+            let notification = Notification {
+                webhook: webhook.clone(),
+                body: render(&webhook.template, event),
+            };
+            if let Err(err) = self.tx.try_send(notification) {
+                warn!(
+                    "Dropping notification for webhook {}: {}",
+                    webhook.name, err
+                );
+            }
+        }
+    }
+
+    /// Notify webhooks with sensor notifications enabled that a sensor
+    /// has gone silent. The body is not templated.
+    pub fn sensor_silent(&self, sensor: &Sensor) {
+        let body = json!({
+            "text": format!(
+                "EveBox: sensor {} has not sent events since {}",
+                sensor.name,
+                sensor.last_seen.to_rfc3339_utc()
+            ),
+            "sensor": sensor,
+        });
+        for webhook in self.webhooks.iter().filter(|webhook| webhook.sensors) {
+            let notification = Notification {
+                webhook: webhook.clone(),
+                body: body.clone(),
+            };
+            if let Err(err) = self.tx.try_send(notification) {
+                warn!(
+                    "Dropping notification for webhook {}: {}",
+                    webhook.name, err
+                );
+            }
+        }
+    }
+}
+
+async fn delivery_task(
+    client: reqwest::Client,
+    mut rx: mpsc::Receiver<Notification>,
+    retry_delay: Duration,
+) {
+    let semaphore = Arc::new(Semaphore::new(MAX_CONCURRENT));
+    while let Some(notification) = rx.recv().await {
+        let Ok(permit) = semaphore.clone().acquire_owned().await else {
+            break;
+        };
+        let client = client.clone();
+        tokio::spawn(async move {
+            deliver(&client, &notification, retry_delay).await;
+            drop(permit);
+        });
+    }
+}
+
+/// Send a notification, retrying on failure.
+async fn deliver(client: &reqwest::Client, notification: &Notification, retry_delay: Duration) {
+    let webhook = &notification.webhook;
+    let mut delay = retry_delay;
+    for attempt in 0..=webhook.retries {
+        if attempt > 0 {
+            tokio::time::sleep(delay).await;
+            delay *= 2;
+        }
+        let mut request = client.post(&webhook.url).json(&notification.body);
+        for (name, value) in &webhook.headers {
+            request = request.header(name, value);
+        }
+        match request.send().await {
+            Ok(response) if response.status().is_success() => {
+                debug!("Sent notification to webhook {}", webhook.name);
+                return;
+            }
+            Ok(response) => {
+                warn!(
+                    "Webhook {} returned status {}, attempt {}",
+                    webhook.name,
+                    response.status(),
+                    attempt + 1
+                );
+            }
+            Err(err) => {
+                warn!(
+                    "Failed to send to webhook {}, attempt {}: {}",
+                    webhook.name,
+                    attempt + 1,
+                    err
+                );
+            }
+        }
+    }
+    error!(
+        "Giving up on notification to webhook {} after {} attempts",
+        webhook.name,
+        webhook.retries + 1
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::StatusCode;
+    use axum::routing::post;
+    use axum::{Json, Router};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    fn webhook_config(url: &str) -> WebhookConfig {
+        WebhookConfig {
+            name: None,
+            url: url.to_string(),
+            query: String::new(),
+            min_severity: None,
+            throttle: None,
+            template: None,
+            headers: HashMap::new(),
+            retries: None,
+            sensors: false,
+        }
+    }
+
+    fn alert() -> serde_json::Value {
+        json!({
+            "event_type": "alert",
+            "src_ip": "10.16.1.10",
+            "dest_ip": "1.1.1.1",
+            "host": "sensor1",
+            "alert": {
+                "signature": "ET POLICY Something",
+                "signature_id": 2013028,
+                "severity": 2,
+            },
+        })
+    }
+
+    #[test]
+    fn test_render() {
+        let event = alert();
+        let rendered = render(&default_template(), &event);
+        assert_eq!(
+            rendered["text"],
+            "EveBox alert: ET POLICY Something [severity 2] 10.16.1.10 -> 1.1.1.1 on sensor1"
+        );
+        assert_eq!(rendered["event"], event);
+
+        let template = json!({
+            "sid": "{{alert.signature_id}}",
+            "missing": "[{{alert.missing}}]",
+            "list": ["{{ src_ip }}", 1],
+        });
+        let rendered = render(&template, &event);
+        assert_eq!(
+            rendered,
+            json!({
+                "sid": 2013028,
+                "missing": "[]",
+                "list": ["10.16.1.10", 1],
+            })
+        );
+    }
+
+    #[test]
+    fn test_matches_and_throttle() {
+        let mut config = webhook_config("http://localhost");
+        config.query = "src_ip:10.16.1.10".to_string();
+        config.min_severity = Some(2);
+        config.throttle = Some("5m".to_string());
+        let webhook = Webhook::new(config).unwrap();
+
+        let mut event = alert();
+        assert!(webhook.matches(&event));
+        event["alert"]["severity"] = 3.into();
+        assert!(!webhook.matches(&event));
+        event["alert"]["severity"] = 1.into();
+        event["src_ip"] = "10.16.1.11".into();
+        assert!(!webhook.matches(&event));
+
+        let now = Instant::now();
+        assert!(webhook.throttle_ok(&event, now));
+        assert!(!webhook.throttle_ok(&event, now + Duration::from_secs(60)));
+        assert!(webhook.throttle_ok(&event, now + Duration::from_secs(301)));
+        event["alert"]["signature_id"] = 1.into();
+        assert!(webhook.throttle_ok(&event, now + Duration::from_secs(302)));
+    }
+
+    #[tokio::test]
+    async fn test_deliver_with_retry() {
+        let requests = Arc::new(AtomicUsize::new(0));
+        let (body_tx, mut body_rx) = mpsc::channel(1);
+        let app = Router::new().route(
+            "/hook",
+            post({
+                let requests = requests.clone();
+                move |Json(body): Json<serde_json::Value>| async move {
+                    // Fail the first request to exercise the retry.
+                    if requests.fetch_add(1, Ordering::SeqCst) == 0 {
+                        return StatusCode::SERVICE_UNAVAILABLE;
+                    }
+                    body_tx.send(body).await.unwrap();
+                    StatusCode::OK
+                }
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await });
+
+        let mut config = webhook_config(&format!("http://{addr}/hook"));
+        config.template = Some(json!({"sid": "{{alert.signature_id}}"}));
+        let webhook = Arc::new(Webhook::new(config).unwrap());
+        let (tx, rx) = mpsc::channel(QUEUE_SIZE);
+        tokio::spawn(delivery_task(
+            reqwest::Client::new(),
+            rx,
+            Duration::from_millis(10),
+        ));
+        let notifier = Notifier {
+            webhooks: vec![webhook],
+            tx,
+        };
+
+        let mut event = alert();
+        event["tags"] = json!(["evebox.archived"]);
+        notifier.run(&event);
+        notifier.run(&alert());
+
+        let body = tokio::time::timeout(Duration::from_secs(10), body_rx.recv())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(body, json!({"sid": 2013028}));
+        assert_eq!(requests.load(Ordering::SeqCst), 2);
+    }
+}
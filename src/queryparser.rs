@@ -63,6 +63,10 @@ impl QueryParser {
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub(crate) enum QueryValue {
     String(String),
+    /// An unquoted term using full text search syntax, a prefix
+    /// (`evil*`) or NEAR group (`NEAR(evil payload, 5)`). Datastores
+    /// without support for it treat it as a plain string.
+    Fts(String),
     KeyValue(String, String),
     From(datetime::DateTime),
     To(datetime::DateTime),
@@ -86,6 +90,7 @@ pub(crate) fn parse(
     let mut negated = false;
 
     while !ptr.is_empty() {
+        let quoted = ptr.trim_start().starts_with('"');
         (ptr, token) = parse_token(ptr)?;
         if token == "-" || token == "!" {
             negated = true;
@@ -120,10 +125,12 @@ pub(crate) fn parse(
         } else {
             let token = token.trim();
             if !token.is_empty() {
-                elements.push(QueryElement {
-                    negated,
-                    value: QueryValue::String(token.to_string()),
-                });
+                let value = if !quoted && is_fts(token) {
+                    QueryValue::Fts(token.to_string())
+                } else {
+                    QueryValue::String(token.to_string())
+                };
+                elements.push(QueryElement { negated, value });
             }
             negated = false;
         }
@@ -132,6 +139,63 @@ pub(crate) fn parse(
     Ok(elements)
 }
 
+/// Check if an unquoted term uses full text search syntax.
+fn is_fts(token: &str) -> bool {
+    token.starts_with("NEAR(") || (token.len() > 1 && token.ends_with('*'))
+}
+
+/// Replace `assignee:me` with the username of the requesting user.
+pub(crate) fn resolve_assignee_me(elements: &mut [QueryElement], username: Option<&str>) {
+    if let Some(username) = username {
+        for element in elements {
+            if let QueryValue::KeyValue(key, value) = &mut element.value {
+                if key == "assignee" && value == "me" {
+                    *value = username.to_string();
+                }
+            }
+        }
+    }
+}
+
+/// Check if an event matches all the query elements, for evaluating a
+/// query against events in memory instead of in a datastore.
+///
+/// Plain strings are matched case insensitively anywhere in the
+/// event. Key/value elements are matched against the field with dotted
+/// notation, matching any value if the field is an array.
+pub(crate) fn matches(elements: &[QueryElement], event: &serde_json::Value) -> bool {
+    elements
+        .iter()
+        .all(|e| element_matches(e, event) != e.negated)
+}
+
+fn element_matches(element: &QueryElement, event: &serde_json::Value) -> bool {
+    use crate::eve::eve::Eve;
+
+    match &element.value {
+        QueryValue::String(s) | QueryValue::Fts(s) => {
+            event.to_string().to_lowercase().contains(&s.to_lowercase())
+        }
+        QueryValue::KeyValue(k, v) => match k.as_ref() {
+            "tag" => field_matches(&event["tags"], v),
+            "@ip" => field_matches(&event["src_ip"], v) || field_matches(&event["dest_ip"], v),
+            _ => field_matches(k.split('.').fold(event, |value, key| &value[key]), v),
+        },
+        QueryValue::From(ts) => event.datetime().is_some_and(|dt| dt >= *ts),
+        QueryValue::To(ts) => event.datetime().is_some_and(|dt| dt <= *ts),
+    }
+}
+
+fn field_matches(field: &serde_json::Value, value: &str) -> bool {
+    match field {
+        serde_json::Value::String(s) => s == value,
+        serde_json::Value::Number(n) => n.to_string() == value,
+        serde_json::Value::Bool(b) => b.to_string() == value,
+        serde_json::Value::Array(values) => values.iter().any(|v| field_matches(v, value)),
+        _ => false,
+    }
+}
+
 fn parse_token(input: &str) -> IResult<&str, String> {
     // Skip any leading whitespace.
     let (input, _) = multispace0(input)?;
@@ -146,6 +210,10 @@ fn parse_token(input: &str) -> IResult<&str, String> {
         return Ok(parse_quoted_string(input));
     }
 
+    if input.starts_with("NEAR(") {
+        return Ok(parse_near_group(input));
+    }
+
     let (input, token) = take_till(|c| c == ' ' || c == ':')(input)?;
 
     Ok((input, token.to_string()))
@@ -200,10 +268,44 @@ fn parse_quoted_string(input: &str) -> (&str, String) {
     (ptr, string)
 }
 
+// Parse an FTS5 style NEAR group, for example `NEAR(foo bar, 5)`.
+//
+// The group is returned as a single token including the `NEAR(`
+// prefix and closing parenthesis so it is not split on whitespace
+// like other tokens. Quoted strings inside the group are kept as-is.
+fn parse_near_group(input: &str) -> (&str, String) {
+    assert!(input.starts_with("NEAR("));
+    let mut in_quote = false;
+    for (i, c) in input.char_indices() {
+        if c == '"' {
+            in_quote = !in_quote;
+        } else if c == ')' && !in_quote {
+            return (&input[i + 1..], input[..i + 1].to_string());
+        }
+    }
+
+    // No closing parenthesis, consume the rest of the input.
+    ("", input.to_string())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_resolve_assignee_me() {
+        let mut elements = parse("assignee:me -assignee:none", None).unwrap();
+        resolve_assignee_me(&mut elements, Some("alice"));
+        assert_eq!(
+            elements[0].value,
+            QueryValue::KeyValue("assignee".to_string(), "alice".to_string())
+        );
+        assert_eq!(
+            elements[1].value,
+            QueryValue::KeyValue("assignee".to_string(), "none".to_string())
+        );
+    }
+
     #[test]
     fn test_parse() {
         let elements = parse("foobar", None).unwrap();
@@ -269,6 +371,30 @@ mod tests {
         assert!(!elements[0].negated);
     }
 
+    #[test]
+    fn test_matches() {
+        let event = json!({
+            "timestamp": "2024-05-16T09:48:44.000000-0600",
+            "src_ip": "10.16.1.10",
+            "dest_ip": "1.1.1.1",
+            "tags": ["foo"],
+            "alert": {
+                "signature": "ET POLICY Something",
+                "signature_id": 2013028,
+            },
+        });
+        let matches = |query: &str| matches(&parse(query, None).unwrap(), &event);
+        assert!(matches(""));
+        assert!(matches("\"et policy\""));
+        assert!(matches("alert.signature_id:2013028 src_ip:10.16.1.10"));
+        assert!(!matches("alert.signature_id:2013028 -src_ip:10.16.1.10"));
+        assert!(matches("@ip:1.1.1.1 tag:foo"));
+        assert!(!matches("tag:bar"));
+        assert!(!matches("alert.missing:1"));
+        assert!(matches("@from:2024-05-16"));
+        assert!(!matches("@from:2024-05-17"));
+    }
+
     #[test]
     fn test_next_token() {
         let (rem, token) = parse_token("\"foobar\"asdf").unwrap();
@@ -298,6 +424,28 @@ mod tests {
         assert_eq!(token, "");
     }
 
+    #[test]
+    fn test_parse_near() {
+        let elements = parse("NEAR(evil payload, 5) -foo", None).unwrap();
+        assert_eq!(elements.len(), 2);
+        assert_eq!(
+            elements[0].value,
+            QueryValue::Fts("NEAR(evil payload, 5)".to_string())
+        );
+        assert!(elements[1].negated);
+
+        let elements = parse(r#"NEAR("a b" c)"#, None).unwrap();
+        assert_eq!(elements.len(), 1);
+        assert_eq!(
+            elements[0].value,
+            QueryValue::Fts(r#"NEAR("a b" c)"#.to_string())
+        );
+
+        let elements = parse(r#"evil* "evil*""#, None).unwrap();
+        assert_eq!(elements[0].value, QueryValue::Fts("evil*".to_string()));
+        assert_eq!(elements[1].value, QueryValue::String("evil*".to_string()));
+    }
+
     #[test]
     fn test_parse_quoted() {
         let (n, s) = parse_quoted_string(r#""simple""#);
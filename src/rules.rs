@@ -1,16 +1,109 @@
 // SPDX-FileCopyrightText: (C) 2022 Jason Ish <jason@codemonkey.net>
 // SPDX-License-Identifier: MIT
 
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::io::BufRead;
 use std::path::{Path, PathBuf};
 use std::sync::{Arc, RwLock};
 
+use serde::Serialize;
+use suricatax_rule_parser::Parsed;
 use tracing::{debug, error, info};
 
+use crate::datetime::DateTime;
+
+/// A loaded rule.
+#[derive(Debug, Clone, Serialize)]
+pub(crate) struct Rule {
+    pub sid: u64,
+    pub msg: Option<String>,
+    pub classtype: Option<String>,
+    /// References as in the rule, e.g. "url,www.example.com".
+    pub references: Vec<String>,
+    /// Metadata key/values. A key may have multiple values, as with
+    /// Suricata's alert metadata.
+    pub metadata: BTreeMap<String, Vec<String>>,
+    /// False if the rule is commented out.
+    pub enabled: bool,
+    pub filename: PathBuf,
+    pub rule: String,
+}
+
+impl Rule {
+    /// Add the rule metadata, classtype and references to an alert,
+    /// keeping any already logged by Suricata.
+    pub fn enrich(&self, event: &mut serde_json::Value) {
+        let alert = &mut event["alert"];
+        if !self.metadata.is_empty() {
+            if !alert["metadata"].is_object() {
+                alert["metadata"] = json!({});
+            }
+            for (key, values) in &self.metadata {
+                if alert["metadata"][key].is_null() {
+                    alert["metadata"][key] = values.clone().into();
+                }
+            }
+        }
+        if let Some(classtype) = &self.classtype {
+            if alert["classtype"].is_null() {
+                alert["classtype"] = classtype.clone().into();
+            }
+        }
+        if !self.references.is_empty() && alert["references"].is_null() {
+            alert["references"] = self.references.clone().into();
+        }
+    }
+}
+
+/// A loaded rule file.
+#[derive(Debug, Clone, Serialize)]
+pub(crate) struct RuleFile {
+    pub filename: PathBuf,
+    /// Modification time of the file when loaded, in seconds.
+    pub mtime: i64,
+    pub loaded_at: DateTime,
+    pub rules: usize,
+}
+
+/// Parameters for searching rules.
+#[derive(Debug, Default)]
+pub(crate) struct RuleSearch {
+    /// Matched case insensitively against the message, or the SID if
+    /// numeric.
+    pub query: Option<String>,
+    pub classtype: Option<String>,
+    pub filename: Option<String>,
+}
+
+impl RuleSearch {
+    fn matches(&self, rule: &Rule) -> bool {
+        if let Some(classtype) = &self.classtype {
+            if rule.classtype.as_deref() != Some(classtype.as_str()) {
+                return false;
+            }
+        }
+        if let Some(filename) = &self.filename {
+            if rule.filename.to_string_lossy() != filename.as_str() {
+                return false;
+            }
+        }
+        if let Some(query) = &self.query {
+            let query = query.to_lowercase();
+            let msg_matches = rule
+                .msg
+                .as_ref()
+                .is_some_and(|msg| msg.to_lowercase().contains(&query));
+            if !msg_matches && rule.sid.to_string() != query {
+                return false;
+            }
+        }
+        true
+    }
+}
+
 struct Inner {
-    map: HashMap<u64, String>,
-    files: HashMap<PathBuf, i64>,
+    map: HashMap<u64, Rule>,
+    files: HashMap<PathBuf, RuleFile>,
 }
 
 pub(crate) fn read_next_rule(input: &mut dyn BufRead) -> Result<Option<String>, std::io::Error> {
@@ -35,15 +128,26 @@ pub(crate) fn read_next_rule(input: &mut dyn BufRead) -> Result<Option<String>,
 }
 
 impl Inner {
-    fn load_path(&mut self, path: &Path) {
+    fn load_path(&mut self, path: &Path, mtime: i64) {
+        let mut count = 0;
         if let Ok(file) = std::fs::File::open(path) {
             let mut reader = std::io::BufReader::new(file);
             while let Ok(Some(line)) = read_next_rule(&mut reader) {
-                if let Some(rule) = parse_line(&line) {
-                    self.map.insert(rule.0, rule.1);
+                if let Some(rule) = parse_line(&line, path) {
+                    self.map.insert(rule.sid, rule);
+                    count += 1;
                 }
             }
         }
+        self.files.insert(
+            path.to_path_buf(),
+            RuleFile {
+                filename: path.to_path_buf(),
+                mtime,
+                loaded_at: DateTime::now(),
+                rules: count,
+            },
+        );
     }
 }
 
@@ -76,11 +180,48 @@ impl RuleMap {
     pub fn find_by_sid(&self, sid: u64) -> Option<String> {
         let inner = self.inner.read().unwrap();
         if let Some(rule) = inner.map.get(&sid) {
-            return Some(rule.to_string());
+            return Some(rule.rule.to_string());
         }
         None
     }
 
+    pub fn get(&self, sid: u64) -> Option<Rule> {
+        self.inner.read().unwrap().map.get(&sid).cloned()
+    }
+
+    /// Enrich an alert with the metadata of its rule, if known.
+    pub fn enrich(&self, sid: u64, event: &mut serde_json::Value) -> bool {
+        match self.inner.read().unwrap().map.get(&sid) {
+            Some(rule) => {
+                rule.enrich(event);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Return the matching rules ordered by SID, up to `limit`, along
+    /// with the total number of matches.
+    pub fn search(&self, search: &RuleSearch, limit: usize) -> (Vec<Rule>, usize) {
+        let inner = self.inner.read().unwrap();
+        let mut rules: Vec<&Rule> = inner
+            .map
+            .values()
+            .filter(|rule| search.matches(rule))
+            .collect();
+        let total = rules.len();
+        rules.sort_by_key(|rule| rule.sid);
+        let rules = rules.into_iter().take(limit).cloned().collect();
+        (rules, total)
+    }
+
+    pub fn files(&self) -> Vec<RuleFile> {
+        let inner = self.inner.read().unwrap();
+        let mut files: Vec<RuleFile> = inner.files.values().cloned().collect();
+        files.sort_by(|a, b| a.filename.cmp(&b.filename));
+        files
+    }
+
     pub fn rescan(&self) {
         for path in &self.paths.clone() {
             match glob::glob(path) {
@@ -102,15 +243,15 @@ impl RuleMap {
                                         filetime::FileTime::from_last_modification_time(&meta)
                                             .unix_seconds();
                                     let mut inner = self.inner.write().unwrap();
-                                    let prev = inner.files.insert(path.clone(), mtime);
+                                    let prev = inner.files.get(&path).map(|file| file.mtime);
                                     if let Some(prev) = prev {
                                         if mtime > prev {
                                             info!("Reloading rules from {:?}", path);
-                                            (*inner).load_path(&path);
+                                            (*inner).load_path(&path, mtime);
                                         }
                                     } else {
                                         info!("Loading rules from file {:?}", path);
-                                        (*inner).load_path(&path);
+                                        (*inner).load_path(&path, mtime);
                                     }
                                 }
                             },
@@ -122,28 +263,57 @@ impl RuleMap {
     }
 }
 
-fn parse_line(line: &str) -> Option<(u64, String)> {
-    let mut offset = 0;
-    if line.starts_with('#') {
-        offset = 1;
-    }
+/// Parse a line as a rule, a rule that is commented out is parsed as
+/// a disabled rule.
+fn parse_line(line: &str, filename: &Path) -> Option<Rule> {
+    let enabled = !line.starts_with('#');
+    let original = line.strip_prefix('#').unwrap_or(line);
 
-    let original = &line[offset..];
+    let parsed = match suricatax_rule_parser::parse_rule(original) {
+        Ok(parsed) => parsed,
+        Err(err) => {
+            debug!("Failed to parse as rule: {:?}: {}", err, line);
+            return None;
+        }
+    };
 
-    match suricatax_rule_parser::parse_rule(original) {
-        Ok(rule) => {
-            for option in &rule.options {
-                if let suricatax_rule_parser::Parsed::Sid(sid) = option.parsed {
-                    return Some((sid, original.to_string()));
+    let mut sid = None;
+    let mut msg = None;
+    let mut classtype = None;
+    let mut references = vec![];
+    let mut metadata: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    for option in parsed.options {
+        match option.parsed {
+            Parsed::Sid(v) => sid = Some(v),
+            Parsed::Message(v) => msg = Some(v),
+            Parsed::Classtype(v) => classtype = Some(v),
+            Parsed::Reference(v) => references.push(v),
+            Parsed::Metadata(entries) => {
+                // Each entry is a key and value separated by
+                // whitespace, keys may repeat.
+                for entry in entries {
+                    if let Some((key, value)) = entry.trim().split_once(char::is_whitespace) {
+                        metadata
+                            .entry(key.to_string())
+                            .or_default()
+                            .push(value.trim().to_string());
+                    }
                 }
             }
-        }
-        Err(err) => {
-            debug!("Failed to parse as rule: {:?}: {}", err, line);
+            _ => {}
         }
     }
 
-    None
+    Some(Rule {
+        sid: sid?,
+        msg,
+        classtype,
+        references,
+        metadata,
+        enabled,
+        filename: filename.to_path_buf(),
+        rule: original.to_string(),
+    })
 }
 
 pub(crate) fn load_rules(filenames: &[String]) -> RuleMap {
@@ -185,3 +355,88 @@ pub(crate) fn watch_rules(rulemap: Arc<RuleMap>) {
         }
     });
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RULE: &str = r#"alert http any any -> any any (msg:"ET POLICY Test"; flow:established,to_server; classtype:policy-violation; sid:2000001; rev:1;)"#;
+
+    const METADATA_RULE: &str = r#"alert tcp any any -> any any (msg:"ET EXPLOIT Test"; reference:cve,2021-44228; reference:url,www.example.com; classtype:attempted-admin; sid:2034647; rev:2; metadata:attack_target Server, created_at 2021_12_10, mitre_technique_id T1190, mitre_technique_id T1203; metadata:signature_severity Major;)"#;
+
+    #[test]
+    fn test_rule_metadata() {
+        let rule = parse_line(METADATA_RULE, Path::new("test.rules")).unwrap();
+        assert_eq!(rule.sid, 2034647);
+        assert!(rule.enabled);
+        assert_eq!(
+            rule.references,
+            vec!["cve,2021-44228", "url,www.example.com"]
+        );
+        assert_eq!(rule.classtype.as_deref(), Some("attempted-admin"));
+        assert_eq!(rule.metadata["mitre_technique_id"], vec!["T1190", "T1203"]);
+        assert_eq!(rule.metadata["created_at"], vec!["2021_12_10"]);
+        assert_eq!(rule.metadata["signature_severity"], vec!["Major"]);
+
+        // Metadata logged by Suricata is kept.
+        let mut event = json!({
+            "event_type": "alert",
+            "alert": {
+                "signature_id": 2034647,
+                "metadata": {"signature_severity": ["Critical"]},
+            },
+        });
+        rule.enrich(&mut event);
+        assert_eq!(
+            event["alert"]["metadata"]["mitre_technique_id"],
+            json!(["T1190", "T1203"])
+        );
+        assert_eq!(
+            event["alert"]["metadata"]["signature_severity"],
+            json!(["Critical"])
+        );
+        assert_eq!(event["alert"]["classtype"], "attempted-admin");
+        assert_eq!(
+            event["alert"]["references"],
+            json!(["cve,2021-44228", "url,www.example.com"])
+        );
+    }
+
+    #[test]
+    fn test_search() {
+        let map = RuleMap::new();
+        {
+            let mut inner = map.inner.write().unwrap();
+            let filename = Path::new("/etc/suricata/rules/test.rules");
+            let other = r#"# alert dns any any -> any any (msg:"ET DNS Query"; classtype:bad-unknown; sid:2000002; rev:1;)"#;
+            for line in [RULE, other] {
+                let rule = parse_line(line, filename).unwrap();
+                inner.map.insert(rule.sid, rule);
+            }
+        }
+
+        let rule = map.get(2000001).unwrap();
+        assert_eq!(rule.msg.as_deref(), Some("ET POLICY Test"));
+        assert_eq!(rule.classtype.as_deref(), Some("policy-violation"));
+        assert!(!map.get(2000002).unwrap().enabled);
+
+        let search = |query: Option<&str>, classtype: Option<&str>| {
+            let search = RuleSearch {
+                query: query.map(String::from),
+                classtype: classtype.map(String::from),
+                filename: None,
+            };
+            let (rules, total) = map.search(&search, 10);
+            assert_eq!(rules.len(), total);
+            rules.iter().map(|rule| rule.sid).collect::<Vec<_>>()
+        };
+        assert_eq!(search(None, None), vec![2000001, 2000002]);
+        assert_eq!(search(Some("et dns"), None), vec![2000002]);
+        assert_eq!(search(Some("2000001"), None), vec![2000001]);
+        assert_eq!(search(None, Some("bad-unknown")), vec![2000002]);
+        assert!(search(Some("et dns"), Some("policy-violation")).is_empty());
+
+        let (rules, total) = map.search(&RuleSearch::default(), 1);
+        assert_eq!((rules.len(), total), (1, 2));
+    }
+}
@@ -0,0 +1,195 @@
+// SPDX-FileCopyrightText: (C) 2024 Jason Ish <jason@codemonkey.net>
+// SPDX-License-Identifier: MIT
+
+//! Sensor heartbeat tracking.
+//!
+//! Received events are counted per sensor in memory, and written to
+//! the configuration database periodically. Sensors that haven't sent
+//! an event within the silent threshold are flagged, logged and
+//! notified through any webhooks with sensor notifications enabled.
+
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+use std::ops::Sub;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use serde::Deserialize;
+use tracing::{error, info, warn};
+
+use crate::datetime::DateTime;
+use crate::notifications::Notifier;
+use crate::sqlite::configrepo::sensors::SensorActivity;
+use crate::sqlite::configrepo::ConfigRepo;
+
+/// How often sensor activity is written to the database.
+const UPDATE_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Sensor configuration, from the `sensors` section of the server
+/// configuration.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub(crate) struct SensorsConfig {
+    /// Flag a sensor as silent if no events have been received from
+    /// it in this period, e.g. "15m".
+    #[serde(default = "default_silent_threshold")]
+    pub silent_threshold: String,
+}
+
+impl Default for SensorsConfig {
+    fn default() -> Self {
+        Self {
+            silent_threshold: default_silent_threshold(),
+        }
+    }
+}
+
+fn default_silent_threshold() -> String {
+    "15m".to_string()
+}
+
+/// Records sensor activity as events are received.
+#[derive(Clone, Default)]
+pub(crate) struct SensorTracker {
+    activity: Arc<Mutex<HashMap<String, SensorActivity>>>,
+}
+
+impl SensorTracker {
+    pub fn run(&self, event: &serde_json::Value) {
+        self.record(event, None);
+    }
+
+    /// Record an event, optionally with the version of the agent that
+    /// sent it.
+    pub fn record(&self, event: &serde_json::Value, agent_version: Option<&str>) {
+        let Some(host) = event["host"].as_str() else {
+            return;
+        };
+        let now = DateTime::now();
+        let mut activity = self.activity.lock().unwrap();
+        let sensor = activity
+            .entry(host.to_string())
+            .or_insert_with(|| SensorActivity {
+                name: host.to_string(),
+                first_seen: now.clone(),
+                last_seen: now.clone(),
+                events: 0,
+                agent_version: None,
+            });
+        sensor.last_seen = now;
+        sensor.events += 1;
+        if let Some(agent_version) = agent_version {
+            sensor.agent_version = Some(agent_version.to_string());
+        }
+    }
+
+    fn take(&self) -> Vec<SensorActivity> {
+        let mut activity = self.activity.lock().unwrap();
+        activity.drain().map(|(_, sensor)| sensor).collect()
+    }
+
+    /// Put back activity taken with `take` that could not be written,
+    /// merging it with the activity recorded since.
+    fn restore(&self, taken: Vec<SensorActivity>) {
+        let mut activity = self.activity.lock().unwrap();
+        for sensor in taken {
+            match activity.entry(sensor.name.clone()) {
+                Entry::Vacant(entry) => {
+                    entry.insert(sensor);
+                }
+                Entry::Occupied(mut entry) => {
+                    let current = entry.get_mut();
+                    current.events += sensor.events;
+                    if sensor.first_seen < current.first_seen {
+                        current.first_seen = sensor.first_seen;
+                    }
+                    if sensor.last_seen > current.last_seen {
+                        current.last_seen = sensor.last_seen;
+                    }
+                    if current.agent_version.is_none() {
+                        current.agent_version = sensor.agent_version;
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Start the task writing sensor activity to the database and
+/// checking for silent sensors.
+pub(crate) fn start(
+    config_repo: Arc<ConfigRepo>,
+    tracker: SensorTracker,
+    notifier: Option<Notifier>,
+    config: SensorsConfig,
+) -> anyhow::Result<()> {
+    let threshold = humantime::parse_duration(&config.silent_threshold)
+        .map_err(|err| anyhow!("bad sensors.silent-threshold: {}", err))?;
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(UPDATE_INTERVAL);
+        interval.tick().await;
+        // The activity covers the time since the last successful
+        // update, used for the event rate.
+        let mut last_update = Instant::now();
+        loop {
+            interval.tick().await;
+            let activity = tracker.take();
+            if let Err(err) = config_repo
+                .update_sensors(&activity, last_update.elapsed().as_secs_f64())
+                .await
+            {
+                error!("Failed to update sensor activity: {}", err);
+                tracker.restore(activity);
+                continue;
+            }
+            last_update = Instant::now();
+
+            let last_seen = DateTime::now().sub(threshold);
+            match config_repo.mark_silent_sensors(&last_seen).await {
+                Ok(sensors) => {
+                    for sensor in sensors {
+                        warn!(
+                            "Sensor {} has not sent events since {}",
+                            sensor.name,
+                            sensor.last_seen.to_rfc3339_utc()
+                        );
+                        if let Some(notifier) = &notifier {
+                            notifier.sensor_silent(&sensor);
+                        }
+                    }
+                }
+                Err(err) => {
+                    error!("Failed to check for silent sensors: {}", err);
+                }
+            }
+        }
+    });
+    info!(
+        "Tracking sensor activity, silent threshold {}",
+        humantime::format_duration(threshold)
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_restore() {
+        let tracker = SensorTracker::default();
+        let event = json!({"host": "sensor1"});
+        tracker.record(&event, Some("0.19.0"));
+        tracker.record(&event, None);
+        let taken = tracker.take();
+        let first_seen = taken[0].first_seen.clone();
+
+        tracker.record(&event, None);
+        tracker.restore(taken);
+        let activity = tracker.take();
+        assert_eq!(activity.len(), 1);
+        assert_eq!(activity[0].events, 3);
+        assert_eq!(activity[0].first_seen, first_seen);
+        assert_eq!(activity[0].agent_version.as_deref(), Some("0.19.0"));
+    }
+}
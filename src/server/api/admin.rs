@@ -4,14 +4,27 @@
 use std::sync::Arc;
 
 use anyhow::Result;
-use axum::{Extension, Json};
+use axum::{Extension, Form, Json};
+use serde::Deserialize;
 use tracing::error;
 
 use crate::prelude::*;
+use crate::server::session::Session;
 use crate::server::{main::SessionExtractor, ServerContext};
+use crate::sqlite::configrepo::audit::AuditQuery;
 
+use super::util::parse_duration;
 use super::ApiError;
 
+/// Return an error unless the session is an administrator. All users
+/// are administrators when authentication is disabled.
+pub(super) fn require_admin(context: &ServerContext, session: &Session) -> Result<(), ApiError> {
+    if context.config.authentication_required && !session.admin {
+        return Err(ApiError::Forbidden);
+    }
+    Ok(())
+}
+
 pub(super) async fn update_ja4db(
     Extension(context): Extension<Arc<ServerContext>>,
     _session: SessionExtractor,
@@ -38,3 +51,47 @@ async fn do_update(context: Arc<ServerContext>) -> Result<Json<serde_json::Value
     });
     Ok(Json(response))
 }
+
+#[derive(Debug, Deserialize)]
+pub(super) struct AuditParams {
+    /// Only return entries newer than this duration, e.g. "24h".
+    time_range: Option<String>,
+    from: Option<String>,
+    to: Option<String>,
+    tz_offset: Option<String>,
+    username: Option<String>,
+    action: Option<String>,
+    limit: Option<u64>,
+}
+
+pub(super) async fn audit(
+    Extension(context): Extension<Arc<ServerContext>>,
+    SessionExtractor(session): SessionExtractor,
+    Form(params): Form<AuditParams>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    require_admin(&context, &session)?;
+    let tz_offset = params.tz_offset.as_deref();
+    let parse_datetime = |value: &str| {
+        crate::datetime::parse(value, tz_offset)
+            .map_err(|err| ApiError::bad_request(format!("bad timestamp {value}: {err}")))
+    };
+    let mut from = params.from.as_deref().map(parse_datetime).transpose()?;
+    if let Some(time_range) = &params.time_range {
+        let duration = parse_duration(time_range)
+            .map_err(|err| ApiError::bad_request(format!("time_range: {err}")))?;
+        let duration = chrono::Duration::from_std(duration)
+            .map_err(|_| ApiError::bad_request("time_range"))?;
+        from = Some(crate::datetime::DateTime::now().sub(duration));
+    }
+    let query = AuditQuery {
+        from,
+        to: params.to.as_deref().map(parse_datetime).transpose()?,
+        username: params.username,
+        action: params.action,
+        limit: params.limit,
+    };
+    let entries = context.config_repo.get_audit(&query).await?;
+    Ok(Json(json!({
+        "entries": entries,
+    })))
+}
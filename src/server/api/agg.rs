@@ -40,7 +40,7 @@ fn default_order() -> String {
 }
 
 pub(crate) async fn agg(
-    _session: SessionExtractor,
+    SessionExtractor(session): SessionExtractor,
     State(context): State<Arc<ServerContext>>,
     Form(form): Form<AggParams>,
 ) -> Result<impl IntoResponse, ApiError> {
@@ -52,6 +52,7 @@ pub(crate) async fn agg(
         .map(|qs| queryparser::parse(&qs, default_tz_offset))
         .transpose()?
         .unwrap_or_default();
+    queryparser::resolve_assignee_me(&mut query_string, session.username.as_deref());
 
     let min_timestamp = parse_duration(&form.time_range)
         .map(|d| chrono::Utc::now().sub(d))
@@ -0,0 +1,89 @@
+// SPDX-FileCopyrightText: (C) 2024 Jason Ish <jason@codemonkey.net>
+// SPDX-License-Identifier: MIT
+
+//! Triage actions applied to all alerts matching a query.
+
+use super::genericquery::TimeRange;
+use super::ApiError;
+use crate::eventrepo::{BulkAction, BulkActionParams};
+use crate::queryparser;
+use crate::queryparser::{QueryElement, QueryValue};
+use crate::server::{main::SessionExtractor, ServerContext};
+use axum::extract::{Path, State};
+use axum::response::IntoResponse;
+use axum::Json;
+use serde::Deserialize;
+use serde_json::json;
+use std::sync::Arc;
+use tracing::info;
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct BulkRequest {
+    query_string: Option<String>,
+    time_range: Option<TimeRange>,
+    tz_offset: Option<String>,
+    /// Only return the number of alerts that would be updated.
+    #[serde(default)]
+    dry_run: bool,
+    /// The comment to add, required for the comment action.
+    comment: Option<String>,
+}
+
+pub(crate) async fn handler(
+    SessionExtractor(session): SessionExtractor,
+    State(context): State<Arc<ServerContext>>,
+    Path(action): Path<String>,
+    Json(request): Json<BulkRequest>,
+) -> Result<impl IntoResponse, ApiError> {
+    let action = match action.as_str() {
+        "archive" => BulkAction::Archive,
+        "unarchive" => BulkAction::Unarchive,
+        "escalate" => BulkAction::Escalate,
+        "de-escalate" => BulkAction::Deescalate,
+        "comment" => match request.comment {
+            Some(comment) if !comment.trim().is_empty() => BulkAction::Comment(comment),
+            _ => return Err(ApiError::bad_request("comment")),
+        },
+        _ => return Err(ApiError::bad_request("action")),
+    };
+
+    let mut query_string = request
+        .query_string
+        .as_ref()
+        .map(|qs| queryparser::parse(qs, request.tz_offset.as_deref()))
+        .transpose()?
+        .unwrap_or_default();
+    queryparser::resolve_assignee_me(&mut query_string, session.username.as_deref());
+
+    if let Some(time_range) = &request.time_range {
+        let min_timestamp = time_range.parse_time_range_as_min_timestamp()?;
+        query_string.push(QueryElement {
+            negated: false,
+            value: QueryValue::From(min_timestamp),
+        });
+    }
+
+    let params = BulkActionParams {
+        query_string,
+        dry_run: request.dry_run,
+    };
+
+    if !params.dry_run {
+        info!(
+            ?action,
+            username = ?session.username,
+            query_string = ?request.query_string,
+            "Applying bulk action"
+        );
+    }
+
+    let count = context
+        .datastore
+        .bulk_action(&params, &action, session)
+        .await?;
+
+    Ok(Json(json!({
+        "count": count,
+        "dry_run": params.dry_run,
+    })))
+}
@@ -0,0 +1,206 @@
+// SPDX-FileCopyrightText: (C) 2024 Jason Ish <jason@codemonkey.net>
+// SPDX-License-Identifier: MIT
+
+//! API endpoints for cases (investigations).
+
+use std::sync::Arc;
+
+use axum::extract::{Path, State};
+use axum::http::{header, StatusCode};
+use axum::response::IntoResponse;
+use axum::routing::{delete, get, post};
+use axum::{Json, Router};
+use serde::Deserialize;
+use tracing::info;
+
+use super::ApiError;
+use crate::server::{main::SessionExtractor, ServerContext};
+use crate::sqlite::configrepo::cases::CaseFields;
+
+pub(crate) fn router() -> Router<Arc<ServerContext>> {
+    Router::new()
+        .route("/", get(list_cases).post(create_case))
+        .route("/:id", get(get_case).put(update_case).delete(delete_case))
+        .route("/:id/notes", post(add_note))
+        .route("/:id/events", post(add_events))
+        .route("/:id/events/:event_id", delete(remove_event))
+        .route("/:id/export", get(export_case))
+}
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct NoteRequest {
+    note: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct EventsRequest {
+    event_ids: Vec<String>,
+}
+
+async fn list_cases(
+    _session: SessionExtractor,
+    State(context): State<Arc<ServerContext>>,
+) -> Result<impl IntoResponse, ApiError> {
+    let cases = context.config_repo.get_cases().await?;
+    Ok(Json(json!({
+        "cases": cases,
+    })))
+}
+
+async fn create_case(
+    SessionExtractor(session): SessionExtractor,
+    State(context): State<Arc<ServerContext>>,
+    Json(fields): Json<CaseFields>,
+) -> Result<impl IntoResponse, ApiError> {
+    if fields
+        .title
+        .as_deref()
+        .map(str::trim)
+        .unwrap_or_default()
+        .is_empty()
+    {
+        return Err(ApiError::bad_request("title"));
+    }
+    let id = context
+        .config_repo
+        .create_case(fields, session.username.as_deref())
+        .await?;
+    info!("Created case {} by user {:?}", id, session.username);
+    let case = context.config_repo.get_case(id).await?;
+    Ok((StatusCode::CREATED, Json(case)))
+}
+
+async fn get_case(
+    _session: SessionExtractor,
+    State(context): State<Arc<ServerContext>>,
+    Path(id): Path<i64>,
+) -> Result<impl IntoResponse, ApiError> {
+    let case = context.config_repo.get_case(id).await?;
+    let notes = context.config_repo.get_case_notes(id).await?;
+    let events = context.config_repo.get_case_events(id).await?;
+    Ok(Json(json!({
+        "case": case,
+        "notes": notes,
+        "events": events,
+    })))
+}
+
+async fn update_case(
+    _session: SessionExtractor,
+    State(context): State<Arc<ServerContext>>,
+    Path(id): Path<i64>,
+    Json(fields): Json<CaseFields>,
+) -> Result<impl IntoResponse, ApiError> {
+    if let Some(title) = &fields.title {
+        if title.trim().is_empty() {
+            return Err(ApiError::bad_request("title"));
+        }
+    }
+    context.config_repo.update_case(id, fields).await?;
+    let case = context.config_repo.get_case(id).await?;
+    Ok(Json(case))
+}
+
+async fn delete_case(
+    SessionExtractor(session): SessionExtractor,
+    State(context): State<Arc<ServerContext>>,
+    Path(id): Path<i64>,
+) -> Result<impl IntoResponse, ApiError> {
+    context.config_repo.delete_case(id).await?;
+    info!("Deleted case {} by user {:?}", id, session.username);
+    Ok(StatusCode::OK)
+}
+
+async fn add_note(
+    SessionExtractor(session): SessionExtractor,
+    State(context): State<Arc<ServerContext>>,
+    Path(id): Path<i64>,
+    Json(request): Json<NoteRequest>,
+) -> Result<impl IntoResponse, ApiError> {
+    if request.note.trim().is_empty() {
+        return Err(ApiError::bad_request("note"));
+    }
+    let note_id = context
+        .config_repo
+        .add_case_note(id, session.username.as_deref(), &request.note)
+        .await?;
+    Ok(Json(json!({
+        "id": note_id,
+    })))
+}
+
+/// Link events to a case. Each event must exist in the datastore.
+async fn add_events(
+    SessionExtractor(session): SessionExtractor,
+    State(context): State<Arc<ServerContext>>,
+    Path(id): Path<i64>,
+    Json(request): Json<EventsRequest>,
+) -> Result<impl IntoResponse, ApiError> {
+    if request.event_ids.is_empty() {
+        return Err(ApiError::bad_request("event_ids"));
+    }
+    // Make sure the case exists before looking up events.
+    context.config_repo.get_case(id).await?;
+    for event_id in &request.event_ids {
+        if context
+            .datastore
+            .get_event_by_id(event_id.clone())
+            .await?
+            .is_none()
+        {
+            return Err(ApiError::bad_request(format!(
+                "event not found: {event_id}"
+            )));
+        }
+    }
+    let added = context
+        .config_repo
+        .add_case_events(id, &request.event_ids, session.username.as_deref())
+        .await?;
+    Ok(Json(json!({
+        "added": added,
+    })))
+}
+
+async fn remove_event(
+    _session: SessionExtractor,
+    State(context): State<Arc<ServerContext>>,
+    Path((id, event_id)): Path<(i64, String)>,
+) -> Result<impl IntoResponse, ApiError> {
+    let removed = context.config_repo.remove_case_event(id, &event_id).await?;
+    Ok(Json(json!({
+        "removed": removed,
+    })))
+}
+
+/// Export a case as JSON, including the full source of each linked
+/// event. Events no longer in the datastore, for example due to
+/// retention, are exported with a null event.
+async fn export_case(
+    _session: SessionExtractor,
+    State(context): State<Arc<ServerContext>>,
+    Path(id): Path<i64>,
+) -> Result<impl IntoResponse, ApiError> {
+    let case = context.config_repo.get_case(id).await?;
+    let notes = context.config_repo.get_case_notes(id).await?;
+    let mut events = vec![];
+    for linked in context.config_repo.get_case_events(id).await? {
+        let event = context
+            .datastore
+            .get_event_by_id(linked.event_id.clone())
+            .await?;
+        events.push(json!({
+            "event_id": linked.event_id,
+            "added_by": linked.added_by,
+            "added_at": linked.added_at,
+            "event": event,
+        }));
+    }
+    let export = json!({
+        "case": case,
+        "notes": notes,
+        "events": events,
+    });
+    let disposition = format!("attachment; filename=\"case-{id}.json\"");
+    Ok(([(header::CONTENT_DISPOSITION, disposition)], Json(export)))
+}
@@ -0,0 +1,221 @@
+// SPDX-FileCopyrightText: (C) 2024 Jason Ish <jason@codemonkey.net>
+// SPDX-License-Identifier: MIT
+
+//! Streaming export of events matching a query.
+//!
+//! Events are fetched from the datastore a page at a time and written
+//! to the response as they are received, so an export of any size
+//! does not need to be buffered in memory.
+
+use super::genericquery::TimeRange;
+use super::ApiError;
+use crate::eventrepo::{DatastoreError, EventCursor, EventExportParams};
+use crate::queryparser;
+use crate::queryparser::{QueryElement, QueryValue};
+use crate::server::{main::SessionExtractor, ServerContext};
+use axum::body::Body;
+use axum::extract::State;
+use axum::http::header;
+use axum::response::IntoResponse;
+use axum::Form;
+use serde::Deserialize;
+use std::sync::Arc;
+use tracing::error;
+
+/// Number of events to fetch from the datastore per page.
+const PAGE_SIZE: u64 = 1000;
+
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum ExportFormat {
+    Ndjson,
+    Csv,
+}
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct ExportParams {
+    #[serde(default = "default_format")]
+    format: ExportFormat,
+    /// Comma separated list of fields to include as CSV columns.
+    columns: Option<String>,
+    query_string: Option<String>,
+    time_range: Option<TimeRange>,
+    event_type: Option<String>,
+    tz_offset: Option<String>,
+}
+
+fn default_format() -> ExportFormat {
+    ExportFormat::Ndjson
+}
+
+const DEFAULT_CSV_COLUMNS: &[&str] = &[
+    "timestamp",
+    "event_type",
+    "src_ip",
+    "src_port",
+    "dest_ip",
+    "dest_port",
+    "proto",
+    "app_proto",
+];
+
+struct ExportState {
+    context: Arc<ServerContext>,
+    params: EventExportParams,
+    format: ExportFormat,
+    columns: Vec<String>,
+    cursor: Option<EventCursor>,
+    first: bool,
+    done: bool,
+}
+
+pub(crate) async fn handler(
+    SessionExtractor(session): SessionExtractor,
+    State(context): State<Arc<ServerContext>>,
+    Form(form): Form<ExportParams>,
+) -> Result<impl IntoResponse, ApiError> {
+    let default_tz_offset = form.tz_offset.as_deref();
+    let mut query_string = form
+        .query_string
+        .as_ref()
+        .map(|qs| queryparser::parse(qs, default_tz_offset))
+        .transpose()?
+        .unwrap_or_default();
+    queryparser::resolve_assignee_me(&mut query_string, session.username.as_deref());
+
+    if let Some(time_range) = &form.time_range {
+        let min_timestamp = time_range.parse_time_range_as_min_timestamp()?;
+        query_string.push(QueryElement {
+            negated: false,
+            value: QueryValue::From(min_timestamp),
+        });
+    }
+
+    let columns: Vec<String> = match &form.columns {
+        Some(columns) => columns
+            .split(',')
+            .map(|c| c.trim().to_string())
+            .filter(|c| !c.is_empty())
+            .collect(),
+        None => DEFAULT_CSV_COLUMNS.iter().map(|c| c.to_string()).collect(),
+    };
+    if form.format == ExportFormat::Csv && columns.is_empty() {
+        return Err(ApiError::bad_request("columns"));
+    }
+
+    let state = ExportState {
+        context,
+        params: EventExportParams {
+            event_type: form.event_type,
+            query_string,
+            size: PAGE_SIZE,
+        },
+        format: form.format,
+        columns,
+        cursor: None,
+        first: true,
+        done: false,
+    };
+
+    let stream = futures::stream::unfold(state, |mut state| async move {
+        if state.done {
+            return None;
+        }
+
+        let mut buf = String::new();
+        if state.first && state.format == ExportFormat::Csv {
+            buf.push_str(&csv_row(state.columns.iter().map(String::as_str)));
+        }
+
+        state.first = false;
+
+        let cursor = state.cursor.take();
+        let page = match state
+            .context
+            .datastore
+            .export_page(&state.params, cursor)
+            .await
+        {
+            Ok(page) => page,
+            Err(err) => {
+                error!("Failed to fetch page of events for export: {:?}", err);
+                state.done = true;
+                return Some((Err(err), state));
+            }
+        };
+
+        for event in &page.events {
+            let source = &event["_source"];
+            match state.format {
+                ExportFormat::Ndjson => {
+                    buf.push_str(&source.to_string());
+                    buf.push('\n');
+                }
+                ExportFormat::Csv => {
+                    let values = state
+                        .columns
+                        .iter()
+                        .map(|column| csv_value(lookup(source, column)))
+                        .collect::<Vec<String>>();
+                    buf.push_str(&csv_row(values.iter().map(String::as_str)));
+                }
+            }
+        }
+
+        state.cursor = page.cursor;
+        if state.cursor.is_none() {
+            state.done = true;
+        }
+
+        Some((Ok::<String, DatastoreError>(buf), state))
+    });
+
+    let (content_type, filename) = match form.format {
+        ExportFormat::Ndjson => ("application/x-ndjson", "evebox-export.ndjson"),
+        ExportFormat::Csv => ("text/csv", "evebox-export.csv"),
+    };
+
+    Ok((
+        [
+            (header::CONTENT_TYPE, content_type.to_string()),
+            (
+                header::CONTENT_DISPOSITION,
+                format!("attachment; filename=\"{filename}\""),
+            ),
+        ],
+        Body::from_stream(stream),
+    ))
+}
+
+/// Lookup a value in an event by a dotted field name.
+fn lookup<'a>(event: &'a serde_json::Value, field: &str) -> &'a serde_json::Value {
+    let mut value = event;
+    for part in field.split('.') {
+        value = &value[part];
+    }
+    value
+}
+
+fn csv_value(value: &serde_json::Value) -> String {
+    match value {
+        serde_json::Value::Null => String::new(),
+        serde_json::Value::String(s) => s.to_string(),
+        _ => value.to_string(),
+    }
+}
+
+/// Format a CSV row, quoting values as needed.
+fn csv_row<'a>(values: impl Iterator<Item = &'a str>) -> String {
+    let mut row = values
+        .map(|value| {
+            if value.contains([',', '"', '\n', '\r']) {
+                format!("\"{}\"", value.replace('"', "\"\""))
+            } else {
+                value.to_string()
+            }
+        })
+        .collect::<Vec<String>>()
+        .join(",");
+    row.push_str("\r\n");
+    row
+}
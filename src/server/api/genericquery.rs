@@ -23,6 +23,11 @@ pub(crate) struct GenericQuery {
     pub interval: Option<String>,
     pub tz_offset: Option<String>,
     pub sensor: Option<String>,
+    /// Comma separated list of keys to group alerts on.
+    pub group_by: Option<String>,
+    /// Opaque cursor for paging through events. An empty value starts
+    /// paging from the first event.
+    pub cursor: Option<String>,
 }
 
 impl TimeRange {
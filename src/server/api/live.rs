@@ -0,0 +1,94 @@
+// SPDX-FileCopyrightText: (C) 2024 Jason Ish <jason@codemonkey.net>
+// SPDX-License-Identifier: MIT
+
+//! Live tail of newly imported events as Server-Sent Events.
+//!
+//! Each event is sent as a message with the event as JSON data. A
+//! client that can't keep up misses events, and is sent a "lagged"
+//! event with the number of events it missed.
+
+use super::ApiError;
+use crate::queryparser;
+use crate::queryparser::QueryElement;
+use crate::server::{main::SessionExtractor, ServerContext};
+use axum::extract::State;
+use axum::response::sse::{Event, KeepAlive, Sse};
+use axum::response::IntoResponse;
+use axum::Form;
+use serde::Deserialize;
+use std::sync::Arc;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::broadcast::Receiver;
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct LiveParams {
+    query_string: Option<String>,
+    /// Comma separated list of event types.
+    event_type: Option<String>,
+    tz_offset: Option<String>,
+}
+
+struct LiveState {
+    rx: Receiver<Arc<serde_json::Value>>,
+    query_string: Vec<QueryElement>,
+    event_types: Vec<String>,
+}
+
+impl LiveState {
+    fn matches(&self, event: &serde_json::Value) -> bool {
+        if !self.event_types.is_empty() {
+            match event["event_type"].as_str() {
+                Some(event_type) if self.event_types.iter().any(|t| t == event_type) => {}
+                _ => return false,
+            }
+        }
+        queryparser::matches(&self.query_string, event)
+    }
+}
+
+pub(crate) async fn handler(
+    _session: SessionExtractor,
+    State(context): State<Arc<ServerContext>>,
+    Form(form): Form<LiveParams>,
+) -> Result<impl IntoResponse, ApiError> {
+    let query_string = form
+        .query_string
+        .as_ref()
+        .map(|qs| queryparser::parse(qs, form.tz_offset.as_deref()))
+        .transpose()?
+        .unwrap_or_default();
+    let event_types = form
+        .event_type
+        .as_deref()
+        .unwrap_or_default()
+        .split(',')
+        .map(|t| t.trim().to_string())
+        .filter(|t| !t.is_empty())
+        .collect();
+
+    let state = LiveState {
+        rx: context.live.subscribe(),
+        query_string,
+        event_types,
+    };
+
+    let stream = futures::stream::unfold(state, |mut state| async move {
+        loop {
+            match state.rx.recv().await {
+                Ok(event) => {
+                    if state.matches(&event) {
+                        let sse = Event::default().data(event.to_string());
+                        return Some((Ok::<_, std::convert::Infallible>(sse), state));
+                    }
+                }
+                Err(RecvError::Lagged(n)) => {
+                    let sse = Event::default().event("lagged").data(n.to_string());
+                    return Some((Ok(sse), state));
+                }
+                Err(RecvError::Closed) => return None,
+            }
+        }
+    });
+
+    Ok(Sse::new(stream).keep_alive(KeepAlive::default()))
+}
@@ -1,17 +1,20 @@
 // SPDX-FileCopyrightText: (C) 2020 Jason Ish <jason@codemonkey.net>
 // SPDX-License-Identifier: MIT
 
-use axum::extract::Extension;
-use axum::http::StatusCode;
+use axum::extract::{ConnectInfo, Extension};
+use axum::http::{HeaderMap, StatusCode};
 use axum::response::IntoResponse;
 use axum::Json;
 use serde::Deserialize;
+use std::net::SocketAddr;
 use std::sync::Arc;
 use tracing::{error, info, warn};
 
+use crate::server::audit;
 use crate::server::main::SessionExtractor;
 use crate::server::session::Session;
 use crate::server::ServerContext;
+use crate::sqlite::configrepo::audit::action;
 use crate::sqlite::configrepo::ConfigRepoError;
 
 #[derive(Debug, Deserialize)]
@@ -34,8 +37,11 @@ pub(crate) async fn options(
 pub(crate) async fn post(
     context: Extension<Arc<ServerContext>>,
     _session: Option<SessionExtractor>,
+    headers: HeaderMap,
+    connect_info: Option<ConnectInfo<SocketAddr>>,
     form: axum::extract::Form<LoginForm>,
 ) -> impl IntoResponse {
+    let remote_addr = audit::remote_addr(&context, &headers, connect_info.as_ref());
     if !context.config.authentication_required {
         (StatusCode::OK, Json(serde_json::json!({}))).into_response()
     } else {
@@ -63,6 +69,14 @@ pub(crate) async fn post(
                 | ConfigRepoError::BadPassword(_)
                 | ConfigRepoError::NoUser(_) => {
                     warn!("Login failure for username={}, error={:?}", &username, err);
+                    audit::record(
+                        &context,
+                        Some(&username),
+                        remote_addr.as_deref(),
+                        action::LOGIN_FAILED,
+                        None,
+                    )
+                    .await;
                     return (StatusCode::UNAUTHORIZED, "").into_response();
                 }
                 _ => {
@@ -73,8 +87,17 @@ pub(crate) async fn post(
         };
 
         info!("Creating session for user {:?}", &username);
+        audit::record(
+            &context,
+            Some(&username),
+            remote_addr.as_deref(),
+            action::LOGIN,
+            None,
+        )
+        .await;
         let mut session = Session::new();
         session.username = Some(user.username);
+        session.admin = user.admin;
         let session = Arc::new(session);
         context.session_store.put(session.clone()).unwrap();
 
@@ -105,12 +128,23 @@ pub(crate) async fn post(
 pub(crate) async fn logout(
     context: Extension<Arc<ServerContext>>,
     SessionExtractor(session): SessionExtractor,
+    headers: HeaderMap,
+    connect_info: Option<ConnectInfo<SocketAddr>>,
 ) -> impl IntoResponse {
     if let Some(session_id) = &session.session_id {
         if !context.session_store.delete(session_id) {
             warn!("Logout request for unknown session ID");
         } else {
             info!("User logged out: {:?}", session.username);
+            let remote_addr = audit::remote_addr(&context, &headers, connect_info.as_ref());
+            audit::record(
+                &context,
+                session.username.as_deref(),
+                remote_addr.as_deref(),
+                action::LOGOUT,
+                None,
+            )
+            .await;
         }
         let _ = context.config_repo.delete_session(session_id).await;
     }
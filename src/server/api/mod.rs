@@ -2,12 +2,14 @@
 // SPDX-License-Identifier: MIT
 
 use crate::datetime::DateTime;
-use crate::eventrepo::EventQueryParams;
-use crate::eventrepo::{DatastoreError, EventRepo};
+use crate::eventrepo::{AlertGroupKey, DatastoreError, EventRepo, TriageStatus};
+use crate::eventrepo::{EventCursor, EventQueryParams, MAX_EVENTS_SIZE};
 use crate::queryparser::{QueryElement, QueryStringParseError, QueryValue};
 use crate::server::api::genericquery::GenericQuery;
 use crate::server::main::SessionExtractor;
+use crate::server::session::Session;
 use crate::server::ServerContext;
+use crate::sqlite::configrepo::ConfigRepoError;
 use crate::{elastic, queryparser};
 use axum::extract::{Extension, Form, Path, State};
 use axum::http::StatusCode;
@@ -27,12 +29,19 @@ use self::util::parse_duration;
 
 pub(crate) mod admin;
 pub(crate) mod agg;
+pub(crate) mod bulk;
+pub(crate) mod cases;
 pub(crate) mod eve2pcap;
+pub(crate) mod export;
 pub(crate) mod genericquery;
+pub(crate) mod live;
 pub(crate) mod login;
+pub(crate) mod rules;
+pub(crate) mod sensors;
 pub(crate) mod sqlite;
 pub(crate) mod stats;
 pub(crate) mod submit;
+pub(crate) mod suppressions;
 pub(crate) mod util;
 
 pub(crate) fn router() -> axum::Router<Arc<ServerContext>> {
@@ -42,15 +51,37 @@ pub(crate) fn router() -> axum::Router<Arc<ServerContext>> {
         .route("/api/1/config", get(config))
         .route("/api/1/version", get(get_version))
         .route("/api/1/user", get(get_user))
+        .route("/api/1/users", get(get_users))
         .route("/api/1/alerts", get(alerts))
         .route("/api/1/events", get(events))
+        .route("/api/1/events/live", get(live::handler))
+        .route("/api/1/export", get(export::handler))
         .route("/api/1/event/:id", get(get_event_by_id))
+        .route("/api/1/event/:id/related", get(related_events))
         .route("/api/1/alert-group/star", post(alert_group_star))
         .route("/api/1/alert-group/unstar", post(alert_group_unstar))
         .route("/api/1/alert-group/archive", post(alert_group_archive))
+        .route("/api/1/alert-group/unarchive", post(alert_group_unarchive))
+        .route("/api/1/alert-group/status", post(alert_group_status))
+        .route("/api/1/alert-group/assign", post(alert_group_assign))
+        .route("/api/1/alert-group/unassign", post(alert_group_unassign))
+        .route("/api/1/alert-group/tag", post(alert_group_add_tags))
+        .route("/api/1/alert-group/comment", post(alert_group_comment))
+        .route("/api/1/alert-group/untag", post(alert_group_remove_tags))
+        .route("/api/1/bulk/:action", post(bulk::handler))
         .route("/api/1/event/:id/archive", post(archive_event_by_id))
+        .route("/api/1/event/:id/unarchive", post(unarchive_event_by_id))
+        .route("/api/1/event/:id/status", post(set_status_by_event_id))
+        .route("/api/1/event/:id/assign", post(assign_event_by_id))
+        .route("/api/1/event/:id/unassign", post(unassign_event_by_id))
+        .route("/api/1/event/:id/tag", post(add_tags_by_event_id))
+        .route("/api/1/event/:id/untag", post(remove_tags_by_event_id))
         .route("/api/1/event/:id/escalate", post(escalate_event_by_id))
         .route("/api/event/:id/comment", post(comment_by_event_id))
+        .route(
+            "/api/1/comment/:id",
+            axum::routing::put(edit_comment).delete(delete_comment),
+        )
         .route("/api/1/event/:id/de-escalate", post(deescalate_event_by_id))
         .route("/api/1/report/histogram/time", get(histogram_time))
         .route("/api/1/dhcp/ack", get(dhcp_ack))
@@ -58,6 +89,7 @@ pub(crate) fn router() -> axum::Router<Arc<ServerContext>> {
         .route("/api/1/eve2pcap", post(eve2pcap::handler))
         .route("/api/1/submit", post(submit::handler))
         .route("/api/1/sensors", get(stats::get_sensor_names))
+        .route("/api/1/sensors/status", get(sensors::status))
         .route("/api/agg", get(agg::agg))
         .route("/api/1/sqlite/info", get(sqlite::info))
         .route("/api/1/sqlite/fts/check", get(sqlite::fts_check))
@@ -65,16 +97,46 @@ pub(crate) fn router() -> axum::Router<Arc<ServerContext>> {
         .route("/api/1/sqlite/fts/disable", post(sqlite::fts_disable))
         .route("/api/ja4db/:fingerprint", get(ja4db))
         .route("/api/admin/update/ja4db", post(admin::update_ja4db))
+        .route("/api/admin/audit", get(admin::audit))
         .nest("/api/1/stats", stats::router())
+        .nest("/api/1/cases", cases::router())
+        .nest("/api/1/suppressions", suppressions::router())
+        .nest("/api/1/rules", rules::router())
 }
 
 #[derive(Deserialize, Debug, Clone)]
 pub(crate) struct AlertGroupSpec {
-    pub signature_id: u64,
-    pub src_ip: String,
-    pub dest_ip: String,
+    pub signature_id: Option<u64>,
+    pub src_ip: Option<String>,
+    pub dest_ip: Option<String>,
     pub min_timestamp: String,
     pub max_timestamp: String,
+    /// The keys the group was created with, if empty the default
+    /// grouping is used.
+    #[serde(default)]
+    pub group_by: Vec<AlertGroupKey>,
+}
+
+impl AlertGroupSpec {
+    /// Return the value to match for each key of the group.
+    pub fn key_values(&self) -> anyhow::Result<Vec<(AlertGroupKey, serde_json::Value)>> {
+        let keys: &[AlertGroupKey] = if self.group_by.is_empty() {
+            &AlertGroupKey::DEFAULT
+        } else {
+            &self.group_by
+        };
+        let mut values = vec![];
+        for key in keys {
+            let value = match key {
+                AlertGroupKey::SignatureId => self.signature_id.map(serde_json::Value::from),
+                AlertGroupKey::SrcIp => self.src_ip.clone().map(serde_json::Value::from),
+                AlertGroupKey::DestIp => self.dest_ip.clone().map(serde_json::Value::from),
+            };
+            let value = value.ok_or_else(|| anyhow::anyhow!("missing {}", key.as_str()))?;
+            values.push((*key, value));
+        }
+        Ok(values)
+    }
 }
 
 pub(crate) async fn config(
@@ -101,6 +163,21 @@ pub(crate) async fn get_user(SessionExtractor(session): SessionExtractor) -> imp
     Json(user)
 }
 
+/// List the usernames events can be assigned to.
+pub(crate) async fn get_users(
+    Extension(context): Extension<Arc<ServerContext>>,
+    _session: SessionExtractor,
+) -> Result<impl IntoResponse, ApiError> {
+    let users = context.config_repo.get_users().await.map_err(|err| {
+        error!("Failed to get users: {:?}", err);
+        ApiError::InternalServerError
+    })?;
+    let usernames: Vec<String> = users.into_iter().map(|user| user.username).collect();
+    Ok(Json(json!({
+        "users": usernames,
+    })))
+}
+
 pub(crate) async fn get_version() -> impl IntoResponse {
     let version = serde_json::json!({
         "version": crate::version::version(),
@@ -165,46 +242,272 @@ pub(crate) async fn alert_group_star(
     Extension(context): Extension<Arc<ServerContext>>,
     SessionExtractor(session): SessionExtractor,
     Json(request): Json<AlertGroupSpec>,
-) -> impl IntoResponse {
+) -> Result<impl IntoResponse, ApiError> {
     info!("Escalated alert group: {:?}", request);
+    request
+        .key_values()
+        .map_err(|err| ApiError::bad_request(err.to_string()))?;
     context
         .datastore
         .escalate_by_alert_group(request, session)
-        .await
-        .unwrap();
-    StatusCode::OK
+        .await?;
+    Ok(StatusCode::OK)
 }
 
 pub(crate) async fn alert_group_unstar(
     Extension(context): Extension<Arc<ServerContext>>,
     SessionExtractor(session): SessionExtractor,
     Json(request): Json<AlertGroupSpec>,
-) -> impl IntoResponse {
+) -> Result<impl IntoResponse, ApiError> {
     info!("De-escalating alert group: {:?}", request);
+    request
+        .key_values()
+        .map_err(|err| ApiError::bad_request(err.to_string()))?;
     context
         .datastore
         .deescalate_by_alert_group(session, request)
-        .await
-        .unwrap();
-    StatusCode::OK
+        .await?;
+    Ok(StatusCode::OK)
 }
 
 pub(crate) async fn alert_group_archive(
     Extension(context): Extension<Arc<ServerContext>>,
     SessionExtractor(_session): SessionExtractor,
     Json(request): Json<AlertGroupSpec>,
-) -> impl IntoResponse {
-    match context.datastore.archive_by_alert_group(request).await {
-        Ok(_) => StatusCode::OK,
-        Err(err) => {
+) -> Result<impl IntoResponse, ApiError> {
+    request
+        .key_values()
+        .map_err(|err| ApiError::bad_request(err.to_string()))?;
+    context
+        .datastore
+        .archive_by_alert_group(request)
+        .await
+        .map_err(|err| {
             error!("Failed to archive by alert group: {:?}", err);
-            StatusCode::INTERNAL_SERVER_ERROR
+            err
+        })?;
+    Ok(StatusCode::OK)
+}
+
+pub(crate) async fn alert_group_unarchive(
+    Extension(context): Extension<Arc<ServerContext>>,
+    SessionExtractor(_session): SessionExtractor,
+    Json(request): Json<AlertGroupSpec>,
+) -> Result<impl IntoResponse, ApiError> {
+    request
+        .key_values()
+        .map_err(|err| ApiError::bad_request(err.to_string()))?;
+    context
+        .datastore
+        .unarchive_by_alert_group(request)
+        .await
+        .map_err(|err| {
+            error!("Failed to un-archive by alert group: {:?}", err);
+            err
+        })?;
+    Ok(StatusCode::OK)
+}
+
+#[derive(Deserialize, Debug)]
+pub(crate) struct AlertGroupStatusRequest {
+    #[serde(flatten)]
+    group: AlertGroupSpec,
+    status: TriageStatus,
+    reason: Option<String>,
+}
+
+pub(crate) async fn alert_group_status(
+    Extension(context): Extension<Arc<ServerContext>>,
+    SessionExtractor(session): SessionExtractor,
+    Json(request): Json<AlertGroupStatusRequest>,
+) -> Result<impl IntoResponse, ApiError> {
+    info!("Setting status of alert group: {:?}", request);
+    request
+        .group
+        .key_values()
+        .map_err(|err| ApiError::bad_request(err.to_string()))?;
+    context
+        .datastore
+        .set_status_by_alert_group(request.group, request.status, request.reason, session)
+        .await?;
+    Ok(StatusCode::OK)
+}
+
+#[derive(Deserialize, Debug)]
+pub(crate) struct AssignRequest {
+    /// The user to assign to, defaults to the requesting user.
+    username: Option<String>,
+}
+
+#[derive(Deserialize, Debug)]
+pub(crate) struct AlertGroupAssignRequest {
+    #[serde(flatten)]
+    group: AlertGroupSpec,
+    username: Option<String>,
+}
+
+/// Resolve the user to assign to, validating that the user exists
+/// when authentication is enabled.
+async fn resolve_assignee(
+    context: &ServerContext,
+    session: &Session,
+    username: Option<String>,
+) -> Result<String, ApiError> {
+    let username = username
+        .or_else(|| session.username.clone())
+        .ok_or_else(|| ApiError::bad_request("username"))?;
+    if context.config.authentication_required {
+        match context.config_repo.get_user_by_name(&username).await {
+            Ok(_) => {}
+            Err(ConfigRepoError::NoUser(_)) => {
+                return Err(ApiError::bad_request(format!("unknown user: {username}")));
+            }
+            Err(err) => {
+                error!("Failed to lookup user {}: {:?}", username, err);
+                return Err(ApiError::InternalServerError);
+            }
+        }
+    }
+    Ok(username)
+}
+
+pub(crate) async fn alert_group_assign(
+    Extension(context): Extension<Arc<ServerContext>>,
+    SessionExtractor(session): SessionExtractor,
+    Json(request): Json<AlertGroupAssignRequest>,
+) -> Result<impl IntoResponse, ApiError> {
+    request
+        .group
+        .key_values()
+        .map_err(|err| ApiError::bad_request(err.to_string()))?;
+    let assignee = resolve_assignee(&context, &session, request.username).await?;
+    info!("Assigning alert group to {}: {:?}", assignee, request.group);
+    context
+        .datastore
+        .set_assignee_by_alert_group(request.group, Some(assignee), session)
+        .await?;
+    Ok(StatusCode::OK)
+}
+
+pub(crate) async fn alert_group_unassign(
+    Extension(context): Extension<Arc<ServerContext>>,
+    SessionExtractor(session): SessionExtractor,
+    Json(request): Json<AlertGroupSpec>,
+) -> Result<impl IntoResponse, ApiError> {
+    request
+        .key_values()
+        .map_err(|err| ApiError::bad_request(err.to_string()))?;
+    info!("Unassigning alert group: {:?}", request);
+    context
+        .datastore
+        .set_assignee_by_alert_group(request, None, session)
+        .await?;
+    Ok(StatusCode::OK)
+}
+
+#[derive(Deserialize, Debug)]
+pub(crate) struct TagsRequest {
+    tags: Vec<String>,
+}
+
+#[derive(Deserialize, Debug)]
+pub(crate) struct AlertGroupTagsRequest {
+    #[serde(flatten)]
+    group: AlertGroupSpec,
+    tags: Vec<String>,
+}
+
+/// Validate user tags. Tags may not contain whitespace, and the
+/// `evebox.` prefix is reserved for tags managed by EveBox.
+fn validate_user_tags(tags: Vec<String>) -> Result<Vec<String>, ApiError> {
+    let mut valid: Vec<String> = vec![];
+    for tag in tags {
+        let tag = tag.trim();
+        if tag.is_empty() || tag.contains(char::is_whitespace) {
+            return Err(ApiError::bad_request(format!("invalid tag: {tag:?}")));
+        }
+        if tag.starts_with("evebox.") {
+            return Err(ApiError::bad_request(format!("reserved tag: {tag}")));
+        }
+        if !valid.iter().any(|t| t == tag) {
+            valid.push(tag.to_string());
         }
     }
+    if valid.is_empty() {
+        return Err(ApiError::bad_request("tags"));
+    }
+    Ok(valid)
+}
+
+pub(crate) async fn add_tags_by_event_id(
+    Extension(context): Extension<Arc<ServerContext>>,
+    SessionExtractor(session): SessionExtractor,
+    Path(event_id): Path<String>,
+    Json(request): Json<TagsRequest>,
+) -> Result<impl IntoResponse, ApiError> {
+    let tags = validate_user_tags(request.tags)?;
+    context
+        .datastore
+        .add_user_tags_by_id(&event_id, tags, session)
+        .await?;
+    Ok(StatusCode::OK)
+}
+
+pub(crate) async fn remove_tags_by_event_id(
+    Extension(context): Extension<Arc<ServerContext>>,
+    SessionExtractor(session): SessionExtractor,
+    Path(event_id): Path<String>,
+    Json(request): Json<TagsRequest>,
+) -> Result<impl IntoResponse, ApiError> {
+    let tags = validate_user_tags(request.tags)?;
+    context
+        .datastore
+        .remove_user_tags_by_id(&event_id, tags, session)
+        .await?;
+    Ok(StatusCode::OK)
+}
+
+pub(crate) async fn alert_group_add_tags(
+    Extension(context): Extension<Arc<ServerContext>>,
+    SessionExtractor(session): SessionExtractor,
+    Json(request): Json<AlertGroupTagsRequest>,
+) -> Result<impl IntoResponse, ApiError> {
+    request
+        .group
+        .key_values()
+        .map_err(|err| ApiError::bad_request(err.to_string()))?;
+    let tags = validate_user_tags(request.tags)?;
+    info!("Adding tags {:?} to alert group: {:?}", tags, request.group);
+    context
+        .datastore
+        .add_user_tags_by_alert_group(request.group, tags, session)
+        .await?;
+    Ok(StatusCode::OK)
+}
+
+pub(crate) async fn alert_group_remove_tags(
+    Extension(context): Extension<Arc<ServerContext>>,
+    SessionExtractor(session): SessionExtractor,
+    Json(request): Json<AlertGroupTagsRequest>,
+) -> Result<impl IntoResponse, ApiError> {
+    request
+        .group
+        .key_values()
+        .map_err(|err| ApiError::bad_request(err.to_string()))?;
+    let tags = validate_user_tags(request.tags)?;
+    info!(
+        "Removing tags {:?} from alert group: {:?}",
+        tags, request.group
+    );
+    context
+        .datastore
+        .remove_user_tags_by_alert_group(request.group, tags, session)
+        .await?;
+    Ok(StatusCode::OK)
 }
 
 pub(crate) async fn histogram_time(
-    _session: SessionExtractor,
+    SessionExtractor(session): SessionExtractor,
     Extension(context): Extension<Arc<ServerContext>>,
     Form(query): Form<GenericQuery>,
 ) -> Result<impl IntoResponse, ApiError> {
@@ -223,6 +526,7 @@ pub(crate) async fn histogram_time(
         .map(|q| queryparser::parse(&q, default_tz_offset))
         .transpose()?
         .unwrap_or_default();
+    queryparser::resolve_assignee_me(&mut query_string, session.username.as_deref());
 
     if let Some(event_type) = &query.event_type {
         query_string.push(QueryElement {
@@ -258,12 +562,13 @@ pub(crate) async fn histogram_time(
 pub(crate) async fn alerts(
     Extension(context): Extension<Arc<ServerContext>>,
     // Session required to get here.
-    _session: SessionExtractor,
+    SessionExtractor(session): SessionExtractor,
     Form(query): Form<GenericQuery>,
 ) -> Result<impl IntoResponse, ApiError> {
     let mut options = elastic::AlertQueryOptions {
         query_string: query.query_string,
         sensor: query.sensor,
+        username: session.username.clone(),
         ..elastic::AlertQueryOptions::default()
     };
 
@@ -274,6 +579,11 @@ pub(crate) async fn alerts(
         }
     }
 
+    if let Some(group_by) = &query.group_by {
+        options.group_by =
+            AlertGroupKey::parse_list(group_by).map_err(|_| ApiError::bad_request("group_by"))?;
+    }
+
     if let Some(time_range) = query.time_range {
         if !time_range.is_empty() {
             let now = DateTime::now();
@@ -311,6 +621,114 @@ pub(crate) async fn get_event_by_id(
     }
 }
 
+#[derive(Deserialize, Debug, Default)]
+pub(crate) struct RelatedEventsQuery {
+    /// Time window before and after the event to search, defaults to
+    /// 1 hour.
+    pub window: Option<String>,
+    /// Also include events with the same community ID.
+    #[serde(default)]
+    pub community_id: bool,
+}
+
+/// Return events with the same flow ID as the event, and optionally
+/// the same community ID, within a time window around the event.
+///
+/// Each query returns at most `MAX_EVENTS_SIZE` events, `truncated` is
+/// set in the response if there were more.
+pub(crate) async fn related_events(
+    _session: SessionExtractor,
+    Extension(context): Extension<Arc<ServerContext>>,
+    Path(event_id): axum::extract::Path<String>,
+    Form(query): Form<RelatedEventsQuery>,
+) -> Result<impl IntoResponse, ApiError> {
+    let event = match context.datastore.get_event_by_id(event_id).await? {
+        Some(event) => event,
+        None => return Ok((StatusCode::NOT_FOUND, "not found").into_response()),
+    };
+    let source = &event["_source"];
+
+    let flow_id = source["flow_id"]
+        .as_u64()
+        .or_else(|| source["suricata"]["eve"]["flow_id"].as_u64())
+        .ok_or_else(|| ApiError::bad_request("event does not have a flow_id"))?;
+    let community_id = source["community_id"]
+        .as_str()
+        .or_else(|| source["network"]["community_id"].as_str());
+    let timestamp = source["timestamp"]
+        .as_str()
+        .ok_or_else(|| ApiError::bad_request("event does not have a timestamp"))?;
+    let timestamp = crate::datetime::parse(timestamp, None)
+        .map_err(|err| ApiError::bad_request(format!("bad event timestamp: {err}")))?;
+
+    let window = query.window.as_deref().unwrap_or("1h");
+    let window = parse_duration(window)
+        .ok()
+        .and_then(|window| chrono::Duration::from_std(window).ok())
+        .ok_or_else(|| ApiError::bad_request("window"))?;
+    let min_timestamp = DateTime::sub(&timestamp, window);
+    let max_timestamp = timestamp.add(window);
+
+    let mut keys = vec![("flow_id", flow_id.to_string())];
+    if query.community_id {
+        if let Some(community_id) = community_id {
+            keys.push(("community_id", community_id.to_string()));
+        }
+    }
+
+    let mut ids = std::collections::HashSet::new();
+    let mut events = vec![];
+    let mut truncated = false;
+    for (key, value) in keys {
+        let params = EventQueryParams {
+            order: Some("asc".to_string()),
+            // One more than returned to tell if there are more.
+            size: Some(MAX_EVENTS_SIZE + 1),
+            query_string: vec![
+                QueryElement {
+                    negated: false,
+                    value: QueryValue::KeyValue(key.to_string(), value),
+                },
+                QueryElement {
+                    negated: false,
+                    value: QueryValue::From(min_timestamp.clone()),
+                },
+                QueryElement {
+                    negated: false,
+                    value: QueryValue::To(max_timestamp.clone()),
+                },
+            ],
+            ..Default::default()
+        };
+        let response = context.datastore.events(params).await?;
+        if let serde_json::Value::Array(hits) = &response["events"] {
+            truncated |= hits.len() as u64 > MAX_EVENTS_SIZE;
+            for hit in hits.iter().take(MAX_EVENTS_SIZE as usize) {
+                if ids.insert(hit["_id"].to_string()) {
+                    events.push(hit.clone());
+                }
+            }
+        }
+    }
+
+    // Events from the community ID query will be out of order with
+    // those from the flow ID query.
+    events.sort_by_key(|event| {
+        event["_source"]["timestamp"]
+            .as_str()
+            .and_then(|ts| crate::datetime::parse(ts, None).ok())
+            .map(|ts| ts.to_nanos())
+    });
+
+    let response = json!({
+        "flow_id": flow_id,
+        "community_id": community_id,
+        "events": events,
+        "truncated": truncated,
+    });
+    Ok(Json(response).into_response())
+}
+
 pub(crate) async fn archive_event_by_id(
     Extension(context): Extension<Arc<ServerContext>>,
     Path(event_id): axum::extract::Path<String>,
@@ -328,6 +746,68 @@ pub(crate) async fn archive_event_by_id(
     }
 }
 
+pub(crate) async fn unarchive_event_by_id(
+    Extension(context): Extension<Arc<ServerContext>>,
+    Path(event_id): axum::extract::Path<String>,
+    _session: SessionExtractor,
+) -> impl IntoResponse {
+    match context.datastore.unarchive_event_by_id(&event_id).await {
+        Ok(()) => StatusCode::OK,
+        Err(err) => {
+            error!(
+                "Failed to un-archive event by ID: id={}, err={:?}",
+                event_id, err
+            );
+            StatusCode::INTERNAL_SERVER_ERROR
+        }
+    }
+}
+
+#[derive(Deserialize, Debug)]
+pub(crate) struct EventStatusRequest {
+    status: TriageStatus,
+    reason: Option<String>,
+}
+
+pub(crate) async fn set_status_by_event_id(
+    Extension(context): Extension<Arc<ServerContext>>,
+    Path(event_id): axum::extract::Path<String>,
+    SessionExtractor(session): SessionExtractor,
+    Json(request): Json<EventStatusRequest>,
+) -> Result<impl IntoResponse, ApiError> {
+    context
+        .datastore
+        .set_status_by_id(&event_id, request.status, request.reason, session)
+        .await?;
+    Ok(StatusCode::OK)
+}
+
+pub(crate) async fn assign_event_by_id(
+    Extension(context): Extension<Arc<ServerContext>>,
+    Path(event_id): axum::extract::Path<String>,
+    SessionExtractor(session): SessionExtractor,
+    Json(request): Json<AssignRequest>,
+) -> Result<impl IntoResponse, ApiError> {
+    let assignee = resolve_assignee(&context, &session, request.username).await?;
+    context
+        .datastore
+        .set_assignee_by_id(&event_id, Some(assignee), session)
+        .await?;
+    Ok(StatusCode::OK)
+}
+
+pub(crate) async fn unassign_event_by_id(
+    Extension(context): Extension<Arc<ServerContext>>,
+    Path(event_id): axum::extract::Path<String>,
+    SessionExtractor(session): SessionExtractor,
+) -> Result<impl IntoResponse, ApiError> {
+    context
+        .datastore
+        .set_assignee_by_id(&event_id, None, session)
+        .await?;
+    Ok(StatusCode::OK)
+}
+
 pub(crate) async fn escalate_event_by_id(
     Extension(context): Extension<Arc<ServerContext>>,
     Path(event_id): axum::extract::Path<String>,
@@ -384,13 +864,111 @@ pub(crate) async fn comment_by_event_id(
     }
 }
 
+#[derive(Deserialize, Debug)]
+pub(crate) struct AlertGroupCommentRequest {
+    #[serde(flatten)]
+    group: AlertGroupSpec,
+    comment: String,
+}
+
+pub(crate) async fn alert_group_comment(
+    Extension(context): Extension<Arc<ServerContext>>,
+    SessionExtractor(session): SessionExtractor,
+    Json(request): Json<AlertGroupCommentRequest>,
+) -> Result<impl IntoResponse, ApiError> {
+    request
+        .group
+        .key_values()
+        .map_err(|err| ApiError::bad_request(err.to_string()))?;
+    if request.comment.trim().is_empty() {
+        return Err(ApiError::bad_request("comment"));
+    }
+    context
+        .datastore
+        .comment_by_alert_group(request.group, request.comment, session)
+        .await?;
+    Ok(StatusCode::OK)
+}
+
+/// The user whose comments may be changed in this session, `None` if
+/// any comment may be changed.
+fn comment_owner(session: &Session) -> Option<&str> {
+    if session.admin {
+        None
+    } else {
+        session.username.as_deref()
+    }
+}
+
+/// Comment IDs are UUIDs, return it in its canonical form.
+fn parse_comment_id(comment_id: &str) -> Result<String, ApiError> {
+    uuid::Uuid::parse_str(comment_id)
+        .map(|id| id.to_string())
+        .map_err(|_| ApiError::bad_request("comment_id"))
+}
+
+fn comment_not_found() -> Response {
+    let body = json!({
+        "error": "comment not found",
+    });
+    (StatusCode::NOT_FOUND, Json(body)).into_response()
+}
+
+/// Edit a comment. Users may only edit their own comments, unless
+/// they are an administrator.
+pub(crate) async fn edit_comment(
+    Extension(context): Extension<Arc<ServerContext>>,
+    SessionExtractor(session): SessionExtractor,
+    Path(comment_id): Path<String>,
+    Json(body): Json<EventCommentRequestBody>,
+) -> Result<impl IntoResponse, ApiError> {
+    let comment_id = parse_comment_id(&comment_id)?;
+    if body.comment.trim().is_empty() {
+        return Err(ApiError::bad_request("comment"));
+    }
+    let n = context
+        .datastore
+        .update_comment(&comment_id, Some(body.comment), comment_owner(&session))
+        .await?;
+    if n == 0 {
+        return Ok(comment_not_found());
+    }
+    info!(
+        "Comment {} edited by {:?} on {} events",
+        comment_id, session.username, n
+    );
+    Ok(StatusCode::OK.into_response())
+}
+
+/// Delete a comment. Users may only delete their own comments, unless
+/// they are an administrator.
+pub(crate) async fn delete_comment(
+    Extension(context): Extension<Arc<ServerContext>>,
+    SessionExtractor(session): SessionExtractor,
+    Path(comment_id): Path<String>,
+) -> Result<impl IntoResponse, ApiError> {
+    let comment_id = parse_comment_id(&comment_id)?;
+    let n = context
+        .datastore
+        .update_comment(&comment_id, None, comment_owner(&session))
+        .await?;
+    if n == 0 {
+        return Ok(comment_not_found());
+    }
+    info!(
+        "Comment {} deleted by {:?} from {} events",
+        comment_id, session.username, n
+    );
+    Ok(StatusCode::OK.into_response())
+}
+
 pub(crate) async fn events(
-    _session: SessionExtractor,
+    SessionExtractor(session): SessionExtractor,
     Extension(context): Extension<Arc<ServerContext>>,
     Form(query): Form<GenericQuery>,
 ) -> Result<impl IntoResponse, ApiError> {
     let mut params = EventQueryParams {
-        size: query.size,
+        size: query.size.map(|size| size.clamp(1, MAX_EVENTS_SIZE)),
         sort_by: query.sort_by,
         event_type: query.event_type,
         order: query.order,
@@ -407,13 +985,23 @@ pub(crate) async fn events(
 
     let default_tz_offset = query.tz_offset.as_deref();
 
-    let query_string = query
+    let mut query_string = query
         .query_string
         .map(|qs| queryparser::parse(&qs, default_tz_offset))
         .transpose()?
         .unwrap_or_default();
+    queryparser::resolve_assignee_me(&mut query_string, session.username.as_deref());
     params.query_string = query_string;
 
+    if let Some(cursor) = &query.cursor {
+        params.paginate = true;
+        if !cursor.is_empty() {
+            let cursor =
+                EventCursor::decode(cursor).map_err(|_| ApiError::bad_request("cursor"))?;
+            params.cursor = Some(cursor);
+        }
+    }
+
     let results = context.datastore.events(params).await?;
     Ok(Json(results).into_response())
 }
@@ -474,6 +1062,10 @@ pub(crate) enum ApiError {
     Sqlx(#[from] sqlx::Error),
     #[error("bad query string")]
     QueryString(#[from] QueryStringParseError),
+    #[error("{0}")]
+    ConfigRepo(#[from] ConfigRepoError),
+    #[error("forbidden")]
+    Forbidden,
 }
 
 impl ApiError {
@@ -493,6 +1085,29 @@ impl IntoResponse for ApiError {
             ),
             ApiError::Sqlx(_) => (StatusCode::INTERNAL_SERVER_ERROR, err),
             ApiError::QueryString(_) => (StatusCode::BAD_REQUEST, err),
+            ApiError::Forbidden => (StatusCode::FORBIDDEN, err),
+            ApiError::ConfigRepo(ConfigRepoError::CaseNotFound(_))
+            | ApiError::ConfigRepo(ConfigRepoError::SuppressionNotFound(_)) => {
+                (StatusCode::NOT_FOUND, err)
+            }
+            ApiError::ConfigRepo(err) => {
+                error!(
+                    "Configuration database error while servicing API request: {}",
+                    err
+                );
+                (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    "internal server error".to_string(),
+                )
+            }
+            // Query string errors detected by the datastore, for
+            // example unsupported FTS syntax.
+            ApiError::DatastoreError(DatastoreError::SqlxDynError(err))
+                if err.is::<QueryStringParseError>() =>
+            {
+                (StatusCode::BAD_REQUEST, err.to_string())
+            }
+            ApiError::DatastoreError(DatastoreError::EventNotFound) => (StatusCode::NOT_FOUND, err),
             ApiError::DatastoreError(err) => {
                 // Log datastore errors.
                 error!("Datastore error while servicing API request: {}", err);
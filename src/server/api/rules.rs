@@ -0,0 +1,80 @@
+// SPDX-FileCopyrightText: (C) 2024 Jason Ish <jason@codemonkey.net>
+// SPDX-License-Identifier: MIT
+
+//! API endpoints for browsing the rules loaded from `input.rules`.
+
+use std::sync::Arc;
+
+use axum::extract::{Form, Path, State};
+use axum::http::StatusCode;
+use axum::response::IntoResponse;
+use axum::routing::get;
+use axum::{Json, Router};
+use serde::Deserialize;
+
+use super::ApiError;
+use crate::rules::RuleSearch;
+use crate::server::{main::SessionExtractor, ServerContext};
+
+const DEFAULT_LIMIT: usize = 100;
+
+pub(crate) fn router() -> Router<Arc<ServerContext>> {
+    Router::new()
+        .route("/", get(search_rules))
+        .route("/files", get(list_files))
+        .route("/:sid", get(get_rule))
+}
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct SearchQuery {
+    /// Text to find in the message, or a SID.
+    q: Option<String>,
+    classtype: Option<String>,
+    filename: Option<String>,
+    limit: Option<usize>,
+}
+
+async fn search_rules(
+    _session: SessionExtractor,
+    State(context): State<Arc<ServerContext>>,
+    Form(query): Form<SearchQuery>,
+) -> Result<impl IntoResponse, ApiError> {
+    let search = RuleSearch {
+        query: query.q.filter(|q| !q.is_empty()),
+        classtype: query.classtype.filter(|c| !c.is_empty()),
+        filename: query.filename.filter(|f| !f.is_empty()),
+    };
+    let (rules, total) = match &context.rules {
+        Some(rules) => rules.search(&search, query.limit.unwrap_or(DEFAULT_LIMIT)),
+        None => (vec![], 0),
+    };
+    Ok(Json(json!({
+        "data": rules,
+        "total": total,
+    })))
+}
+
+async fn list_files(
+    _session: SessionExtractor,
+    State(context): State<Arc<ServerContext>>,
+) -> Result<impl IntoResponse, ApiError> {
+    let files = context
+        .rules
+        .as_ref()
+        .map(|rules| rules.files())
+        .unwrap_or_default();
+    Ok(Json(json!({
+        "data": files,
+    })))
+}
+
+async fn get_rule(
+    _session: SessionExtractor,
+    State(context): State<Arc<ServerContext>>,
+    Path(sid): Path<u64>,
+) -> Result<impl IntoResponse, ApiError> {
+    match context.rules.as_ref().and_then(|rules| rules.get(sid)) {
+        Some(rule) => Ok(Json(rule).into_response()),
+        None => Ok((StatusCode::NOT_FOUND, "not found").into_response()),
+    }
+}
@@ -0,0 +1,22 @@
+// SPDX-FileCopyrightText: (C) 2024 Jason Ish <jason@codemonkey.net>
+// SPDX-License-Identifier: MIT
+
+use super::ApiError;
+use crate::server::{main::SessionExtractor, ServerContext};
+use axum::extract::State;
+use axum::response::IntoResponse;
+use axum::Json;
+use std::sync::Arc;
+
+/// Return the activity of each sensor: when it was first and last
+/// seen, its event count and rate, agent version and if it has been
+/// flagged as silent.
+pub(crate) async fn status(
+    _session: SessionExtractor,
+    State(context): State<Arc<ServerContext>>,
+) -> Result<impl IntoResponse, ApiError> {
+    let sensors = context.config_repo.get_sensors().await?;
+    Ok(Json(json!({
+        "data": sensors,
+    })))
+}
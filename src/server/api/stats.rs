@@ -20,6 +20,7 @@ pub(crate) fn router() -> Router<Arc<ServerContext>> {
     Router::new()
         .route("/agg/diff", get(agg_differential))
         .route("/agg", get(agg))
+        .route("/sensors", get(sensor_stats))
 }
 
 #[derive(Debug, Clone, Deserialize)]
@@ -31,24 +32,38 @@ pub(crate) struct StatsAggQuery {
 
 impl StatsAggQuery {
     fn start_datetime(&self) -> anyhow::Result<DateTime> {
-        let start_time = if let Some(time_range) = self.time_range {
-            if time_range == 0 {
-                let then = chrono::DateTime::UNIX_EPOCH;
-                then.fixed_offset()
-            } else {
-                let delta = chrono::Duration::seconds(time_range);
-                let now = DateTime::now();
-
-                now.datetime - delta
-            }
+        start_datetime(self.time_range)
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub(crate) struct SensorStatsQuery {
+    sensor_name: Option<String>,
+    /// Time range in seconds, 0 for all time. Default 24 hours.
+    time_range: Option<i64>,
+    /// Seconds between samples. Defaults to a value suitable for the
+    /// time range.
+    interval: Option<u64>,
+}
+
+fn start_datetime(time_range: Option<i64>) -> anyhow::Result<DateTime> {
+    let start_time = if let Some(time_range) = time_range {
+        if time_range == 0 {
+            let then = chrono::DateTime::UNIX_EPOCH;
+            then.fixed_offset()
         } else {
-            let delta = chrono::Duration::hours(24);
+            let delta = chrono::Duration::seconds(time_range);
             let now = DateTime::now();
 
             now.datetime - delta
-        };
-        Ok(start_time.into())
-    }
+        }
+    } else {
+        let delta = chrono::Duration::hours(24);
+        let now = DateTime::now();
+
+        now.datetime - delta
+    };
+    Ok(start_time.into())
 }
 
 async fn agg(
@@ -75,6 +90,40 @@ async fn agg(
     }
 }
 
+/// Per-sensor rates and deltas computed from consecutive stats
+/// events: packets, bytes and flows per second, kernel drop
+/// percentage and memcap hits.
+async fn sensor_stats(
+    _session: SessionExtractor,
+    State(context): State<Arc<ServerContext>>,
+    Form(form): Form<SensorStatsQuery>,
+) -> Result<impl IntoResponse, ApiError> {
+    let start_time = start_datetime(form.time_range)?;
+    let interval = form.interval.unwrap_or_else(|| {
+        let range = (DateTime::now().datetime - start_time.datetime).num_seconds();
+        crate::util::histogram_interval(range)
+    });
+    let params = eventrepo::SensorStatsParams {
+        sensor_name: form.sensor_name,
+        start_time,
+        interval,
+    };
+
+    match context.datastore.sensor_stats(&params).await {
+        Ok(sensors) => Ok(Json(json!({
+            "interval": params.interval,
+            "data": sensors,
+        }))),
+        Err(err) => {
+            error!(
+                "Sensor stats query failed: params={:?}, error={:?}",
+                &params, err
+            );
+            Err(ApiError::InternalServerError)
+        }
+    }
+}
+
 async fn agg_differential(
     _session: SessionExtractor,
     State(context): State<Arc<ServerContext>>,
@@ -1,10 +1,12 @@
 // SPDX-FileCopyrightText: (C) 2020 Jason Ish <jason@codemonkey.net>
 // SPDX-License-Identifier: MIT
 
+use crate::eve::filters::RuleMetadataFilter;
 use crate::server::ServerContext;
 use axum::body::Bytes;
 use axum::extract::Extension;
-use axum::http::StatusCode;
+use axum::http::header::USER_AGENT;
+use axum::http::{HeaderMap, StatusCode};
 use axum::response::IntoResponse;
 use axum::Json;
 use serde_json::json;
@@ -14,9 +16,10 @@ use tracing::error;
 
 pub(crate) async fn handler(
     Extension(context): Extension<Arc<ServerContext>>,
+    headers: HeaderMap,
     body: Bytes,
 ) -> impl IntoResponse {
-    let mut importer = match context.datastore.get_importer() {
+    let mut importer = match context.get_importer() {
         Some(importer) => importer,
         None => {
             return (StatusCode::NOT_IMPLEMENTED, "").into_response();
@@ -24,6 +27,16 @@ pub(crate) async fn handler(
     };
     let mut errors = Vec::new();
 
+    // The EveBox agent identifies itself and its version in the
+    // user agent.
+    let agent_version = headers
+        .get(USER_AGENT)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("EveBox-Agent/"));
+
+    // Enrich alerts from agents without the rules loaded.
+    let rule_metadata = context.rules.clone().map(|map| RuleMetadataFilter { map });
+
     let mut buf = &body[..];
     let mut count = 0;
     let mut line = String::new();
@@ -45,8 +58,16 @@ pub(crate) async fn handler(
                             "Failed to decode event from request body ({err}): {line}"
                         ));
                     }
-                    Ok(event) => {
+                    Ok(mut event) => {
                         count += 1;
+                        if let Some(filter) = &rule_metadata {
+                            filter.run(&mut event);
+                        }
+                        context.suppressions.run(&mut event);
+                        context.sensors.record(&event, agent_version);
+                        if let Some(notifier) = &context.notifier {
+                            notifier.run(&event);
+                        }
                         if let Err(err) = importer.submit(event).await {
                             error!("Failed to submit event to importer: {}", err);
                         }
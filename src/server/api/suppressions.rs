@@ -0,0 +1,228 @@
+// SPDX-FileCopyrightText: (C) 2024 Jason Ish <jason@codemonkey.net>
+// SPDX-License-Identifier: MIT
+
+//! API endpoints for suppressions.
+//!
+//! Suppressions are applied to alerts as they are received. A
+//! suppression can also be run once against the alerts already in the
+//! inbox.
+
+use std::sync::Arc;
+
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use axum::response::IntoResponse;
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use serde::Deserialize;
+use tracing::info;
+
+use super::ApiError;
+use crate::datetime::DateTime;
+use crate::elastic::TAG_ARCHIVED;
+use crate::eventrepo::{EventCursor, EventExportParams};
+use crate::queryparser::{QueryElement, QueryValue};
+use crate::server::{main::SessionExtractor, ServerContext};
+use crate::sqlite::configrepo::suppressions::{IpNetwork, Suppression, SuppressionFields};
+
+/// Number of alerts to fetch per page when applying a suppression to
+/// existing alerts.
+const PAGE_SIZE: u64 = 1000;
+
+pub(crate) fn router() -> Router<Arc<ServerContext>> {
+    Router::new()
+        .route("/", get(list_suppressions).post(create_suppression))
+        .route("/:id", get(get_suppression).delete(delete_suppression))
+        .route("/:id/apply", post(apply_suppression))
+}
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct SuppressionRequest {
+    signature_id: u64,
+    src_ip: Option<IpNetwork>,
+    dest_ip: Option<IpNetwork>,
+    sensor: Option<String>,
+    comment: Option<String>,
+    /// Timestamp the suppression expires at.
+    expires_at: Option<String>,
+    /// Duration until the suppression expires, e.g. "7d".
+    expires_in: Option<String>,
+    tz_offset: Option<String>,
+    /// Also archive matching alerts already in the inbox.
+    #[serde(default)]
+    apply: bool,
+}
+
+/// Reload the suppressions used on ingest after a change.
+async fn reload(context: &ServerContext) -> Result<(), ApiError> {
+    let suppressions = context.config_repo.get_suppressions().await?;
+    context.suppressions.set(suppressions);
+    Ok(())
+}
+
+async fn list_suppressions(
+    _session: SessionExtractor,
+    State(context): State<Arc<ServerContext>>,
+) -> Result<impl IntoResponse, ApiError> {
+    let suppressions = context.config_repo.get_suppressions().await?;
+    Ok(Json(json!({
+        "suppressions": suppressions,
+    })))
+}
+
+async fn create_suppression(
+    SessionExtractor(session): SessionExtractor,
+    State(context): State<Arc<ServerContext>>,
+    Json(request): Json<SuppressionRequest>,
+) -> Result<impl IntoResponse, ApiError> {
+    let expires_at = match (&request.expires_at, &request.expires_in) {
+        (Some(_), Some(_)) => {
+            return Err(ApiError::bad_request(
+                "only one of expires_at and expires_in may be set",
+            ));
+        }
+        (Some(expires_at), None) => Some(
+            crate::datetime::parse(expires_at, request.tz_offset.as_deref())
+                .map_err(|err| ApiError::bad_request(format!("expires_at: {err}")))?,
+        ),
+        (None, Some(expires_in)) => {
+            let duration = humantime::parse_duration(expires_in)
+                .map_err(|err| ApiError::bad_request(format!("expires_in: {err}")))?;
+            let duration = chrono::Duration::from_std(duration)
+                .map_err(|_| ApiError::bad_request("expires_in"))?;
+            Some(DateTime::now().add(duration))
+        }
+        (None, None) => None,
+    };
+    let sensor = request
+        .sensor
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty());
+    let fields = SuppressionFields {
+        signature_id: request.signature_id,
+        src_ip: request.src_ip,
+        dest_ip: request.dest_ip,
+        sensor,
+        comment: request.comment,
+        expires_at,
+    };
+    let id = context
+        .config_repo
+        .add_suppression(fields, session.username.as_deref())
+        .await?;
+    reload(&context).await?;
+    let suppression = context.config_repo.get_suppression(id).await?;
+    info!(
+        "Created suppression {} for signature ID {} by user {:?}",
+        id, suppression.signature_id, session.username
+    );
+    let archived = if request.apply {
+        Some(archive_matching(&context, &suppression).await?)
+    } else {
+        None
+    };
+    Ok((
+        StatusCode::CREATED,
+        Json(json!({
+            "suppression": suppression,
+            "archived": archived,
+        })),
+    ))
+}
+
+async fn get_suppression(
+    _session: SessionExtractor,
+    State(context): State<Arc<ServerContext>>,
+    Path(id): Path<i64>,
+) -> Result<impl IntoResponse, ApiError> {
+    let suppression = context.config_repo.get_suppression(id).await?;
+    Ok(Json(suppression))
+}
+
+async fn delete_suppression(
+    SessionExtractor(session): SessionExtractor,
+    State(context): State<Arc<ServerContext>>,
+    Path(id): Path<i64>,
+) -> Result<impl IntoResponse, ApiError> {
+    context.config_repo.delete_suppression(id).await?;
+    reload(&context).await?;
+    info!("Deleted suppression {} by user {:?}", id, session.username);
+    Ok(StatusCode::OK)
+}
+
+/// Run a suppression once against the alerts in the inbox.
+async fn apply_suppression(
+    _session: SessionExtractor,
+    State(context): State<Arc<ServerContext>>,
+    Path(id): Path<i64>,
+) -> Result<impl IntoResponse, ApiError> {
+    let suppression = context.config_repo.get_suppression(id).await?;
+    if suppression.is_expired(&DateTime::now()) {
+        return Err(ApiError::bad_request("suppression has expired"));
+    }
+    let archived = archive_matching(&context, &suppression).await?;
+    Ok(Json(json!({
+        "archived": archived,
+    })))
+}
+
+/// Archive the unarchived alerts matching a suppression, returning the
+/// number of alerts archived.
+///
+/// The datastore query only narrows the alerts down by signature ID
+/// and sensor, addresses are matched here so networks work the same
+/// for all datastores. The matches are archived a page at a time.
+async fn archive_matching(
+    context: &ServerContext,
+    suppression: &Suppression,
+) -> Result<u64, ApiError> {
+    let mut query_string = vec![
+        QueryElement {
+            negated: false,
+            value: QueryValue::KeyValue(
+                "alert.signature_id".to_string(),
+                suppression.signature_id.to_string(),
+            ),
+        },
+        QueryElement {
+            negated: true,
+            value: QueryValue::KeyValue("tag".to_string(), TAG_ARCHIVED.to_string()),
+        },
+    ];
+    if let Some(sensor) = &suppression.sensor {
+        query_string.push(QueryElement {
+            negated: false,
+            value: QueryValue::KeyValue("host".to_string(), sensor.to_string()),
+        });
+    }
+    let params = EventExportParams {
+        event_type: Some("alert".to_string()),
+        query_string,
+        size: PAGE_SIZE,
+    };
+
+    let mut cursor: Option<EventCursor> = None;
+    let mut archived = 0;
+    loop {
+        let page = context.datastore.export_page(&params, cursor).await?;
+        let event_ids: Vec<String> = page
+            .events
+            .iter()
+            .filter(|event| suppression.matches(&event["_source"]))
+            .map(|event| match &event["_id"] {
+                serde_json::Value::String(id) => id.to_string(),
+                id => id.to_string(),
+            })
+            .collect();
+        archived += context.datastore.archive_events_by_id(&event_ids).await?;
+        cursor = page.cursor;
+        if cursor.is_none() {
+            break;
+        }
+    }
+    info!(
+        "Archived {} existing alerts matching suppression {}",
+        archived, suppression.id
+    );
+    Ok(archived)
+}
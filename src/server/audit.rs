@@ -0,0 +1,152 @@
+// SPDX-FileCopyrightText: (C) 2024 Jason Ish <jason@codemonkey.net>
+// SPDX-License-Identifier: MIT
+
+//! Audit logging of user actions.
+//!
+//! Requests that change something, such as triage actions, are
+//! recorded by middleware. The session is not known until the handler
+//! extracts it, so the middleware places an `AuditSession` slot in the
+//! request extensions that the `SessionExtractor` fills in.
+
+use std::net::SocketAddr;
+use std::sync::{Arc, OnceLock};
+
+use axum::body::Body;
+use axum::extract::{ConnectInfo, MatchedPath, Request, State};
+use axum::http::{header, HeaderMap, Method, StatusCode};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use tracing::error;
+
+use super::session::Session;
+use super::ServerContext;
+
+/// Slot for the session of the request being audited.
+#[derive(Clone, Default)]
+pub(crate) struct AuditSession(Arc<OnceLock<Arc<Session>>>);
+
+impl AuditSession {
+    pub(crate) fn set(&self, session: Arc<Session>) {
+        let _ = self.0.set(session);
+    }
+
+    fn get(&self) -> Option<Arc<Session>> {
+        self.0.get().cloned()
+    }
+}
+
+/// Requests not audited by the middleware, as they are audited
+/// explicitly or do not change anything.
+const SKIP_PATHS: &[&str] = &[
+    "/api/1/login",
+    "/api/1/logout",
+    "/api/1/eve2pcap",
+    "/api/1/submit",
+];
+
+/// Request bodies up to this size are recorded with the action.
+const MAX_BODY_SIZE: usize = 64 * 1024;
+
+/// Return the address of the client, taking the `x-forwarded-for`
+/// header into account if running behind a reverse proxy.
+pub(crate) fn remote_addr(
+    context: &ServerContext,
+    headers: &HeaderMap,
+    connect_info: Option<&ConnectInfo<SocketAddr>>,
+) -> Option<String> {
+    if context.config.http_reverse_proxy {
+        if let Some(forwarded_for) = headers.get("x-forwarded-for").and_then(|h| h.to_str().ok()) {
+            return Some(forwarded_for.to_string());
+        }
+    }
+    connect_info.map(|ConnectInfo(addr)| addr.ip().to_string())
+}
+
+/// Record an audit entry, logging any error instead of failing the
+/// request.
+pub(crate) async fn record(
+    context: &ServerContext,
+    username: Option<&str>,
+    remote_addr: Option<&str>,
+    action: &str,
+    details: Option<serde_json::Value>,
+) {
+    if let Err(err) = context
+        .config_repo
+        .add_audit(username, remote_addr, action, details)
+        .await
+    {
+        error!("Failed to record audit entry: action={}: {:?}", action, err);
+    }
+}
+
+pub(crate) async fn middleware(
+    State(context): State<Arc<ServerContext>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let method = request.method().clone();
+    if !matches!(
+        method,
+        Method::POST | Method::PUT | Method::PATCH | Method::DELETE
+    ) || SKIP_PATHS.contains(&request.uri().path())
+    {
+        return next.run(request).await;
+    }
+
+    let path = request.uri().path().to_string();
+    let route = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|p| p.as_str().to_string())
+        .unwrap_or_else(|| path.clone());
+    let remote_addr = remote_addr(
+        &context,
+        request.headers(),
+        request.extensions().get::<ConnectInfo<SocketAddr>>(),
+    );
+
+    let content_length = request
+        .headers()
+        .get(header::CONTENT_LENGTH)
+        .and_then(|h| h.to_str().ok())
+        .and_then(|h| h.parse::<usize>().ok());
+    let (mut request, body) = if content_length.is_some_and(|n| n <= MAX_BODY_SIZE) {
+        let (parts, body) = request.into_parts();
+        let bytes = match axum::body::to_bytes(body, MAX_BODY_SIZE).await {
+            Ok(bytes) => bytes,
+            Err(err) => {
+                return (StatusCode::BAD_REQUEST, err.to_string()).into_response();
+            }
+        };
+        let body: Option<serde_json::Value> = serde_json::from_slice(&bytes).ok();
+        (Request::from_parts(parts, Body::from(bytes)), body)
+    } else {
+        (request, None)
+    };
+
+    let slot = AuditSession::default();
+    request.extensions_mut().insert(slot.clone());
+
+    let response = next.run(request).await;
+
+    // Requests without a session were rejected before doing anything.
+    if let Some(session) = slot.get() {
+        let action = format!("{method} {route}");
+        let details = json!({
+            "path": path,
+            "status": response.status().as_u16(),
+            "body": body,
+        });
+        record(
+            &context,
+            session.username.as_deref(),
+            remote_addr.as_deref(),
+            &action,
+            Some(details),
+        )
+        .await;
+    }
+
+    response
+}
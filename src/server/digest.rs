@@ -0,0 +1,463 @@
+// SPDX-FileCopyrightText: (C) 2024 Jason Ish <jason@codemonkey.net>
+// SPDX-License-Identifier: MIT
+
+//! Email digests of new alerts.
+//!
+//! Each configured digest periodically queries the inbox for alerts
+//! received since the previous digest and mails a summary with the
+//! counts per signature and the top sources.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use lettre::message::header::ContentType;
+use lettre::message::Mailbox;
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor};
+use serde::Deserialize;
+use tracing::{debug, error, info};
+
+use super::ServerContext;
+use crate::datetime::DateTime;
+use crate::elastic::AlertQueryOptions;
+use crate::eventrepo::AggAlert;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum SmtpTls {
+    None,
+    Starttls,
+    Tls,
+}
+
+/// SMTP server configuration, from `notifications.smtp`.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub(crate) struct SmtpConfig {
+    #[serde(default = "default_host")]
+    pub host: String,
+    /// Defaults to the standard port for the TLS mode.
+    pub port: Option<u16>,
+    #[serde(default = "default_tls")]
+    pub tls: SmtpTls,
+    pub username: Option<String>,
+    pub password: Option<String>,
+    pub from: String,
+}
+
+fn default_host() -> String {
+    "localhost".to_string()
+}
+
+fn default_tls() -> SmtpTls {
+    SmtpTls::None
+}
+
+/// Digest configuration, from the `notifications.digests` list.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub(crate) struct DigestConfig {
+    pub name: Option<String>,
+    pub to: Vec<String>,
+    /// How often to send the digest, e.g. "1h".
+    #[serde(default = "default_interval")]
+    pub interval: String,
+    /// Only include alerts matching this query string.
+    pub query: Option<String>,
+    pub subject: Option<String>,
+    /// Number of signatures and sources to list.
+    #[serde(default = "default_top")]
+    pub top: usize,
+    /// Send a digest even if there are no new alerts.
+    #[serde(default)]
+    pub send_empty: bool,
+}
+
+fn default_interval() -> String {
+    "1h".to_string()
+}
+
+fn default_top() -> usize {
+    10
+}
+
+#[derive(Debug, Default, PartialEq, Eq)]
+struct SignatureSummary {
+    signature_id: u64,
+    signature: String,
+    severity: u64,
+    /// Number of alerts.
+    count: u64,
+    /// Number of alert groups.
+    groups: u64,
+}
+
+#[derive(Debug, Default)]
+struct Summary {
+    groups: usize,
+    alerts: u64,
+    signatures: Vec<SignatureSummary>,
+    sources: Vec<(String, u64)>,
+}
+
+/// Summarize alert groups by signature and source, keeping the top
+/// `top` of each by number of alerts.
+fn summarize(alerts: &[AggAlert], top: usize) -> Summary {
+    let mut signatures: HashMap<u64, SignatureSummary> = HashMap::new();
+    let mut sources: HashMap<String, u64> = HashMap::new();
+    let mut total = 0;
+    for alert in alerts {
+        let count = alert.metadata.count;
+        total += count;
+        let signature_id = alert.source["alert"]["signature_id"].as_u64().unwrap_or(0);
+        let entry = signatures
+            .entry(signature_id)
+            .or_insert_with(|| SignatureSummary {
+                signature_id,
+                signature: alert.source["alert"]["signature"]
+                    .as_str()
+                    .unwrap_or_default()
+                    .to_string(),
+                severity: alert.source["alert"]["severity"].as_u64().unwrap_or(0),
+                ..Default::default()
+            });
+        entry.count += count;
+        entry.groups += 1;
+        if let Some(src_ip) = alert.source["src_ip"].as_str() {
+            *sources.entry(src_ip.to_string()).or_default() += count;
+        }
+    }
+
+    let mut signatures: Vec<SignatureSummary> = signatures.into_values().collect();
+    signatures.sort_by(|a, b| {
+        b.count
+            .cmp(&a.count)
+            .then(a.signature_id.cmp(&b.signature_id))
+    });
+    signatures.truncate(top);
+
+    let mut sources: Vec<(String, u64)> = sources.into_iter().collect();
+    sources.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+    sources.truncate(top);
+
+    Summary {
+        groups: alerts.len(),
+        alerts: total,
+        signatures,
+        sources,
+    }
+}
+
+fn format_summary(summary: &Summary, from: &DateTime, to: &DateTime) -> String {
+    let mut body = format!(
+        "New alerts from {} to {}\n\nAlert groups: {}\nAlerts: {}\n",
+        from.to_rfc3339_utc(),
+        to.to_rfc3339_utc(),
+        summary.groups,
+        summary.alerts
+    );
+    if !summary.signatures.is_empty() {
+        body.push_str("\nTop signatures:\n");
+        for s in &summary.signatures {
+            body.push_str(&format!(
+                "  {:>8} alerts  {:>5} groups  [{}] {} (sid {})\n",
+                s.count, s.groups, s.severity, s.signature, s.signature_id
+            ));
+        }
+    }
+    if !summary.sources.is_empty() {
+        body.push_str("\nTop sources:\n");
+        for (src_ip, count) in &summary.sources {
+            body.push_str(&format!("  {:>8} alerts  {}\n", count, src_ip));
+        }
+    }
+    body
+}
+
+struct Digest {
+    name: String,
+    to: Vec<Mailbox>,
+    interval: Duration,
+    query: Option<String>,
+    subject: String,
+    top: usize,
+    send_empty: bool,
+}
+
+impl Digest {
+    fn new(config: DigestConfig) -> anyhow::Result<Self> {
+        let name = config.name.unwrap_or_else(|| "digest".to_string());
+        if config.to.is_empty() {
+            bail!("digest {}: no recipients", name);
+        }
+        let to = config
+            .to
+            .iter()
+            .map(|to| to.parse::<Mailbox>())
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|err| anyhow!("digest {}: bad recipient: {}", name, err))?;
+        let interval = humantime::parse_duration(&config.interval)
+            .map_err(|err| anyhow!("digest {}: bad interval: {}", name, err))?;
+        if interval.is_zero() {
+            bail!("digest {}: interval must not be 0", name);
+        }
+        Ok(Self {
+            to,
+            interval,
+            query: config.query,
+            subject: config
+                .subject
+                .unwrap_or_else(|| "EveBox alert digest".to_string()),
+            top: config.top,
+            send_empty: config.send_empty,
+            name,
+        })
+    }
+}
+
+/// Sends the digests through an SMTP server.
+struct Mailer {
+    from: Mailbox,
+    transport: AsyncSmtpTransport<Tokio1Executor>,
+}
+
+impl Mailer {
+    fn new(config: SmtpConfig) -> anyhow::Result<Self> {
+        let from = config
+            .from
+            .parse::<Mailbox>()
+            .map_err(|err| anyhow!("bad smtp from address: {}", err))?;
+        let mut builder = match config.tls {
+            SmtpTls::None => {
+                AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(&config.host).port(25)
+            }
+            SmtpTls::Starttls => {
+                AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(&config.host)?
+            }
+            SmtpTls::Tls => AsyncSmtpTransport::<Tokio1Executor>::relay(&config.host)?,
+        };
+        if let Some(port) = config.port {
+            builder = builder.port(port);
+        }
+        if let (Some(username), Some(password)) = (config.username, config.password) {
+            builder = builder.credentials(Credentials::new(username, password));
+        }
+        Ok(Self {
+            from,
+            transport: builder.build(),
+        })
+    }
+
+    async fn send(&self, digest: &Digest, body: String) -> anyhow::Result<()> {
+        let mut message = Message::builder()
+            .from(self.from.clone())
+            .subject(&digest.subject);
+        for to in &digest.to {
+            message = message.to(to.clone());
+        }
+        let message = message.header(ContentType::TEXT_PLAIN).body(body)?;
+        self.transport.send(message).await?;
+        Ok(())
+    }
+}
+
+/// Build and send a digest of the alerts in the inbox with an event
+/// timestamp in the period from `from` to `to`. Returns false if there
+/// was nothing to send.
+async fn run_digest(
+    context: &ServerContext,
+    mailer: &Mailer,
+    digest: &Digest,
+    from: &DateTime,
+    to: &DateTime,
+) -> anyhow::Result<bool> {
+    let options = AlertQueryOptions {
+        timestamp_gte: Some(from.clone()),
+        timestamp_lte: Some(to.clone()),
+        query_string: digest.query.clone(),
+        tags: vec!["-evebox.archived".to_string()],
+        ..Default::default()
+    };
+    let alerts = context.datastore.alerts(options).await?;
+    let summary = summarize(&alerts.events, digest.top);
+    if summary.groups == 0 && !digest.send_empty {
+        return Ok(false);
+    }
+    mailer
+        .send(digest, format_summary(&summary, from, to))
+        .await?;
+    Ok(true)
+}
+
+/// Start a task for each configured digest.
+pub(crate) fn start(
+    context: Arc<ServerContext>,
+    smtp: SmtpConfig,
+    digests: Vec<DigestConfig>,
+) -> anyhow::Result<()> {
+    let mailer = Arc::new(Mailer::new(smtp)?);
+    let digests = digests
+        .into_iter()
+        .map(Digest::new)
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    for digest in digests {
+        info!(
+            "Sending alert digest {} every {}",
+            digest.name,
+            humantime::format_duration(digest.interval)
+        );
+        let context = context.clone();
+        let mailer = mailer.clone();
+        tokio::spawn(async move {
+            let mut from = DateTime::now();
+            let start = tokio::time::Instant::now() + digest.interval;
+            let mut interval = tokio::time::interval_at(start, digest.interval);
+            loop {
+                interval.tick().await;
+                let to = DateTime::now();
+                match run_digest(&context, &mailer, &digest, &from, &to).await {
+                    Ok(sent) => {
+                        debug!("Digest {} complete, sent={}", digest.name, sent);
+                        from = to;
+                    }
+                    Err(err) => {
+                        // The next digest will cover this period as
+                        // well.
+                        error!("Failed to send digest {}: {:#}", digest.name, err);
+                    }
+                }
+            }
+        });
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::eventrepo::AggAlertMetadata;
+    use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+
+    fn agg_alert(signature_id: u64, src_ip: &str, count: u64) -> AggAlert {
+        AggAlert {
+            id: String::new(),
+            source: json!({
+                "src_ip": src_ip,
+                "alert": {
+                    "signature_id": signature_id,
+                    "signature": format!("Signature {signature_id}"),
+                    "severity": 2,
+                },
+            }),
+            metadata: AggAlertMetadata {
+                count,
+                escalated_count: 0,
+                min_timestamp: DateTime::now(),
+                max_timestamp: DateTime::now(),
+            },
+        }
+    }
+
+    #[test]
+    fn test_summarize() {
+        let alerts = vec![
+            agg_alert(1, "10.0.0.1", 5),
+            agg_alert(2, "10.0.0.1", 3),
+            agg_alert(2, "10.0.0.2", 10),
+            agg_alert(3, "10.0.0.3", 1),
+        ];
+        let summary = summarize(&alerts, 2);
+        assert_eq!(summary.groups, 4);
+        assert_eq!(summary.alerts, 19);
+        assert_eq!(
+            summary
+                .signatures
+                .iter()
+                .map(|s| (s.signature_id, s.count, s.groups))
+                .collect::<Vec<_>>(),
+            vec![(2, 13, 2), (1, 5, 1)]
+        );
+        assert_eq!(
+            summary.sources,
+            vec![("10.0.0.2".to_string(), 10), ("10.0.0.1".to_string(), 8)]
+        );
+    }
+
+    /// A minimal SMTP server accepting a single message, returning the
+    /// message data.
+    async fn smtp_sink(listener: tokio::net::TcpListener) -> String {
+        let (stream, _) = listener.accept().await.unwrap();
+        let (reader, mut writer) = stream.into_split();
+        let mut reader = BufReader::new(reader);
+        writer.write_all(b"220 localhost ESMTP\r\n").await.unwrap();
+        let mut data = String::new();
+        let mut in_data = false;
+        loop {
+            let mut line = String::new();
+            if reader.read_line(&mut line).await.unwrap() == 0 {
+                break;
+            }
+            if in_data {
+                if line == ".\r\n" {
+                    in_data = false;
+                    writer.write_all(b"250 OK\r\n").await.unwrap();
+                } else {
+                    data.push_str(&line);
+                }
+                continue;
+            }
+            let command = line.to_uppercase();
+            if command.starts_with("EHLO") || command.starts_with("HELO") {
+                writer.write_all(b"250 localhost\r\n").await.unwrap();
+            } else if command.starts_with("DATA") {
+                in_data = true;
+                writer.write_all(b"354 Go ahead\r\n").await.unwrap();
+            } else if command.starts_with("QUIT") {
+                writer.write_all(b"221 Bye\r\n").await.unwrap();
+                break;
+            } else {
+                writer.write_all(b"250 OK\r\n").await.unwrap();
+            }
+        }
+        data
+    }
+
+    #[tokio::test]
+    async fn test_send() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let sink = tokio::spawn(smtp_sink(listener));
+
+        let mailer = Mailer::new(SmtpConfig {
+            host: "127.0.0.1".to_string(),
+            port: Some(port),
+            tls: SmtpTls::None,
+            username: None,
+            password: None,
+            from: "evebox@localhost".to_string(),
+        })
+        .unwrap();
+        let digest = Digest::new(DigestConfig {
+            name: None,
+            to: vec!["soc@localhost".to_string()],
+            interval: "1h".to_string(),
+            query: None,
+            subject: None,
+            top: 10,
+            send_empty: false,
+        })
+        .unwrap();
+        let summary = summarize(&[agg_alert(2013028, "10.0.0.1", 5)], digest.top);
+        let now = DateTime::now();
+        let body = format_summary(&summary, &now, &now);
+        mailer.send(&digest, body).await.unwrap();
+        drop(mailer);
+
+        let data = sink.await.unwrap();
+        assert!(data.contains("To: soc@localhost"));
+        assert!(data.contains("Subject: EveBox alert digest"));
+        assert!(data.contains("Signature 2013028 (sid 2013028)"));
+        assert!(data.contains("10.0.0.1"));
+    }
+}
@@ -0,0 +1,166 @@
+// SPDX-FileCopyrightText: (C) 2024 Jason Ish <jason@codemonkey.net>
+// SPDX-License-Identifier: MIT
+
+//! Health and readiness endpoints for orchestrators and load
+//! balancers. Neither requires authentication.
+//!
+//! - `/healthz`: the process is alive and serving requests.
+//! - `/readyz`: the datastore, configuration database and EVE input
+//!   are functional. Returns 503 if any check fails.
+
+use super::ServerContext;
+use crate::eve::watcher::CHECK_INTERVAL;
+use crate::eventrepo::EventRepo;
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::response::IntoResponse;
+use axum::Json;
+use serde::Serialize;
+use std::future::Future;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Time allowed for each readiness check.
+const CHECK_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "lowercase")]
+enum Status {
+    Ok,
+    Error,
+    Disabled,
+}
+
+#[derive(Debug, Serialize)]
+struct Check {
+    status: Status,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+impl Check {
+    fn ok() -> Self {
+        Self {
+            status: Status::Ok,
+            error: None,
+        }
+    }
+
+    fn error(error: impl ToString) -> Self {
+        Self {
+            status: Status::Error,
+            error: Some(error.to_string()),
+        }
+    }
+
+    fn is_error(&self) -> bool {
+        matches!(self.status, Status::Error)
+    }
+
+    /// Run a check, failing it if it doesn't complete in time.
+    async fn run<F, E>(f: F) -> Self
+    where
+        F: Future<Output = Result<(), E>>,
+        E: ToString,
+    {
+        match tokio::time::timeout(CHECK_TIMEOUT, f).await {
+            Ok(Ok(())) => Self::ok(),
+            Ok(Err(err)) => Self::error(err),
+            Err(_) => Self::error("timed out"),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct DatastoreCheck {
+    #[serde(rename = "type")]
+    kind: &'static str,
+    #[serde(flatten)]
+    check: Check,
+}
+
+#[derive(Debug, Serialize)]
+struct InputCheck {
+    #[serde(flatten)]
+    check: Check,
+    files: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    seconds_since_check: Option<u64>,
+}
+
+#[derive(Debug, Serialize)]
+struct Checks {
+    datastore: DatastoreCheck,
+    configdb: Check,
+    input: InputCheck,
+}
+
+pub(crate) async fn healthz() -> impl IntoResponse {
+    Json(json!({"status": Status::Ok}))
+}
+
+pub(crate) async fn readyz(State(context): State<Arc<ServerContext>>) -> impl IntoResponse {
+    let datastore = match &context.datastore {
+        EventRepo::Elastic(elastic) => DatastoreCheck {
+            kind: "elasticsearch",
+            check: Check::run(async { elastic.client.get_info().await.map(|_| ()) }).await,
+        },
+        EventRepo::SQLite(sqlite) => DatastoreCheck {
+            kind: "sqlite",
+            check: Check::run(async {
+                sqlx::query_scalar::<_, i64>("SELECT 1")
+                    .fetch_one(&sqlite.pool)
+                    .await
+                    .map(|_| ())
+            })
+            .await,
+        },
+    };
+
+    let configdb = Check::run(async {
+        sqlx::query_scalar::<_, i64>("SELECT 1")
+            .fetch_one(&context.config_repo.pool)
+            .await
+            .map(|_| ())
+    })
+    .await;
+
+    let input = match &context.input {
+        None => InputCheck {
+            check: Check {
+                status: Status::Disabled,
+                error: None,
+            },
+            files: vec![],
+            seconds_since_check: None,
+        },
+        Some(input) => {
+            let since_last_check = input.since_last_check();
+            // The watcher checks for new files periodically, if it
+            // hasn't for a while it has likely stalled.
+            let check = match since_last_check {
+                None => Check::error("watcher has not started"),
+                Some(since) if since > CHECK_INTERVAL * 4 => Check::error("watcher has stalled"),
+                Some(_) => Check::ok(),
+            };
+            InputCheck {
+                check,
+                files: input.files(),
+                seconds_since_check: since_last_check.map(|since| since.as_secs()),
+            }
+        }
+    };
+
+    let ready = !(datastore.check.is_error() || configdb.is_error() || input.check.is_error());
+    let checks = Checks {
+        datastore,
+        configdb,
+        input,
+    };
+    let (code, status) = if ready {
+        (StatusCode::OK, Status::Ok)
+    } else {
+        (StatusCode::SERVICE_UNAVAILABLE, Status::Error)
+    };
+    (code, Json(json!({"status": status, "checks": checks})))
+}
@@ -6,14 +6,20 @@ use crate::bookmark;
 use crate::config::Config;
 use crate::elastic;
 use crate::elastic::Version;
-use crate::eve::filters::{AddFieldFilter, AddRuleFilter};
+use crate::eve::filters::{AddFieldFilter, AddRuleFilter, RuleMetadataFilter};
 use crate::eve::watcher::EvePatternWatcher;
 use crate::eventrepo::EventRepo;
+use crate::notifications::{Notifier, WebhookConfig};
 use crate::server::api;
+use crate::server::audit::{self, AuditSession};
+use crate::server::health;
+use crate::server::metrics;
 use crate::server::session::Session;
+use crate::sqlite::configrepo::audit::action as audit_action;
 use crate::sqlite::configrepo::{self, ConfigRepo};
 use crate::sqlite::connection::init_event_db;
 use crate::sqlite::{self};
+use crate::syslog::{SyslogConfig, SyslogEventSink};
 use anyhow::Result;
 use axum::async_trait;
 use axum::extract::connect_info::IntoMakeServiceWithConnectInfo;
@@ -84,6 +90,8 @@ pub async fn main(args: &clap::ArgMatches) -> Result<()> {
         || config.get_bool("no-check-certificate")?;
     server_config.http_request_logging = config.get_bool("http.request-logging")?;
     server_config.http_reverse_proxy = config.get_bool("http.reverse-proxy")?;
+    server_config.metrics_enabled = config.get_bool("http.metrics.enabled")?;
+    server_config.metrics_token = config.get("http.metrics.token")?;
 
     debug!(
         "Certificate checks disabled: {}",
@@ -142,6 +150,18 @@ pub async fn main(args: &clap::ArgMatches) -> Result<()> {
         );
     }
 
+    let webhooks: Option<Vec<WebhookConfig>> = config.get_value("notifications.webhooks")?;
+    if let Some(webhooks) = webhooks.filter(|webhooks| !webhooks.is_empty()) {
+        info!("Enabling {} notification webhook(s)", webhooks.len());
+        context.notifier = Some(Notifier::start(webhooks)?);
+    }
+
+    let syslog: Option<SyslogConfig> = config.get_value("syslog")?;
+    if let Some(syslog) = syslog.filter(|syslog| syslog.enabled) {
+        info!("Forwarding events to syslog server {}", syslog.address);
+        context.syslog = Some(SyslogEventSink::start(syslog)?);
+    }
+
     if let Some(filename) = config_filename {
         match load_event_services(filename) {
             Err(err) => {
@@ -153,32 +173,42 @@ pub async fn main(args: &clap::ArgMatches) -> Result<()> {
         }
     }
 
+    // Rules are loaded even if input is disabled, as they are also
+    // available through the API.
+    match config.get_config_value::<Vec<String>>("input.rules") {
+        Ok(Some(rules)) => {
+            let rulemap = Arc::new(crate::rules::load_rules(&rules));
+            crate::rules::watch_rules(rulemap.clone());
+            context.rules = Some(rulemap);
+        }
+        Ok(None) => {}
+        Err(err) => {
+            error!("Failed to read input.rules configuration: {}", err);
+        }
+    }
+
     if is_input_enabled(&config) {
         let input_patterns = get_input_patterns(&config)?;
         if input_patterns.is_empty() {
             bail!("EVE input enabled, but no paths provided");
         }
-        let sink = context.datastore.get_importer().ok_or(anyhow!(
+        let sink = context.get_importer().ok_or(anyhow!(
             "An event importer is not implemented for this datastore"
         ))?;
 
         let mut filters = Vec::new();
 
-        match config.get_config_value::<Vec<String>>("input.rules") {
-            Ok(Some(rules)) => {
-                let rulemap = crate::rules::load_rules(&rules);
-                let rulemap = Arc::new(rulemap);
-                filters.push(crate::eve::filters::EveFilter::AddRuleFilter(
-                    AddRuleFilter {
-                        map: rulemap.clone(),
-                    },
-                ));
-                crate::rules::watch_rules(rulemap);
-            }
-            Ok(None) => {}
-            Err(err) => {
-                error!("Failed to read input.rules configuration: {}", err);
-            }
+        if let Some(rulemap) = &context.rules {
+            filters.push(crate::eve::filters::EveFilter::AddRuleFilter(
+                AddRuleFilter {
+                    map: rulemap.clone(),
+                },
+            ));
+            filters.push(crate::eve::filters::EveFilter::RuleMetadataFilter(
+                RuleMetadataFilter {
+                    map: rulemap.clone(),
+                },
+            ));
         }
 
         let geoip_disabled = config.get_bool("geoip.disabled")?;
@@ -199,6 +229,13 @@ pub async fn main(args: &clap::ArgMatches) -> Result<()> {
         filters.push(crate::eve::filters::EveFilter::AutoArchiveFilter(
             crate::eve::filters::AutoArchiveFilter::default(),
         ));
+        filters.push(context.suppressions.clone().into());
+
+        if config.get_bool("input.community-id")? {
+            let seed: u16 = config.get_value("input.community-id-seed")?.unwrap_or(0);
+            debug!("Community ID enabled with seed {}", seed);
+            filters.push(crate::eve::filters::CommunityIdFilter::new(seed).into());
+        }
 
         let additional_fields: Option<HashMap<String, serde_yaml::Value>> =
             config.get_value("input.additional-fields")?;
@@ -210,6 +247,15 @@ pub async fn main(args: &clap::ArgMatches) -> Result<()> {
             }
         }
 
+        filters.push(crate::eve::filters::EveFilter::SensorTracker(
+            context.sensors.clone(),
+        ));
+
+        // Notify last so notifications see the final event.
+        if let Some(notifier) = &context.notifier {
+            filters.push(crate::eve::filters::EveFilter::Notifier(notifier.clone()));
+        }
+
         let end = config.get_bool("end")?;
 
         let bookmark_directory: Option<String> = config.get_string("input.bookmark-directory");
@@ -222,10 +268,30 @@ pub async fn main(args: &clap::ArgMatches) -> Result<()> {
             bookmark_directory,
             data_directory,
         );
+        context.input = Some(watcher.status());
         watcher.run();
     }
 
     let context = Arc::new(context);
+
+    let sensors: Option<crate::sensors::SensorsConfig> = config.get_value("sensors")?;
+    crate::sensors::start(
+        context.config_repo.clone(),
+        context.sensors.clone(),
+        context.notifier.clone(),
+        sensors.unwrap_or_default(),
+    )?;
+
+    let digests: Option<Vec<crate::server::digest::DigestConfig>> =
+        config.get_value("notifications.digests")?;
+    if let Some(digests) = digests.filter(|digests| !digests.is_empty()) {
+        let smtp: Option<crate::server::digest::SmtpConfig> =
+            config.get_value("notifications.smtp")?;
+        let smtp =
+            smtp.ok_or_else(|| anyhow!("notifications.digests requires notifications.smtp"))?;
+        crate::server::digest::start(context.clone(), smtp, digests)?;
+    }
+
     info!(
         "Starting server on {}:{}, tls={}",
         server_config.host, server_config.port, server_config.tls_enabled
@@ -290,6 +356,15 @@ async fn create_admin_user(context: &ServerContext) -> Result<(String, String)>
         .map(char::from)
         .collect();
     context.config_repo.add_user(username, &password).await?;
+    context.config_repo.set_admin(username, true).await?;
+    audit::record(
+        context,
+        None,
+        None,
+        audit_action::USER_ADD,
+        Some(json!({"username": username, "admin": true})),
+    )
+    .await;
     Ok((username.to_string(), password))
 }
 
@@ -329,8 +404,18 @@ pub(crate) fn build_axum_service(
         .on_response(DefaultOnResponse::new().level(Level::INFO))
         .on_request(());
 
-    let app = axum::Router::new()
-        .merge(api::router())
+    let mut app = axum::Router::new().merge(api::router());
+    if context.config.metrics_enabled {
+        app = app.route("/metrics", axum::routing::get(metrics::handler));
+    }
+    let app = app
+        .route("/healthz", axum::routing::get(health::healthz))
+        .route("/readyz", axum::routing::get(health::readyz))
+        .layer(axum::middleware::from_fn(metrics::middleware))
+        .layer(axum::middleware::from_fn_with_state(
+            context.clone(),
+            audit::middleware,
+        ))
         .layer(DefaultBodyLimit::max(1024 * 1024 * 32))
         .layer(Extension(context.clone()))
         .layer(response_header_layer)
@@ -431,6 +516,9 @@ pub(crate) async fn build_context(
     config_repo: ConfigRepo,
 ) -> Result<ServerContext> {
     let context = ServerContext::new(config, Arc::new(config_repo), datastore);
+    context
+        .suppressions
+        .set(context.config_repo.get_suppressions().await?);
     Ok(context)
 }
 
@@ -550,6 +638,21 @@ where
         req: &mut axum::http::request::Parts,
         state: &S,
     ) -> Result<Self, Self::Rejection> {
+        let result = Self::extract(req, state).await;
+        if let Ok(SessionExtractor(session)) = &result {
+            if let Some(slot) = req.extensions.get::<AuditSession>() {
+                slot.set(session.clone());
+            }
+        }
+        result
+    }
+}
+
+impl SessionExtractor {
+    async fn extract<S: Send + Sync>(
+        req: &mut axum::http::request::Parts,
+        state: &S,
+    ) -> Result<Self, (StatusCode, &'static str)> {
         let Extension(context) = Extension::<Arc<ServerContext>>::from_request_parts(req, state)
             .await
             .unwrap();
@@ -604,6 +707,7 @@ where
                     let session = Session {
                         session_id: Some(session_id.to_string()),
                         username: Some(user.username),
+                        admin: user.admin,
                     };
                     let session = Arc::new(session);
                     let _ = context.session_store.put(session.clone());
@@ -640,7 +744,9 @@ where
                     .await
                 {
                     Ok(user) => {
-                        return Ok(Self(Arc::new(Session::with_username(&user.username))));
+                        let mut session = Session::with_username(&user.username);
+                        session.admin = user.admin;
+                        return Ok(Self(Arc::new(session)));
                     }
                     Err(err) => {
                         warn!(
@@ -656,7 +762,7 @@ where
             return Ok(Self(Arc::new(Session::anonymous(remote_user))));
         }
 
-        return Err((StatusCode::UNAUTHORIZED, "authentication required"));
+        Err((StatusCode::UNAUTHORIZED, "authentication required"))
     }
 }
 
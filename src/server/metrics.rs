@@ -0,0 +1,104 @@
+// SPDX-FileCopyrightText: (C) 2024 Jason Ish <jason@codemonkey.net>
+// SPDX-License-Identifier: MIT
+
+//! The Prometheus `/metrics` endpoint, and HTTP request metrics.
+//!
+//! The endpoint is only served if `http.metrics.enabled` is set, and
+//! does not use the regular authentication. If `http.metrics.token`
+//! is set it must be provided as a bearer token.
+
+use super::api::ApiError;
+use super::ServerContext;
+use crate::eventrepo::EventRepo;
+use crate::metrics::{header, METRICS};
+use crate::sqlite::info::Info;
+use axum::extract::{MatchedPath, Request, State};
+use axum::http::header::CONTENT_TYPE;
+use axum::http::StatusCode;
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use axum_extra::headers::authorization::Bearer;
+use axum_extra::headers::Authorization;
+use axum_extra::TypedHeader;
+use std::fmt::Write;
+use std::sync::Arc;
+use std::time::Instant;
+
+pub(crate) async fn handler(
+    State(context): State<Arc<ServerContext>>,
+    authorization: Option<TypedHeader<Authorization<Bearer>>>,
+) -> Result<Response, ApiError> {
+    if let Some(token) = &context.config.metrics_token {
+        let authorized = authorization.is_some_and(|TypedHeader(Authorization(bearer))| {
+            constant_time_eq(bearer.token().as_bytes(), token.as_bytes())
+        });
+        if !authorized {
+            return Ok((StatusCode::UNAUTHORIZED, "unauthorized").into_response());
+        }
+    }
+
+    let mut out = String::new();
+    METRICS.render(&mut out);
+
+    header(
+        &mut out,
+        "evebox_sessions_active",
+        "gauge",
+        "Active user sessions.",
+    );
+    let _ = writeln!(
+        out,
+        "evebox_sessions_active {}",
+        context.session_store.count()
+    );
+
+    if let EventRepo::SQLite(sqlite) = &context.datastore {
+        let mut conn = sqlite.pool.acquire().await?;
+        let mut info = Info::new(&mut conn);
+        let page_size = info.pragma_i64("page_size").await?;
+        let page_count = info.pragma_i64("page_count").await?;
+        header(
+            &mut out,
+            "evebox_sqlite_database_size_bytes",
+            "gauge",
+            "Size of the SQLite event database.",
+        );
+        let _ = writeln!(
+            out,
+            "evebox_sqlite_database_size_bytes {}",
+            page_size * page_count
+        );
+    }
+
+    Ok((
+        [(CONTENT_TYPE, "text/plain; version=0.0.4; charset=utf-8")],
+        out,
+    )
+        .into_response())
+}
+
+/// Compare without returning early on the first difference, so the
+/// token can't be guessed a byte at a time from the response time.
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    a.iter().zip(b).fold(0, |acc, (a, b)| acc | (a ^ b)) == 0
+}
+
+/// Middleware recording the count and latency of HTTP requests by
+/// route.
+pub(crate) async fn middleware(request: Request, next: Next) -> Response {
+    // Use the route pattern rather than the path, so event IDs and
+    // such don't each get their own label.
+    let route = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|path| path.as_str().to_string())
+        .unwrap_or_else(|| "other".to_string());
+    let method = request.method().to_string();
+    let timer = Instant::now();
+    let response = next.run(request).await;
+    METRICS.http_request(&method, &route, response.status().as_u16(), timer.elapsed());
+    response
+}
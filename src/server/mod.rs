@@ -1,8 +1,16 @@
 // SPDX-FileCopyrightText: (C) 2020 Jason Ish <jason@codemonkey.net>
 // SPDX-License-Identifier: MIT
 
+use crate::eve::filters::SuppressionFilter;
+use crate::eve::watcher::WatcherStatus;
 use crate::eventrepo::EventRepo;
+use crate::importer::EventSink;
+use crate::live::LiveEvents;
+use crate::notifications::Notifier;
+use crate::rules::RuleMap;
+use crate::sensors::SensorTracker;
 use crate::sqlite::configrepo::ConfigRepo;
+use crate::syslog::SyslogEventSink;
 pub(crate) use main::build_context;
 pub use main::main;
 use serde::Serialize;
@@ -11,7 +19,11 @@ use std::path::PathBuf;
 use std::sync::Arc;
 
 pub mod api;
+pub(crate) mod audit;
+pub(crate) mod digest;
+pub(crate) mod health;
 pub(crate) mod main;
+pub(crate) mod metrics;
 pub mod session;
 
 #[derive(Serialize, Default, Debug)]
@@ -26,6 +38,21 @@ pub(crate) struct ServerContext {
     pub config_repo: Arc<ConfigRepo>,
     pub event_services: Option<serde_json::Value>,
     pub defaults: Defaults,
+    /// Suppressions applied to events as they are received.
+    pub suppressions: SuppressionFilter,
+    /// Webhook notifications, if any are configured.
+    pub notifier: Option<Notifier>,
+    /// Syslog output for received events, in addition to the
+    /// datastore.
+    pub syslog: Option<SyslogEventSink>,
+    /// Newly imported events, for live tailing.
+    pub live: LiveEvents,
+    /// Status of the EVE input, if enabled.
+    pub input: Option<WatcherStatus>,
+    /// Activity of sensors sending events.
+    pub sensors: SensorTracker,
+    /// Rules loaded from `input.rules`, if configured.
+    pub rules: Option<Arc<RuleMap>>,
 }
 
 impl ServerContext {
@@ -41,8 +68,27 @@ impl ServerContext {
             config_repo,
             event_services: None,
             defaults: Defaults::default(),
+            suppressions: SuppressionFilter::default(),
+            notifier: None,
+            syslog: None,
+            live: LiveEvents::default(),
+            input: None,
+            sensors: SensorTracker::default(),
+            rules: None,
         }
     }
+
+    /// Return a sink for received events, the datastore importer
+    /// along with any additional outputs. The additional outputs are
+    /// only committed once the datastore commit succeeds.
+    pub(crate) fn get_importer(&self) -> Option<EventSink> {
+        let mut sinks = vec![self.datastore.get_importer()?];
+        if let Some(syslog) = &self.syslog {
+            sinks.push(EventSink::Syslog(syslog.clone()));
+        }
+        sinks.push(EventSink::Live(self.live.sink()));
+        Some(EventSink::Multi(sinks))
+    }
 }
 
 #[derive(Debug, Default, Clone)]
@@ -64,4 +110,8 @@ pub(crate) struct ServerConfig {
     pub authentication_required: bool,
     pub http_reverse_proxy: bool,
     pub http_request_logging: bool,
+    /// Serve Prometheus metrics at `/metrics`.
+    pub metrics_enabled: bool,
+    /// Bearer token required to read the metrics, if set.
+    pub metrics_token: Option<String>,
 }
@@ -44,12 +44,19 @@ impl SessionStore {
         let mut cache = self.cache.lock().unwrap();
         cache.remove(session_id).is_some()
     }
+
+    /// Return the number of active sessions.
+    pub fn count(&self) -> usize {
+        self.cache.lock().unwrap().len()
+    }
 }
 
 #[derive(Debug, Default, Clone)]
 pub(crate) struct Session {
     pub session_id: Option<String>,
     pub username: Option<String>,
+    /// Administrators may act on data owned by other users.
+    pub admin: bool,
 }
 
 impl Session {
@@ -58,6 +65,7 @@ impl Session {
         Session {
             session_id: Some(session_id),
             username: None,
+            admin: false,
         }
     }
 
@@ -66,6 +74,7 @@ impl Session {
         Session {
             session_id: Some(session_id),
             username: Some(username.to_string()),
+            admin: false,
         }
     }
 
@@ -74,6 +83,7 @@ impl Session {
         Session {
             username,
             session_id: Some(session_id),
+            admin: false,
         }
     }
 }
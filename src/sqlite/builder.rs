@@ -2,6 +2,7 @@
 // SPDX-License-Identifier: MIT
 
 use crate::datetime::DateTime;
+use crate::elastic::{TAG_ARCHIVED, TAG_ESCALATED};
 use crate::queryparser;
 use sqlx::sqlite::SqliteArguments;
 use sqlx::Arguments;
@@ -18,7 +19,7 @@ pub(crate) struct EventQueryBuilder<'a> {
     left_join: Vec<String>,
     wheres: Vec<String>,
     group_by: Vec<String>,
-    order_by: Option<(String, String)>,
+    order_by: Vec<(String, String)>,
     limit: i64,
     fts_phrases: Vec<String>,
 
@@ -63,8 +64,10 @@ impl<'a> EventQueryBuilder<'a> {
         self
     }
 
+    /// Add an order by clause. Can be called multiple times to order
+    /// by multiple columns.
     pub fn order_by<T: Into<String>>(&mut self, field: T, order: T) -> &mut Self {
-        self.order_by = Some((field.into(), order.into()));
+        self.order_by.push((field.into(), order.into()));
         self
     }
 
@@ -99,6 +102,16 @@ impl<'a> EventQueryBuilder<'a> {
         self
     }
 
+    /// Add a raw FTS5 expression, such as a prefix or NEAR query. The
+    /// expression must already be valid FTS5 syntax.
+    pub fn push_fts_raw<S>(&mut self, expr: S) -> &mut Self
+    where
+        S: Into<String>,
+    {
+        self.fts_phrases.push(expr.into());
+        self
+    }
+
     pub fn push_arg<T>(&mut self, value: T) -> Result<(), sqlx::error::BoxDynError>
     where
         T: sqlx::Encode<'a, sqlx::Sqlite> + sqlx::Type<sqlx::Sqlite> + 'a,
@@ -175,6 +188,7 @@ impl<'a> EventQueryBuilder<'a> {
                     }
                 }
                 queryparser::QueryValue::String(_) => {}
+                queryparser::QueryValue::Fts(_) => {}
                 queryparser::QueryValue::From(_) => {}
                 queryparser::QueryValue::To(_) => {}
             }
@@ -188,7 +202,26 @@ impl<'a> EventQueryBuilder<'a> {
     ) -> Result<(), sqlx::error::BoxDynError> {
         for e in q {
             match &e.value {
-                queryparser::QueryValue::String(s) => {
+                queryparser::QueryValue::Fts(s) if self.fts => {
+                    if let Some(expr) = fts5_expression(s)? {
+                        if e.negated {
+                            self.push_where(
+                                "events.rowid not in (select rowid from fts where fts match ?)",
+                            )
+                            .push_arg(expr)?;
+                        } else {
+                            self.push_fts_raw(expr);
+                        }
+                    } else if e.negated {
+                        self.push_where("events.source NOT LIKE ?")
+                            .push_arg(format!("%{s}%"))?;
+                    } else {
+                        self.push_fts(s);
+                    }
+                }
+                // Without full text search the syntax is matched
+                // literally.
+                queryparser::QueryValue::String(s) | queryparser::QueryValue::Fts(s) => {
                     if e.negated {
                         self.push_where("events.source NOT LIKE ?")
                             .push_arg(format!("%{s}%"))?;
@@ -201,6 +234,32 @@ impl<'a> EventQueryBuilder<'a> {
                 }
                 queryparser::QueryValue::KeyValue(k, v) => {
                     match k.as_ref() {
+                        "status" => {
+                            let op = if e.negated { "!=" } else { "=" };
+                            self.push_where(format!("events.status {op} ?"))
+                                .push_arg(v)?;
+                        }
+                        // Archived and escalated are columns, not tags
+                        // as with Elasticsearch.
+                        "tag" if v == TAG_ARCHIVED || v == TAG_ESCALATED => {
+                            let column = if v == TAG_ARCHIVED {
+                                "archived"
+                            } else {
+                                "escalated"
+                            };
+                            let value = if e.negated { 0 } else { 1 };
+                            self.push_where(format!("events.{column} = {value}"));
+                        }
+                        "tag" => {
+                            self.push_where(tag_filter(e.negated)).push_arg(v)?;
+                        }
+                        "assignee" => {
+                            let (filter, arg) = assignee_filter(v, e.negated);
+                            self.push_where(filter);
+                            if let Some(arg) = arg {
+                                self.push_arg(arg)?;
+                            }
+                        }
                         "@ip" | "@mac" => {
                             if e.negated {
                                 self.push_where("events.source NOT LIKE ?")
@@ -353,8 +412,13 @@ impl<'a> EventQueryBuilder<'a> {
             sql.push_str(&self.group_by.join(", "));
         }
 
-        if let Some(order_by) = &self.order_by {
-            sql.push_str(&format!(" order by {} {}", order_by.0, order_by.1));
+        if !self.order_by.is_empty() {
+            let order_by = self
+                .order_by
+                .iter()
+                .map(|(field, order)| format!("{field} {order}"))
+                .collect::<Vec<String>>();
+            sql.push_str(&format!(" order by {}", order_by.join(", ")));
         }
 
         if self.limit > 0 {
@@ -364,3 +428,233 @@ impl<'a> EventQueryBuilder<'a> {
         Ok((sql, self.args.clone()))
     }
 }
+
+/// Build the where clause for an `assignee:` query, where `none`
+/// matches unassigned events.
+pub(crate) fn assignee_filter(value: &str, negated: bool) -> (String, Option<String>) {
+    match (value, negated) {
+        ("none", false) => ("events.assignee IS NULL".to_string(), None),
+        ("none", true) => ("events.assignee IS NOT NULL".to_string(), None),
+        (_, false) => ("events.assignee = ?".to_string(), Some(value.to_string())),
+        (_, true) => (
+            "events.assignee IS NOT ?".to_string(),
+            Some(value.to_string()),
+        ),
+    }
+}
+
+/// Build the where clause for a `tag:` query, matching events with
+/// the tag in their `tags` array.
+pub(crate) fn tag_filter(negated: bool) -> String {
+    let filter = "EXISTS (SELECT 1 FROM json_each(events.source, '$.tags') WHERE value = ?)";
+    if negated {
+        format!("NOT {filter}")
+    } else {
+        filter.to_string()
+    }
+}
+
+/// Convert a query string term using FTS5 syntax into an FTS5 match
+/// expression.
+///
+/// Supported are prefix queries (`evil*`) and NEAR groups
+/// (`NEAR(evil payload, 10)`). Plain terms return `None` as they are
+/// handled as regular phrases.
+fn fts5_expression(s: &str) -> Result<Option<String>, queryparser::QueryStringParseError> {
+    if let Some(inner) = s.strip_prefix("NEAR(") {
+        let inner = inner.strip_suffix(')').ok_or_else(|| {
+            queryparser::QueryStringParseError::from(format!("unterminated NEAR group: {s}"))
+        })?;
+
+        // An optional distance follows the last comma.
+        let (terms, distance) = match inner.rsplit_once(',') {
+            Some((terms, distance)) => {
+                let distance = distance.trim().parse::<u32>().map_err(|_| {
+                    queryparser::QueryStringParseError::from(format!(
+                        "invalid NEAR distance: {}",
+                        distance.trim()
+                    ))
+                })?;
+                (terms, Some(distance))
+            }
+            None => (inner, None),
+        };
+
+        let terms = split_near_terms(terms)
+            .iter()
+            .map(|term| fts5_term(term))
+            .collect::<Vec<String>>();
+        if terms.len() < 2 {
+            return Err(queryparser::QueryStringParseError::from(format!(
+                "NEAR requires at least 2 terms: {s}"
+            )));
+        }
+
+        let expr = match distance {
+            Some(distance) => format!("NEAR({}, {distance})", terms.join(" ")),
+            None => format!("NEAR({})", terms.join(" ")),
+        };
+        return Ok(Some(expr));
+    }
+
+    if s.len() > 1 && s.ends_with('*') {
+        return Ok(Some(fts5_term(s)));
+    }
+
+    Ok(None)
+}
+
+/// Quote a single term as an FTS5 string, preserving a trailing `*`
+/// as a prefix operator.
+fn fts5_term(term: &str) -> String {
+    let (term, prefix) = match term.strip_suffix('*') {
+        Some(term) => (term, "*"),
+        None => (term, ""),
+    };
+    format!("\"{}\"{prefix}", term.replace('"', "\"\""))
+}
+
+/// Split the terms of a NEAR group on whitespace, keeping double
+/// quoted phrases together.
+fn split_near_terms(input: &str) -> Vec<String> {
+    let mut terms = vec![];
+    let mut term = String::new();
+    let mut in_quote = false;
+    for c in input.chars() {
+        if c == '"' {
+            in_quote = !in_quote;
+        } else if c.is_whitespace() && !in_quote {
+            if !term.is_empty() {
+                terms.push(std::mem::take(&mut term));
+            }
+        } else {
+            term.push(c);
+        }
+    }
+    if !term.is_empty() {
+        terms.push(term);
+    }
+    terms
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sqlx::SqliteConnection;
+
+    #[test]
+    fn test_fts5_expression() {
+        assert_eq!(fts5_expression("evil").unwrap(), None);
+        assert_eq!(fts5_expression("*").unwrap(), None);
+        assert_eq!(
+            fts5_expression("evil*").unwrap(),
+            Some("\"evil\"*".to_string())
+        );
+        assert_eq!(
+            fts5_expression("ET POL*").unwrap(),
+            Some("\"ET POL\"*".to_string())
+        );
+        assert_eq!(
+            fts5_expression("NEAR(evil payload, 5)").unwrap(),
+            Some("NEAR(\"evil\" \"payload\", 5)".to_string())
+        );
+        assert_eq!(
+            fts5_expression(r#"NEAR("et policy" curl*)"#).unwrap(),
+            Some("NEAR(\"et policy\" \"curl\"*)".to_string())
+        );
+        assert!(fts5_expression("NEAR(evil)").is_err());
+        assert!(fts5_expression("NEAR(evil payload, x)").is_err());
+        assert!(fts5_expression("NEAR(evil payload").is_err());
+    }
+
+    #[test]
+    fn test_fts_syntax_without_fts() {
+        // Matched literally, as FTS is not available.
+        let q = queryparser::parse("evil* NEAR(evil payload)", None).unwrap();
+        let mut builder = EventQueryBuilder::new(false);
+        builder.select("events.rowid").from("events");
+        builder.apply_query_string(&q).unwrap();
+        let (sql, _) = builder.build().unwrap();
+        assert_eq!(sql.matches("events.source LIKE ?").count(), 2);
+    }
+
+    #[test]
+    fn test_status_query() {
+        let q = queryparser::parse("status:false-positive -status:closed", None).unwrap();
+        let mut builder = EventQueryBuilder::new(false);
+        builder.select("events.rowid").from("events");
+        builder.apply_query_string(&q).unwrap();
+        let (sql, _) = builder.build().unwrap();
+        assert!(sql.contains("events.status = ?"));
+        assert!(sql.contains("events.status != ?"));
+    }
+
+    #[tokio::test]
+    async fn test_tag_query() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut conn = crate::sqlite::connection::open_connection(
+            Some(dir.path().join("events.sqlite")),
+            true,
+        )
+        .await
+        .unwrap();
+        crate::sqlite::connection::init_event_db(&mut conn)
+            .await
+            .unwrap();
+
+        let events = [
+            r#"{"tags": ["phishing"]}"#,
+            r#"{"tags": ["phishing", "pentest-2026"]}"#,
+            r#"{"tags": ["pentest-2026"]}"#,
+            r#"{"tags": []}"#,
+            r#"{}"#,
+        ];
+        for event in events {
+            sqlx::query(
+                "INSERT INTO events (timestamp, archived, source, source_values)
+                VALUES (0, 0, ?, '')",
+            )
+            .bind(event)
+            .execute(&mut conn)
+            .await
+            .unwrap();
+        }
+
+        async fn query(conn: &mut SqliteConnection, q: &str) -> Vec<i64> {
+            let q = queryparser::parse(q, None).unwrap();
+            let mut builder = EventQueryBuilder::new(false);
+            builder
+                .select("events.rowid")
+                .from("events")
+                .order_by("events.rowid", "asc");
+            builder.apply_query_string(&q).unwrap();
+            let (sql, args) = builder.build().unwrap();
+            sqlx::query_scalar_with(&sql, args)
+                .fetch_all(conn)
+                .await
+                .unwrap()
+        }
+
+        assert_eq!(query(&mut conn, "tag:phishing").await, vec![1, 2]);
+        assert_eq!(query(&mut conn, "-tag:phishing").await, vec![3, 4, 5]);
+        assert_eq!(
+            query(&mut conn, "tag:phishing -tag:pentest-2026").await,
+            vec![1]
+        );
+        assert_eq!(
+            query(&mut conn, "tag:phishing tag:pentest-2026").await,
+            vec![2]
+        );
+
+        // Archived is a column, but queried like a tag.
+        sqlx::query("UPDATE events SET archived = 1 WHERE rowid = 2")
+            .execute(&mut conn)
+            .await
+            .unwrap();
+        assert_eq!(query(&mut conn, "tag:evebox.archived").await, vec![2]);
+        assert_eq!(
+            query(&mut conn, "tag:phishing -tag:evebox.archived").await,
+            vec![1]
+        );
+    }
+}
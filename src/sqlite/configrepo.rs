@@ -14,6 +14,11 @@ use tracing::info;
 use crate::datetime::DateTime;
 use crate::sqlite::has_table;
 
+pub(crate) mod audit;
+pub(crate) mod cases;
+pub(crate) mod sensors;
+pub(crate) mod suppressions;
+
 #[derive(thiserror::Error, Debug)]
 pub(crate) enum ConfigRepoError {
     #[error("username not found: {0}")]
@@ -26,14 +31,21 @@ pub(crate) enum ConfigRepoError {
     JoinError(#[from] tokio::task::JoinError),
     #[error("user does not exist: {0}")]
     NoUser(String),
+    #[error("case not found: {0}")]
+    CaseNotFound(i64),
+    #[error("suppression not found: {0}")]
+    SuppressionNotFound(i64),
     #[error("sql error: {0}")]
     SqlxError(#[from] sqlx::Error),
+    #[error("sql error: {0}")]
+    SqlxDynError(#[from] sqlx::error::BoxDynError),
 }
 
 #[derive(Debug, Clone, Serialize)]
 pub(crate) struct User {
     pub uuid: String,
     pub username: String,
+    pub admin: bool,
 }
 
 #[derive(Clone)]
@@ -52,15 +64,20 @@ impl ConfigRepo {
         password_in: &str,
     ) -> Result<User, ConfigRepoError> {
         let query = sqlx::query::<sqlx::Sqlite>(
-            "SELECT uuid, username, password FROM users WHERE username = ?",
+            "SELECT uuid, username, password, admin FROM users WHERE username = ?",
         )
         .bind(username);
         if let Some(row) = query.fetch_optional(&self.pool).await? {
             let uuid: String = row.try_get(0)?;
             let username: String = row.try_get(1)?;
             let password_hash: String = row.try_get(2)?;
+            let admin: bool = row.try_get(3)?;
             if bcrypt::verify(password_in, &password_hash)? {
-                return Ok(User { uuid, username });
+                return Ok(User {
+                    uuid,
+                    username,
+                    admin,
+                });
             } else {
                 return Err(ConfigRepoError::BadPassword(username));
             }
@@ -70,7 +87,7 @@ impl ConfigRepo {
     }
 
     pub async fn get_user_by_name(&self, username: &str) -> Result<User, ConfigRepoError> {
-        let row = sqlx::query("SELECT uuid, username FROM users WHERE username = ?")
+        let row = sqlx::query("SELECT uuid, username, admin FROM users WHERE username = ?")
             .bind(username)
             .fetch_optional(&self.pool)
             .await?;
@@ -78,6 +95,7 @@ impl ConfigRepo {
             Ok(User {
                 uuid: row.try_get("uuid")?,
                 username: row.try_get("username")?,
+                admin: row.try_get("admin")?,
             })
         } else {
             Err(ConfigRepoError::NoUser(username.to_string()))
@@ -92,14 +110,16 @@ impl ConfigRepo {
     }
 
     pub async fn get_users(&self) -> Result<Vec<User>, ConfigRepoError> {
-        let rows: Vec<(String, String)> = sqlx::query_as("SELECT uuid, username FROM users")
-            .fetch_all(&self.pool)
-            .await?;
+        let rows: Vec<(String, String, bool)> =
+            sqlx::query_as("SELECT uuid, username, admin FROM users")
+                .fetch_all(&self.pool)
+                .await?;
         Ok(rows
             .into_iter()
             .map(|row| User {
                 uuid: row.0,
                 username: row.1,
+                admin: row.2,
             })
             .collect())
     }
@@ -128,6 +148,19 @@ impl ConfigRepo {
             .rows_affected())
     }
 
+    pub async fn set_admin(&self, username: &str, admin: bool) -> Result<(), ConfigRepoError> {
+        let n = sqlx::query("UPDATE users SET admin = ? WHERE username = ?")
+            .bind(admin)
+            .bind(username)
+            .execute(&self.pool)
+            .await?
+            .rows_affected();
+        if n == 0 {
+            return Err(ConfigRepoError::NoUser(username.to_string()));
+        }
+        Ok(())
+    }
+
     pub async fn update_password_by_id(
         &self,
         id: &str,
@@ -175,7 +208,7 @@ impl ConfigRepo {
 
     pub async fn get_user_by_session(&self, token: &str) -> Result<Option<User>, ConfigRepoError> {
         let sql = r#"
-            SELECT users.uuid, users.username, sessions.expires_at
+            SELECT users.uuid, users.username, users.admin, sessions.expires_at
             FROM users 
             JOIN sessions ON 
             users.uuid = sessions.uuid
@@ -190,6 +223,7 @@ impl ConfigRepo {
         {
             let uuid: String = row.try_get("uuid")?;
             let username: String = row.try_get("username")?;
+            let admin: bool = row.try_get("admin")?;
             let expires_at: i64 = row.try_get("expires_at")?;
 
             let now = DateTime::now().to_seconds();
@@ -207,7 +241,11 @@ impl ConfigRepo {
                 return Ok(None);
             }
             tx.commit().await?;
-            return Ok(Some(User { uuid, username }));
+            return Ok(Some(User {
+                uuid,
+                username,
+                admin,
+            }));
         }
         Ok(None)
     }
@@ -0,0 +1,112 @@
+// SPDX-FileCopyrightText: (C) 2024 Jason Ish <jason@codemonkey.net>
+// SPDX-License-Identifier: MIT
+
+//! Audit log of user actions, such as logins, triage actions and user
+//! management.
+
+use serde::Serialize;
+use sqlx::sqlite::SqliteArguments;
+use sqlx::{Arguments, Row};
+
+use super::{ConfigRepo, ConfigRepoError};
+use crate::datetime::DateTime;
+
+pub(crate) mod action {
+    pub const LOGIN: &str = "login";
+    pub const LOGIN_FAILED: &str = "login-failed";
+    pub const LOGOUT: &str = "logout";
+    pub const USER_ADD: &str = "user-add";
+    pub const USER_REMOVE: &str = "user-remove";
+    pub const USER_PASSWORD: &str = "user-password";
+    pub const USER_ADMIN: &str = "user-admin";
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub(crate) struct AuditEntry {
+    pub id: i64,
+    pub timestamp: DateTime,
+    pub username: Option<String>,
+    pub remote_addr: Option<String>,
+    pub action: String,
+    pub details: Option<serde_json::Value>,
+}
+
+#[derive(Debug, Default)]
+pub(crate) struct AuditQuery {
+    pub from: Option<DateTime>,
+    pub to: Option<DateTime>,
+    pub username: Option<String>,
+    /// Match entries where the action contains this string.
+    pub action: Option<String>,
+    pub limit: Option<u64>,
+}
+
+/// Default maximum number of audit entries to return.
+const DEFAULT_LIMIT: u64 = 1000;
+
+impl ConfigRepo {
+    pub async fn add_audit(
+        &self,
+        username: Option<&str>,
+        remote_addr: Option<&str>,
+        action: &str,
+        details: Option<serde_json::Value>,
+    ) -> Result<(), ConfigRepoError> {
+        let sql = r#"
+            INSERT INTO audit (timestamp, username, remote_addr, action, details)
+            VALUES (?, ?, ?, ?, ?)"#;
+        sqlx::query(sql)
+            .bind(DateTime::now().to_seconds())
+            .bind(username)
+            .bind(remote_addr)
+            .bind(action)
+            .bind(details)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    /// Return audit entries matching the query, newest first.
+    pub async fn get_audit(&self, query: &AuditQuery) -> Result<Vec<AuditEntry>, ConfigRepoError> {
+        let mut filters: Vec<&str> = vec![];
+        let mut args = SqliteArguments::default();
+        if let Some(from) = &query.from {
+            filters.push("timestamp >= ?");
+            args.add(from.to_seconds())?;
+        }
+        if let Some(to) = &query.to {
+            filters.push("timestamp <= ?");
+            args.add(to.to_seconds())?;
+        }
+        if let Some(username) = &query.username {
+            filters.push("username = ?");
+            args.add(username.clone())?;
+        }
+        if let Some(action) = &query.action {
+            filters.push("action LIKE ?");
+            args.add(format!("%{action}%"))?;
+        }
+        let mut sql =
+            "SELECT id, timestamp, username, remote_addr, action, details FROM audit".to_string();
+        if !filters.is_empty() {
+            sql.push_str(" WHERE ");
+            sql.push_str(&filters.join(" AND "));
+        }
+        sql.push_str(" ORDER BY timestamp DESC, id DESC LIMIT ?");
+        args.add(query.limit.unwrap_or(DEFAULT_LIMIT) as i64)?;
+
+        let rows = sqlx::query_with(&sql, args).fetch_all(&self.pool).await?;
+        let mut entries = vec![];
+        for row in rows {
+            entries.push(AuditEntry {
+                id: row.try_get("id")?,
+                timestamp: DateTime::from_seconds(row.try_get("timestamp")?),
+                username: row.try_get("username")?,
+                remote_addr: row.try_get("remote_addr")?,
+                action: row.try_get("action")?,
+                details: row.try_get("details")?,
+            });
+        }
+        Ok(entries)
+    }
+}
@@ -0,0 +1,409 @@
+// SPDX-FileCopyrightText: (C) 2024 Jason Ish <jason@codemonkey.net>
+// SPDX-License-Identifier: MIT
+
+//! Cases (investigations) stored in the configuration database.
+//!
+//! A case bundles related events together with a title, description,
+//! severity, status and a timeline of notes. Events are linked by
+//! their datastore ID so they work with both SQLite and
+//! Elasticsearch.
+
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+use sqlx::sqlite::SqliteRow;
+use sqlx::Row;
+
+use super::{ConfigRepo, ConfigRepoError};
+use crate::datetime::DateTime;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum CaseSeverity {
+    Low,
+    Medium,
+    High,
+    Critical,
+}
+
+impl CaseSeverity {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CaseSeverity::Low => "low",
+            CaseSeverity::Medium => "medium",
+            CaseSeverity::High => "high",
+            CaseSeverity::Critical => "critical",
+        }
+    }
+}
+
+impl FromStr for CaseSeverity {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "low" => Ok(CaseSeverity::Low),
+            "medium" => Ok(CaseSeverity::Medium),
+            "high" => Ok(CaseSeverity::High),
+            "critical" => Ok(CaseSeverity::Critical),
+            _ => Err(anyhow!("invalid case severity: {}", s)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub(crate) enum CaseStatus {
+    Open,
+    InProgress,
+    Closed,
+}
+
+impl CaseStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CaseStatus::Open => "open",
+            CaseStatus::InProgress => "in-progress",
+            CaseStatus::Closed => "closed",
+        }
+    }
+}
+
+impl FromStr for CaseStatus {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "open" => Ok(CaseStatus::Open),
+            "in-progress" => Ok(CaseStatus::InProgress),
+            "closed" => Ok(CaseStatus::Closed),
+            _ => Err(anyhow!("invalid case status: {}", s)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub(crate) struct Case {
+    pub id: i64,
+    pub title: String,
+    pub description: String,
+    pub severity: CaseSeverity,
+    pub status: CaseStatus,
+    pub created_by: Option<String>,
+    pub created_at: DateTime,
+    pub updated_at: DateTime,
+    /// Number of events linked to the case.
+    pub event_count: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub(crate) struct CaseNote {
+    pub id: i64,
+    pub username: Option<String>,
+    pub timestamp: DateTime,
+    pub note: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub(crate) struct CaseEvent {
+    pub event_id: String,
+    pub added_by: Option<String>,
+    pub added_at: DateTime,
+}
+
+/// Fields of a case that can be set on creation or update. Unset
+/// fields are left unchanged on update.
+#[derive(Debug, Default, Deserialize)]
+pub(crate) struct CaseFields {
+    pub title: Option<String>,
+    pub description: Option<String>,
+    pub severity: Option<CaseSeverity>,
+    pub status: Option<CaseStatus>,
+}
+
+const CASE_COLUMNS: &str = r#"
+    cases.id, cases.title, cases.description, cases.severity,
+    cases.status, cases.created_by, cases.created_at, cases.updated_at,
+    (SELECT count(*) FROM case_events WHERE case_events.case_id = cases.id) AS event_count"#;
+
+fn case_from_row(row: &SqliteRow) -> Result<Case, ConfigRepoError> {
+    let severity: String = row.try_get("severity")?;
+    let status: String = row.try_get("status")?;
+    Ok(Case {
+        id: row.try_get("id")?,
+        title: row.try_get("title")?,
+        description: row.try_get("description")?,
+        severity: CaseSeverity::from_str(&severity).unwrap_or(CaseSeverity::Medium),
+        status: CaseStatus::from_str(&status).unwrap_or(CaseStatus::Open),
+        created_by: row.try_get("created_by")?,
+        created_at: DateTime::from_seconds(row.try_get("created_at")?),
+        updated_at: DateTime::from_seconds(row.try_get("updated_at")?),
+        event_count: row.try_get("event_count")?,
+    })
+}
+
+impl ConfigRepo {
+    pub async fn get_cases(&self) -> Result<Vec<Case>, ConfigRepoError> {
+        let sql = format!("SELECT {CASE_COLUMNS} FROM cases ORDER BY cases.updated_at DESC");
+        let rows = sqlx::query(&sql).fetch_all(&self.pool).await?;
+        rows.iter().map(case_from_row).collect()
+    }
+
+    pub async fn get_case(&self, id: i64) -> Result<Case, ConfigRepoError> {
+        let sql = format!("SELECT {CASE_COLUMNS} FROM cases WHERE cases.id = ?");
+        let row = sqlx::query(&sql)
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await?
+            .ok_or(ConfigRepoError::CaseNotFound(id))?;
+        case_from_row(&row)
+    }
+
+    pub async fn create_case(
+        &self,
+        fields: CaseFields,
+        username: Option<&str>,
+    ) -> Result<i64, ConfigRepoError> {
+        let title = fields.title.unwrap_or_default();
+        let now = DateTime::now().to_seconds();
+        let sql = r#"
+            INSERT INTO cases
+              (title, description, severity, status, created_by, created_at, updated_at)
+            VALUES (?, ?, ?, ?, ?, ?, ?)"#;
+        let result = sqlx::query(sql)
+            .bind(title)
+            .bind(fields.description.unwrap_or_default())
+            .bind(fields.severity.unwrap_or(CaseSeverity::Medium).as_str())
+            .bind(fields.status.unwrap_or(CaseStatus::Open).as_str())
+            .bind(username)
+            .bind(now)
+            .bind(now)
+            .execute(&self.pool)
+            .await?;
+        Ok(result.last_insert_rowid())
+    }
+
+    pub async fn update_case(&self, id: i64, fields: CaseFields) -> Result<(), ConfigRepoError> {
+        let sql = r#"
+            UPDATE cases SET
+              title = COALESCE(?, title),
+              description = COALESCE(?, description),
+              severity = COALESCE(?, severity),
+              status = COALESCE(?, status),
+              updated_at = ?
+            WHERE id = ?"#;
+        let result = sqlx::query(sql)
+            .bind(fields.title)
+            .bind(fields.description)
+            .bind(fields.severity.map(|s| s.as_str()))
+            .bind(fields.status.map(|s| s.as_str()))
+            .bind(DateTime::now().to_seconds())
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+        if result.rows_affected() == 0 {
+            return Err(ConfigRepoError::CaseNotFound(id));
+        }
+        Ok(())
+    }
+
+    pub async fn delete_case(&self, id: i64) -> Result<(), ConfigRepoError> {
+        let mut tx = self.pool.begin().await?;
+        sqlx::query("DELETE FROM case_notes WHERE case_id = ?")
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query("DELETE FROM case_events WHERE case_id = ?")
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+        let result = sqlx::query("DELETE FROM cases WHERE id = ?")
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+        if result.rows_affected() == 0 {
+            return Err(ConfigRepoError::CaseNotFound(id));
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Bump the updated timestamp of a case, returning an error if the
+    /// case does not exist.
+    async fn touch_case(
+        &self,
+        conn: &mut sqlx::SqliteConnection,
+        id: i64,
+    ) -> Result<(), ConfigRepoError> {
+        let result = sqlx::query("UPDATE cases SET updated_at = ? WHERE id = ?")
+            .bind(DateTime::now().to_seconds())
+            .bind(id)
+            .execute(&mut *conn)
+            .await?;
+        if result.rows_affected() == 0 {
+            return Err(ConfigRepoError::CaseNotFound(id));
+        }
+        Ok(())
+    }
+
+    pub async fn get_case_notes(&self, id: i64) -> Result<Vec<CaseNote>, ConfigRepoError> {
+        let rows = sqlx::query(
+            "SELECT id, username, timestamp, note FROM case_notes WHERE case_id = ? ORDER BY timestamp, id",
+        )
+        .bind(id)
+        .fetch_all(&self.pool)
+        .await?;
+        let mut notes = vec![];
+        for row in rows {
+            notes.push(CaseNote {
+                id: row.try_get("id")?,
+                username: row.try_get("username")?,
+                timestamp: DateTime::from_seconds(row.try_get("timestamp")?),
+                note: row.try_get("note")?,
+            });
+        }
+        Ok(notes)
+    }
+
+    pub async fn add_case_note(
+        &self,
+        id: i64,
+        username: Option<&str>,
+        note: &str,
+    ) -> Result<i64, ConfigRepoError> {
+        let mut tx = self.pool.begin().await?;
+        self.touch_case(&mut tx, id).await?;
+        let result = sqlx::query(
+            "INSERT INTO case_notes (case_id, username, timestamp, note) VALUES (?, ?, ?, ?)",
+        )
+        .bind(id)
+        .bind(username)
+        .bind(DateTime::now().to_seconds())
+        .bind(note)
+        .execute(&mut *tx)
+        .await?;
+        tx.commit().await?;
+        Ok(result.last_insert_rowid())
+    }
+
+    pub async fn get_case_events(&self, id: i64) -> Result<Vec<CaseEvent>, ConfigRepoError> {
+        let rows = sqlx::query(
+            "SELECT event_id, added_by, added_at FROM case_events WHERE case_id = ? ORDER BY added_at, rowid",
+        )
+        .bind(id)
+        .fetch_all(&self.pool)
+        .await?;
+        let mut events = vec![];
+        for row in rows {
+            events.push(CaseEvent {
+                event_id: row.try_get("event_id")?,
+                added_by: row.try_get("added_by")?,
+                added_at: DateTime::from_seconds(row.try_get("added_at")?),
+            });
+        }
+        Ok(events)
+    }
+
+    /// Link events to a case, returning the number of events that were
+    /// not already linked.
+    pub async fn add_case_events(
+        &self,
+        id: i64,
+        event_ids: &[String],
+        username: Option<&str>,
+    ) -> Result<u64, ConfigRepoError> {
+        let now = DateTime::now().to_seconds();
+        let mut tx = self.pool.begin().await?;
+        self.touch_case(&mut tx, id).await?;
+        let mut n = 0;
+        for event_id in event_ids {
+            n += sqlx::query(
+                "INSERT OR IGNORE INTO case_events (case_id, event_id, added_by, added_at) VALUES (?, ?, ?, ?)",
+            )
+            .bind(id)
+            .bind(event_id)
+            .bind(username)
+            .bind(now)
+            .execute(&mut *tx)
+            .await?
+            .rows_affected();
+        }
+        tx.commit().await?;
+        Ok(n)
+    }
+
+    /// Unlink an event from a case, returning true if it was linked.
+    pub async fn remove_case_event(
+        &self,
+        id: i64,
+        event_id: &str,
+    ) -> Result<bool, ConfigRepoError> {
+        let mut tx = self.pool.begin().await?;
+        self.touch_case(&mut tx, id).await?;
+        let n = sqlx::query("DELETE FROM case_events WHERE case_id = ? AND event_id = ?")
+            .bind(id)
+            .bind(event_id)
+            .execute(&mut *tx)
+            .await?
+            .rows_affected();
+        tx.commit().await?;
+        Ok(n > 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_cases() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = crate::sqlite::configrepo::open(Some(&dir.path().join("config.sqlite")))
+            .await
+            .unwrap();
+
+        let id = repo
+            .create_case(
+                CaseFields {
+                    title: Some("Test case".to_string()),
+                    severity: Some(CaseSeverity::High),
+                    ..Default::default()
+                },
+                Some("admin"),
+            )
+            .await
+            .unwrap();
+
+        let events = vec!["a".to_string(), "b".to_string()];
+        assert_eq!(repo.add_case_events(id, &events, None).await.unwrap(), 2);
+        assert_eq!(repo.add_case_events(id, &events, None).await.unwrap(), 0);
+        assert!(repo.remove_case_event(id, "a").await.unwrap());
+        repo.add_case_note(id, Some("admin"), "a note")
+            .await
+            .unwrap();
+
+        repo.update_case(
+            id,
+            CaseFields {
+                status: Some(CaseStatus::Closed),
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+
+        let case = repo.get_case(id).await.unwrap();
+        assert_eq!(case.title, "Test case");
+        assert_eq!(case.severity, CaseSeverity::High);
+        assert_eq!(case.status, CaseStatus::Closed);
+        assert_eq!(case.event_count, 1);
+        assert_eq!(repo.get_case_notes(id).await.unwrap().len(), 1);
+
+        repo.delete_case(id).await.unwrap();
+        assert!(matches!(
+            repo.get_case(id).await,
+            Err(ConfigRepoError::CaseNotFound(_))
+        ));
+        assert!(repo.add_case_note(id, None, "note").await.is_err());
+    }
+}
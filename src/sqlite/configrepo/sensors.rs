@@ -0,0 +1,173 @@
+// SPDX-FileCopyrightText: (C) 2024 Jason Ish <jason@codemonkey.net>
+// SPDX-License-Identifier: MIT
+
+//! Sensor heartbeats stored in the configuration database.
+//!
+//! Sensors are identified by the host field of their events. Their
+//! activity is recorded periodically so sensors that have stopped
+//! sending events can be detected.
+
+use serde::Serialize;
+use sqlx::sqlite::SqliteRow;
+use sqlx::Row;
+
+use super::{ConfigRepo, ConfigRepoError};
+use crate::datetime::DateTime;
+
+#[derive(Debug, Clone, Serialize)]
+pub(crate) struct Sensor {
+    pub name: String,
+    pub first_seen: DateTime,
+    /// When an event from the sensor was last received.
+    pub last_seen: DateTime,
+    pub event_count: u64,
+    /// Events per second over the last update interval.
+    pub event_rate: f64,
+    pub agent_version: Option<String>,
+    /// Set when the sensor has been silent longer than the threshold.
+    pub silent: bool,
+}
+
+/// Activity of a sensor since the last update.
+#[derive(Debug, Clone)]
+pub(crate) struct SensorActivity {
+    pub name: String,
+    pub first_seen: DateTime,
+    pub last_seen: DateTime,
+    pub events: u64,
+    pub agent_version: Option<String>,
+}
+
+fn sensor_from_row(row: &SqliteRow) -> Result<Sensor, ConfigRepoError> {
+    let event_count: i64 = row.try_get("event_count")?;
+    Ok(Sensor {
+        name: row.try_get("name")?,
+        first_seen: DateTime::from_seconds(row.try_get("first_seen")?),
+        last_seen: DateTime::from_seconds(row.try_get("last_seen")?),
+        event_count: event_count as u64,
+        event_rate: row.try_get("event_rate")?,
+        agent_version: row.try_get("agent_version")?,
+        silent: row.try_get("silent")?,
+    })
+}
+
+impl ConfigRepo {
+    pub async fn get_sensors(&self) -> Result<Vec<Sensor>, ConfigRepoError> {
+        let rows = sqlx::query("SELECT * FROM sensors ORDER BY name")
+            .fetch_all(&self.pool)
+            .await?;
+        rows.iter().map(sensor_from_row).collect()
+    }
+
+    /// Record sensor activity over an interval of `interval` seconds.
+    /// Sensors with no activity in the interval have their rate reset
+    /// to 0, and active sensors are no longer silent.
+    pub async fn update_sensors(
+        &self,
+        activity: &[SensorActivity],
+        interval: f64,
+    ) -> Result<(), ConfigRepoError> {
+        let mut tx = self.pool.begin().await?;
+        sqlx::query("UPDATE sensors SET event_rate = 0")
+            .execute(&mut *tx)
+            .await?;
+        let sql = r#"
+            INSERT INTO sensors
+              (name, first_seen, last_seen, event_count, event_rate, agent_version, silent)
+            VALUES (?, ?, ?, ?, ?, ?, 0)
+            ON CONFLICT(name) DO UPDATE SET
+              last_seen = MAX(last_seen, excluded.last_seen),
+              event_count = event_count + excluded.event_count,
+              event_rate = excluded.event_rate,
+              agent_version = COALESCE(excluded.agent_version, agent_version),
+              silent = 0"#;
+        for sensor in activity {
+            sqlx::query(sql)
+                .bind(&sensor.name)
+                .bind(sensor.first_seen.to_seconds())
+                .bind(sensor.last_seen.to_seconds())
+                .bind(sensor.events as i64)
+                .bind(sensor.events as f64 / interval)
+                .bind(&sensor.agent_version)
+                .execute(&mut *tx)
+                .await?;
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Flag sensors not seen since `last_seen` as silent, returning
+    /// the sensors that were not already flagged.
+    pub async fn mark_silent_sensors(
+        &self,
+        last_seen: &DateTime,
+    ) -> Result<Vec<Sensor>, ConfigRepoError> {
+        let rows = sqlx::query(
+            "UPDATE sensors SET silent = 1 WHERE silent = 0 AND last_seen < ? RETURNING *",
+        )
+        .bind(last_seen.to_seconds())
+        .fetch_all(&self.pool)
+        .await?;
+        rows.iter().map(sensor_from_row).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_sensors() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = crate::sqlite::configrepo::open(Some(&dir.path().join("config.sqlite")))
+            .await
+            .unwrap();
+        let start = DateTime::from_seconds(1000);
+        let activity = |name: &str, seconds: i64, events: u64| SensorActivity {
+            name: name.to_string(),
+            first_seen: DateTime::from_seconds(seconds),
+            last_seen: DateTime::from_seconds(seconds),
+            events,
+            agent_version: None,
+        };
+
+        repo.update_sensors(
+            &[activity("sensor1", 1000, 60), activity("sensor2", 1000, 6)],
+            60.0,
+        )
+        .await
+        .unwrap();
+        let mut update = activity("sensor1", 1100, 120);
+        update.agent_version = Some("0.20.0".to_string());
+        repo.update_sensors(&[update], 60.0).await.unwrap();
+
+        let sensors = repo.get_sensors().await.unwrap();
+        assert_eq!(sensors.len(), 2);
+        assert_eq!(sensors[0].name, "sensor1");
+        assert_eq!(sensors[0].first_seen.to_seconds(), start.to_seconds());
+        assert_eq!(sensors[0].last_seen.to_seconds(), 1100);
+        assert_eq!(sensors[0].event_count, 180);
+        assert_eq!(sensors[0].event_rate, 2.0);
+        assert_eq!(sensors[0].agent_version.as_deref(), Some("0.20.0"));
+        assert_eq!(sensors[1].event_rate, 0.0);
+
+        // Only newly silent sensors are returned.
+        let silent = repo
+            .mark_silent_sensors(&DateTime::from_seconds(1050))
+            .await
+            .unwrap();
+        assert_eq!(silent.len(), 1);
+        assert_eq!(silent[0].name, "sensor2");
+        assert!(repo
+            .mark_silent_sensors(&DateTime::from_seconds(1050))
+            .await
+            .unwrap()
+            .is_empty());
+
+        // Activity clears the silent flag.
+        repo.update_sensors(&[activity("sensor2", 1200, 1)], 60.0)
+            .await
+            .unwrap();
+        assert!(repo.get_sensors().await.unwrap().iter().all(|s| !s.silent));
+    }
+}
@@ -0,0 +1,278 @@
+// SPDX-FileCopyrightText: (C) 2024 Jason Ish <jason@codemonkey.net>
+// SPDX-License-Identifier: MIT
+
+//! Suppression entries stored in the configuration database.
+//!
+//! A suppression archives alerts for a signature ID as they are
+//! ingested, optionally limited to a source or destination address or
+//! network, and a sensor. Suppressions may expire.
+
+use std::net::IpAddr;
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+use sqlx::sqlite::SqliteRow;
+use sqlx::Row;
+
+use super::{ConfigRepo, ConfigRepoError};
+use crate::datetime::DateTime;
+
+/// An IP address or a CIDR network.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct IpNetwork {
+    addr: IpAddr,
+    prefix: u8,
+}
+
+impl IpNetwork {
+    pub fn contains(&self, addr: &IpAddr) -> bool {
+        match (self.addr, addr) {
+            (IpAddr::V4(net), IpAddr::V4(addr)) => {
+                let mask = u32::MAX.checked_shl(32 - self.prefix as u32).unwrap_or(0);
+                u32::from(net) & mask == u32::from(*addr) & mask
+            }
+            (IpAddr::V6(net), IpAddr::V6(addr)) => {
+                let mask = u128::MAX.checked_shl(128 - self.prefix as u32).unwrap_or(0);
+                u128::from(net) & mask == u128::from(*addr) & mask
+            }
+            _ => false,
+        }
+    }
+}
+
+impl FromStr for IpNetwork {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (addr, prefix) = match s.split_once('/') {
+            Some((addr, prefix)) => (addr, Some(prefix)),
+            None => (s, None),
+        };
+        let addr: IpAddr = addr
+            .parse()
+            .map_err(|_| anyhow!("invalid IP address: {}", s))?;
+        let max = if addr.is_ipv4() { 32 } else { 128 };
+        let prefix = match prefix {
+            Some(prefix) => prefix
+                .parse::<u8>()
+                .ok()
+                .filter(|p| *p <= max)
+                .ok_or_else(|| anyhow!("invalid network prefix: {}", s))?,
+            None => max,
+        };
+        Ok(Self { addr, prefix })
+    }
+}
+
+impl std::fmt::Display for IpNetwork {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let max = if self.addr.is_ipv4() { 32 } else { 128 };
+        if self.prefix == max {
+            write!(f, "{}", self.addr)
+        } else {
+            write!(f, "{}/{}", self.addr, self.prefix)
+        }
+    }
+}
+
+impl Serialize for IpNetwork {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for IpNetwork {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub(crate) struct Suppression {
+    pub id: i64,
+    pub signature_id: u64,
+    pub src_ip: Option<IpNetwork>,
+    pub dest_ip: Option<IpNetwork>,
+    /// Matched against the host of the event.
+    pub sensor: Option<String>,
+    pub comment: Option<String>,
+    pub expires_at: Option<DateTime>,
+    pub created_by: Option<String>,
+    pub created_at: DateTime,
+}
+
+impl Suppression {
+    pub fn is_expired(&self, now: &DateTime) -> bool {
+        self.expires_at
+            .as_ref()
+            .is_some_and(|expires_at| expires_at.to_seconds() <= now.to_seconds())
+    }
+
+    /// Check if an alert matches the suppression. Expiry is not
+    /// checked here.
+    pub fn matches(&self, event: &serde_json::Value) -> bool {
+        if event["alert"]["signature_id"].as_u64() != Some(self.signature_id) {
+            return false;
+        }
+        let addr_matches = |network: &Option<IpNetwork>, field: &str| match network {
+            None => true,
+            Some(network) => event[field]
+                .as_str()
+                .and_then(|addr| addr.parse::<IpAddr>().ok())
+                .is_some_and(|addr| network.contains(&addr)),
+        };
+        if !addr_matches(&self.src_ip, "src_ip") || !addr_matches(&self.dest_ip, "dest_ip") {
+            return false;
+        }
+        match &self.sensor {
+            None => true,
+            Some(sensor) => event["host"].as_str() == Some(sensor.as_str()),
+        }
+    }
+}
+
+/// Fields of a new suppression.
+#[derive(Debug)]
+pub(crate) struct SuppressionFields {
+    pub signature_id: u64,
+    pub src_ip: Option<IpNetwork>,
+    pub dest_ip: Option<IpNetwork>,
+    pub sensor: Option<String>,
+    pub comment: Option<String>,
+    pub expires_at: Option<DateTime>,
+}
+
+fn suppression_from_row(row: &SqliteRow) -> Result<Suppression, ConfigRepoError> {
+    let parse_network = |column: &str| -> Result<Option<IpNetwork>, ConfigRepoError> {
+        let value: Option<String> = row.try_get(column)?;
+        Ok(value.and_then(|value| value.parse().ok()))
+    };
+    let signature_id: i64 = row.try_get("signature_id")?;
+    let expires_at: Option<i64> = row.try_get("expires_at")?;
+    Ok(Suppression {
+        id: row.try_get("id")?,
+        signature_id: signature_id as u64,
+        src_ip: parse_network("src_ip")?,
+        dest_ip: parse_network("dest_ip")?,
+        sensor: row.try_get("sensor")?,
+        comment: row.try_get("comment")?,
+        expires_at: expires_at.map(DateTime::from_seconds),
+        created_by: row.try_get("created_by")?,
+        created_at: DateTime::from_seconds(row.try_get("created_at")?),
+    })
+}
+
+impl ConfigRepo {
+    pub async fn get_suppressions(&self) -> Result<Vec<Suppression>, ConfigRepoError> {
+        let rows = sqlx::query("SELECT * FROM suppressions ORDER BY id")
+            .fetch_all(&self.pool)
+            .await?;
+        rows.iter().map(suppression_from_row).collect()
+    }
+
+    pub async fn get_suppression(&self, id: i64) -> Result<Suppression, ConfigRepoError> {
+        let row = sqlx::query("SELECT * FROM suppressions WHERE id = ?")
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await?
+            .ok_or(ConfigRepoError::SuppressionNotFound(id))?;
+        suppression_from_row(&row)
+    }
+
+    pub async fn add_suppression(
+        &self,
+        fields: SuppressionFields,
+        username: Option<&str>,
+    ) -> Result<i64, ConfigRepoError> {
+        let sql = r#"
+            INSERT INTO suppressions
+              (signature_id, src_ip, dest_ip, sensor, comment, expires_at, created_by, created_at)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?)"#;
+        let result = sqlx::query(sql)
+            .bind(fields.signature_id as i64)
+            .bind(fields.src_ip.map(|n| n.to_string()))
+            .bind(fields.dest_ip.map(|n| n.to_string()))
+            .bind(fields.sensor)
+            .bind(fields.comment)
+            .bind(fields.expires_at.map(|t| t.to_seconds()))
+            .bind(username)
+            .bind(DateTime::now().to_seconds())
+            .execute(&self.pool)
+            .await?;
+        Ok(result.last_insert_rowid())
+    }
+
+    pub async fn delete_suppression(&self, id: i64) -> Result<(), ConfigRepoError> {
+        let result = sqlx::query("DELETE FROM suppressions WHERE id = ?")
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+        if result.rows_affected() == 0 {
+            return Err(ConfigRepoError::SuppressionNotFound(id));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ip_network() {
+        let network: IpNetwork = "10.1.0.0/16".parse().unwrap();
+        assert!(network.contains(&"10.1.2.3".parse().unwrap()));
+        assert!(!network.contains(&"10.2.0.1".parse().unwrap()));
+        assert!(!network.contains(&"::1".parse().unwrap()));
+        assert_eq!(network.to_string(), "10.1.0.0/16");
+
+        let network: IpNetwork = "10.1.2.3".parse().unwrap();
+        assert!(network.contains(&"10.1.2.3".parse().unwrap()));
+        assert!(!network.contains(&"10.1.2.4".parse().unwrap()));
+        assert_eq!(network.to_string(), "10.1.2.3");
+
+        let network: IpNetwork = "0.0.0.0/0".parse().unwrap();
+        assert!(network.contains(&"192.168.1.1".parse().unwrap()));
+
+        let network: IpNetwork = "2001:db8::/32".parse().unwrap();
+        assert!(network.contains(&"2001:db8::1".parse().unwrap()));
+        assert!(!network.contains(&"2001:db9::1".parse().unwrap()));
+
+        assert!("10.1.0.0/33".parse::<IpNetwork>().is_err());
+        assert!("10.1.0".parse::<IpNetwork>().is_err());
+    }
+
+    #[test]
+    fn test_matches() {
+        let suppression = Suppression {
+            id: 1,
+            signature_id: 2013028,
+            src_ip: Some("10.0.0.0/8".parse().unwrap()),
+            dest_ip: None,
+            sensor: Some("sensor1".to_string()),
+            comment: None,
+            expires_at: None,
+            created_by: None,
+            created_at: DateTime::now(),
+        };
+        let mut event = json!({
+            "host": "sensor1",
+            "src_ip": "10.16.1.1",
+            "dest_ip": "1.1.1.1",
+            "alert": {"signature_id": 2013028},
+        });
+        assert!(suppression.matches(&event));
+
+        event["src_ip"] = "192.168.1.1".into();
+        assert!(!suppression.matches(&event));
+
+        event["src_ip"] = "10.16.1.1".into();
+        event["host"] = "sensor2".into();
+        assert!(!suppression.matches(&event));
+
+        event["host"] = "sensor1".into();
+        event["alert"]["signature_id"] = 1.into();
+        assert!(!suppression.matches(&event));
+    }
+}
@@ -2,7 +2,7 @@
 // SPDX-License-Identifier: MIT
 
 use crate::datetime::DateTime;
-use crate::elastic::HistoryEntryBuilder;
+use crate::elastic::{HistoryEntry, HistoryEntryBuilder};
 use crate::eve::eve::ensure_has_history;
 use crate::eventrepo::DatastoreError;
 use crate::server::api::AlertGroupSpec;
@@ -12,7 +12,7 @@ use crate::{LOG_QUERIES, LOG_QUERY_PLAN};
 use serde_json::json;
 use sqlx::sqlite::SqliteArguments;
 use sqlx::Arguments;
-use sqlx::{Row, SqliteConnection, SqlitePool};
+use sqlx::{Encode, Row, Sqlite, SqliteConnection, SqlitePool, Type};
 use std::sync::Arc;
 use std::time::Instant;
 use tracing::{debug, info, instrument, warn};
@@ -21,10 +21,14 @@ use super::has_table;
 
 mod agg;
 mod alerts;
+mod assign;
+mod bulk;
 mod comments;
 mod dhcp;
 mod events;
 mod stats;
+mod status;
+mod tags;
 
 /// SQLite implementation of the event datastore.
 pub(crate) struct SqliteEventRepo {
@@ -114,7 +118,7 @@ impl SqliteEventRepo {
     ) -> Result<Option<serde_json::Value>, DatastoreError> {
         let sql = r#"
             SELECT
-              rowid, archived, escalated, source, history
+              rowid, archived, escalated, source, history, status, assignee
             FROM events
             WHERE rowid = ?"#;
 
@@ -149,6 +153,8 @@ impl SqliteEventRepo {
 
             ensure_has_history(&mut parsed);
             parsed["evebox"]["history"] = history;
+            parsed["evebox"]["status"] = row.try_get::<String, _>("status")?.into();
+            parsed["evebox"]["assignee"] = row.try_get::<Option<String>, _>("assignee")?.into();
 
             let response = json!({
                 "_id": rowid,
@@ -161,16 +167,20 @@ impl SqliteEventRepo {
 
     // TODO: Unsure if the current query string needs to be considered. The Go code didn't
     //          consider it.
-    pub async fn archive_by_alert_group(
+    async fn set_archived_by_alert_group(
         &self,
         alert_group: AlertGroupSpec,
-    ) -> Result<(), DatastoreError> {
-        debug!("Archiving alert group: {:?}", alert_group);
-
-        let action = HistoryEntryBuilder::new_archive().build();
+        archived: bool,
+    ) -> Result<u64, DatastoreError> {
+        let action = if archived {
+            HistoryEntryBuilder::new_archive()
+        } else {
+            HistoryEntryBuilder::new_unarchive()
+        }
+        .build();
         let sql = "
             UPDATE events
-            SET archived = 1,
+            SET archived = ?,
               history = json_insert(history, '$[#]', json(?))
             WHERE %WHERE%
         ";
@@ -178,19 +188,14 @@ impl SqliteEventRepo {
         let mut args = SqliteArguments::default();
         let mut filters: Vec<String> = Vec::new();
 
+        args.add(if archived { 1 } else { 0 })?;
         args.add(action.to_json())?;
 
         filters.push("json_extract(events.source, '$.event_type') = 'alert'".to_string());
-        filters.push("archived = 0".to_string());
-
-        filters.push("json_extract(events.source, '$.alert.signature_id') = ?".to_string());
-        args.add(alert_group.signature_id as i64)?;
+        filters.push("archived = ?".to_string());
+        args.add(if archived { 0 } else { 1 })?;
 
-        filters.push("json_extract(events.source, '$.src_ip') = ?".to_string());
-        args.add(alert_group.src_ip)?;
-
-        filters.push("json_extract(events.source, '$.dest_ip') = ?".to_string());
-        args.add(&alert_group.dest_ip)?;
+        push_alert_group_filters(&alert_group, &mut filters, &mut args)?;
 
         let mints_nanos = crate::datetime::parse(&alert_group.min_timestamp, None)?.to_nanos();
         filters.push("timestamp >= ?".to_string());
@@ -216,11 +221,29 @@ impl SqliteEventRepo {
         let query_elapsed = start.elapsed();
         let n = x.rows_affected();
         debug!(
-            "Archived {n} alerts in {} ms (write-lock wait: {})",
+            "Set {n} alerts to archived = {archived} in {} ms (write-lock wait: {})",
             query_elapsed.as_millis(),
             write_lock_elapsed.as_millis()
         );
 
+        Ok(n)
+    }
+
+    pub async fn archive_by_alert_group(
+        &self,
+        alert_group: AlertGroupSpec,
+    ) -> Result<(), DatastoreError> {
+        debug!("Archiving alert group: {:?}", alert_group);
+        self.set_archived_by_alert_group(alert_group, true).await?;
+        Ok(())
+    }
+
+    pub async fn unarchive_by_alert_group(
+        &self,
+        alert_group: AlertGroupSpec,
+    ) -> Result<(), DatastoreError> {
+        debug!("Un-archiving alert group: {:?}", alert_group);
+        self.set_archived_by_alert_group(alert_group, false).await?;
         Ok(())
     }
 
@@ -252,14 +275,7 @@ impl SqliteEventRepo {
         filters.push("escalated = ?".to_string());
         args.add(if escalate { 0 } else { 1 })?;
 
-        filters.push("json_extract(events.source, '$.alert.signature_id') = ?".to_string());
-        args.add(alert_group.signature_id as i64)?;
-
-        filters.push("json_extract(events.source, '$.src_ip') = ?".to_string());
-        args.add(alert_group.src_ip)?;
-
-        filters.push("json_extract(events.source, '$.dest_ip') = ?".to_string());
-        args.add(alert_group.dest_ip)?;
+        push_alert_group_filters(&alert_group, &mut filters, &mut args)?;
 
         let mints = crate::datetime::parse(&alert_group.min_timestamp, None)?;
         filters.push("timestamp >= ?".to_string());
@@ -312,11 +328,20 @@ impl SqliteEventRepo {
         Ok(())
     }
 
-    pub async fn archive_event_by_id(&self, event_id: &str) -> Result<(), DatastoreError> {
-        let action = HistoryEntryBuilder::new_archive().build();
+    async fn set_archived_for_id(
+        &self,
+        event_id: &str,
+        archived: bool,
+    ) -> Result<(), DatastoreError> {
+        let action = if archived {
+            HistoryEntryBuilder::new_archive()
+        } else {
+            HistoryEntryBuilder::new_unarchive()
+        }
+        .build();
         let sql = r#"
             UPDATE events
-            SET archived = 1,
+            SET archived = ?,
               history = json_insert(history, '$[#]', json(?))
             WHERE rowid = ?"#;
 
@@ -326,6 +351,7 @@ impl SqliteEventRepo {
 
         let mut conn = self.writer.lock().await;
         let n = sqlx::query(sql)
+            .bind(if archived { 1 } else { 0 })
             .bind(action.to_json())
             .bind(event_id)
             .execute(&mut *conn)
@@ -339,6 +365,14 @@ impl SqliteEventRepo {
         }
     }
 
+    pub async fn archive_event_by_id(&self, event_id: &str) -> Result<(), DatastoreError> {
+        self.set_archived_for_id(event_id, true).await
+    }
+
+    pub async fn unarchive_event_by_id(&self, event_id: &str) -> Result<(), DatastoreError> {
+        self.set_archived_for_id(event_id, false).await
+    }
+
     async fn set_escalation_for_id(
         &self,
         event_id: &str,
@@ -403,3 +437,119 @@ impl SqliteEventRepo {
         Ok(sensors)
     }
 }
+
+/// Add a filter for each key of an alert group.
+fn push_alert_group_filters(
+    alert_group: &AlertGroupSpec,
+    filters: &mut Vec<String>,
+    args: &mut SqliteArguments,
+) -> Result<(), DatastoreError> {
+    for (key, value) in alert_group.key_values()? {
+        filters.push(format!(
+            "json_extract(events.source, '$.{}') = ?",
+            key.field()
+        ));
+        match value {
+            serde_json::Value::Number(n) => args.add(n.as_i64())?,
+            serde_json::Value::String(s) => args.add(s)?,
+            _ => args.add(value.to_string())?,
+        }
+    }
+    Ok(())
+}
+
+impl SqliteEventRepo {
+    /// Update an event by ID, setting `set`, an assignment such as
+    /// `status = ?` taking `value` as its argument, and recording the
+    /// action in the history.
+    async fn update_by_id<'q, T>(
+        &self,
+        event_id: &str,
+        set: &str,
+        value: T,
+        action: &HistoryEntry,
+    ) -> Result<(), DatastoreError>
+    where
+        T: 'q + Encode<'q, Sqlite> + Type<Sqlite>,
+    {
+        let event_id: i64 = event_id.parse()?;
+        let sql = format!(
+            "UPDATE events
+            SET {set},
+              history = json_insert(history, '$[#]', json(?))
+            WHERE rowid = ?"
+        );
+
+        let mut args = SqliteArguments::default();
+        args.add(value)?;
+        args.add(action.to_json())?;
+        args.add(event_id)?;
+
+        let mut conn = self.writer.lock().await;
+        let n = sqlx::query_with(&sql, args)
+            .execute(&mut *conn)
+            .await?
+            .rows_affected();
+        if n == 0 {
+            Err(DatastoreError::EventNotFound)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Update the alerts of an alert group like `update_by_id`, only
+    /// updating alerts matching `filter`, which takes `value` as its
+    /// argument. Returns the number of alerts updated.
+    async fn update_by_alert_group<'q, T>(
+        &self,
+        alert_group: &AlertGroupSpec,
+        set: &str,
+        filter: &str,
+        value: T,
+        action: &HistoryEntry,
+    ) -> Result<u64, DatastoreError>
+    where
+        T: 'q + Clone + Encode<'q, Sqlite> + Type<Sqlite>,
+    {
+        let mut args = SqliteArguments::default();
+        let mut filters: Vec<String> = Vec::new();
+
+        args.add(value.clone())?;
+        args.add(action.to_json())?;
+
+        filters.push("json_extract(events.source, '$.event_type') = 'alert'".to_string());
+        filters.push(filter.to_string());
+        args.add(value)?;
+
+        push_alert_group_filters(alert_group, &mut filters, &mut args)?;
+
+        let mints = crate::datetime::parse(&alert_group.min_timestamp, None)?;
+        filters.push("timestamp >= ?".to_string());
+        args.add(mints.to_nanos())?;
+
+        let maxts = crate::datetime::parse(&alert_group.max_timestamp, None)?;
+        filters.push("timestamp <= ?".to_string());
+        args.add(maxts.to_nanos())?;
+
+        let sql = format!(
+            "UPDATE events
+            SET {set},
+              history = json_insert(history, '$[#]', json(?))
+            WHERE {}",
+            filters.join(" AND ")
+        );
+
+        if *LOG_QUERY_PLAN {
+            log_query_plan(&self.pool, &sql, &args).await;
+        } else if *LOG_QUERIES {
+            info!("sql={}", &sql);
+        }
+
+        let mut conn = self.writer.lock().await;
+        let n = sqlx::query_with(&sql, args)
+            .execute(&mut *conn)
+            .await?
+            .rows_affected();
+        Ok(n)
+    }
+}
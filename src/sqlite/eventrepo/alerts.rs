@@ -10,8 +10,8 @@ use tracing::{debug, error, info, instrument, warn};
 
 use super::SqliteEventRepo;
 use crate::datetime::DateTime;
-use crate::eventrepo::{AggAlert, AggAlertMetadata, AlertsResult};
-use crate::sqlite::builder::EventQueryBuilder;
+use crate::eventrepo::{AggAlert, AggAlertMetadata, AlertGroupKey, AlertsResult};
+use crate::sqlite::builder::{assignee_filter, tag_filter, EventQueryBuilder};
 use crate::sqlite::log_query_plan;
 use crate::{elastic::AlertQueryOptions, eventrepo::DatastoreError};
 use crate::{queryparser, LOG_QUERIES, LOG_QUERY_PLAN};
@@ -33,6 +33,7 @@ impl SqliteEventRepo {
         &self,
         options: AlertQueryOptions,
     ) -> Result<AlertsResult, DatastoreError> {
+        let group_keys = options.group_keys().to_vec();
         let mut builder = EventQueryBuilder::new(self.fts().await);
         builder
             .select("rowid")
@@ -40,6 +41,8 @@ impl SqliteEventRepo {
             .select("escalated")
             .select("archived")
             .select("history")
+            .select("status")
+            .select("assignee")
             .selectjs("alert.signature_id")
             .selectjs("alert.signature")
             .selectjs("alert.severity")
@@ -84,6 +87,12 @@ impl SqliteEventRepo {
                 .push_arg(ts.to_nanos())?;
         }
 
+        if let Some(ts) = options.timestamp_lte {
+            builder
+                .push_where("timestamp <= ?")
+                .push_arg(ts.to_nanos())?;
+        }
+
         // Query string.
         if let Some(query_string) = options.query_string {
             match queryparser::parse(&query_string, None) {
@@ -93,10 +102,12 @@ impl SqliteEventRepo {
                         &err, &query_string
                     );
                 }
-                Ok(elements) => {
+                Ok(mut elements) => {
+                    queryparser::resolve_assignee_me(&mut elements, options.username.as_deref());
                     for el in &elements {
                         match &el.value {
-                            queryparser::QueryValue::String(s) => {
+                            queryparser::QueryValue::String(s)
+                            | queryparser::QueryValue::Fts(s) => {
                                 if el.negated {
                                     builder
                                         .push_where("events.source NOT LIKE ?")
@@ -107,6 +118,24 @@ impl SqliteEventRepo {
                                         .push_arg(format!("%{}%", s))?;
                                 }
                             }
+                            queryparser::QueryValue::KeyValue(k, v) if k == "assignee" => {
+                                let (filter, arg) = assignee_filter(v, el.negated);
+                                builder.push_where(filter);
+                                if let Some(arg) = arg {
+                                    builder.push_arg(arg)?;
+                                }
+                            }
+                            queryparser::QueryValue::KeyValue(k, v) if k == "tag" => {
+                                builder
+                                    .push_where(tag_filter(el.negated))
+                                    .push_arg(v.to_string())?;
+                            }
+                            queryparser::QueryValue::KeyValue(k, v) if k == "status" => {
+                                let op = if el.negated { "!=" } else { "=" };
+                                builder
+                                    .push_where(format!("status {op} ?"))
+                                    .push_arg(v.to_string())?;
+                            }
                             queryparser::QueryValue::KeyValue(k, v) => {
                                 // TODO: Handle negation - maybe use query builder?
                                 if let Ok(v) = v.parse::<i64>() {
@@ -165,11 +194,23 @@ impl SqliteEventRepo {
             let dns: serde_json::Value = row.try_get("dns").unwrap_or(serde_json::Value::Null);
             let quic: serde_json::Value = row.try_get("quic").unwrap_or(serde_json::Value::Null);
             let http_hostname: Option<String> = row.try_get("http_hostname")?;
+            let status: String = row.try_get("status")?;
+            let assignee: Option<String> = row.try_get("assignee")?;
 
             if let Some(host) = host {
                 sensors.insert(host);
             }
 
+            let key = group_keys
+                .iter()
+                .map(|key| match key {
+                    AlertGroupKey::SignatureId => alert_signature_id.to_string(),
+                    AlertGroupKey::SrcIp => src_ip.clone(),
+                    AlertGroupKey::DestIp => dest_ip.clone(),
+                })
+                .collect::<Vec<String>>()
+                .join(" ");
+
             let mut source = json!({
                 "timestamp": DateTime::from_nanos(timestamp as i64).to_eve(),
                 "tags": tags,
@@ -185,14 +226,16 @@ impl SqliteEventRepo {
                 "tls": tls,
                 "dns": dns,
                 "quic": quic,
+                "evebox": {
+                    "status": status,
+                    "assignee": assignee,
+                },
             });
 
             if let Some(http_hostname) = http_hostname {
                 source["http"]["hostname"] = http_hostname.into();
             }
 
-            let key = format!("{alert_signature_id}{src_ip}{dest_ip}");
-
             if let serde_json::Value::Null = &source["tags"] {
                 let tags: Vec<String> = Vec::new();
                 source["tags"] = tags.into();
@@ -257,6 +300,8 @@ impl SqliteEventRepo {
             timed_out,
             took: 0,
             ecs: false,
+            truncated: false,
+            group_by: group_keys,
         })
     }
 
@@ -265,13 +310,16 @@ impl SqliteEventRepo {
         &self,
         options: AlertQueryOptions,
     ) -> Result<AlertsResult, DatastoreError> {
+        let group_keys = options.group_keys().to_vec();
         let query = r#"
     		    SELECT b.count,
 			        a.rowid as id,
               b.mints as mints,
               b.escalated_count,
               a.archived,
-              a.source
+              a.source,
+              a.status,
+              a.assignee
             FROM events a
             INNER JOIN
             (
@@ -283,10 +331,7 @@ impl SqliteEventRepo {
                 sum(escalated) as escalated_count
                 FROM %FROM%
                 WHERE %WHERE%
-                GROUP BY
-                  json_extract(events.source, '$.alert.signature_id'),
-                  json_extract(events.source, '$.src_ip'),
-                  json_extract(events.source, '$.dest_ip')
+                GROUP BY %GROUP_BY%
             ) AS b
              WHERE a.rowid = b.rowid AND
                a.timestamp = b.maxts
@@ -328,6 +373,11 @@ impl SqliteEventRepo {
             args.add(ts.to_nanos())?;
         }
 
+        if let Some(ts) = options.timestamp_lte {
+            filters.push("timestamp <= ?".into());
+            args.add(ts.to_nanos())?;
+        }
+
         // Query string.
         if let Some(query_string) = options.query_string {
             match queryparser::parse(&query_string, None) {
@@ -337,10 +387,12 @@ impl SqliteEventRepo {
                         &err, &query_string
                     );
                 }
-                Ok(elements) => {
+                Ok(mut elements) => {
+                    queryparser::resolve_assignee_me(&mut elements, options.username.as_deref());
                     for el in &elements {
                         match &el.value {
-                            queryparser::QueryValue::String(s) => {
+                            queryparser::QueryValue::String(s)
+                            | queryparser::QueryValue::Fts(s) => {
                                 if el.negated {
                                     filters.push("events.source NOT LIKE ?".into());
                                     args.add(format!("%{s}%"))?;
@@ -349,6 +401,22 @@ impl SqliteEventRepo {
                                     args.add(format!("%{s}%"))?;
                                 }
                             }
+                            queryparser::QueryValue::KeyValue(k, v) if k == "assignee" => {
+                                let (filter, arg) = assignee_filter(v, el.negated);
+                                filters.push(filter);
+                                if let Some(arg) = arg {
+                                    args.add(arg)?;
+                                }
+                            }
+                            queryparser::QueryValue::KeyValue(k, v) if k == "tag" => {
+                                filters.push(tag_filter(el.negated));
+                                args.add(v.to_string())?;
+                            }
+                            queryparser::QueryValue::KeyValue(k, v) if k == "status" => {
+                                let op = if el.negated { "!=" } else { "=" };
+                                filters.push(format!("events.status {op} ?"));
+                                args.add(v.to_string())?;
+                            }
                             queryparser::QueryValue::KeyValue(k, v) => {
                                 // TODO: Handle negation - maybe use query builder?
                                 if let Ok(v) = v.parse::<i64>() {
@@ -375,8 +443,14 @@ impl SqliteEventRepo {
             }
         }
 
+        let group_by = group_keys
+            .iter()
+            .map(|key| format!("json_extract(events.source, '$.{}')", key.field()))
+            .collect::<Vec<String>>();
+
         let query = query.replace("%WHERE%", &filters.join(" AND "));
         let query = query.replace("%FROM%", &from.join(", "));
+        let query = query.replace("%GROUP_BY%", &group_by.join(", "));
 
         if *LOG_QUERY_PLAN {
             log_query_plan(&self.pool, &query, &args).await;
@@ -406,6 +480,8 @@ impl SqliteEventRepo {
             timed_out: false,
             took: 0,
             ecs: false,
+            truncated: false,
+            group_by: group_keys,
         })
     }
 }
@@ -418,6 +494,8 @@ fn alert_row_mapper(row: SqliteRow) -> Result<AggAlert, DatastoreError> {
     let escalated_count: i64 = row.try_get(3)?;
     let archived: i8 = row.try_get(4)?;
     let mut parsed: serde_json::Value = row.try_get(5)?;
+    let status: String = row.try_get(6)?;
+    let assignee: Option<String> = row.try_get(7)?;
 
     if let serde_json::Value::Null = &parsed["tags"] {
         let tags: Vec<String> = Vec::new();
@@ -448,6 +526,10 @@ fn alert_row_mapper(row: SqliteRow) -> Result<AggAlert, DatastoreError> {
         "host": parsed["host"],
         "dns": parsed["dns"],
         "tls": parsed["tls"],
+        "evebox": {
+            "status": status,
+            "assignee": assignee,
+        },
     });
 
     if parsed["http"]["hostname"].as_str().is_some() {
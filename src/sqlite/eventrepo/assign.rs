@@ -0,0 +1,57 @@
+// SPDX-FileCopyrightText: (C) 2024 Jason Ish <jason@codemonkey.net>
+// SPDX-License-Identifier: MIT
+
+use std::sync::Arc;
+use std::time::Instant;
+
+use tracing::debug;
+
+use super::SqliteEventRepo;
+use crate::elastic::HistoryEntryBuilder;
+use crate::eventrepo::DatastoreError;
+use crate::server::api::AlertGroupSpec;
+use crate::server::session::Session;
+
+impl SqliteEventRepo {
+    /// Assign an event to a user, or unassign it if the assignee is
+    /// `None`.
+    pub async fn set_assignee_by_id(
+        &self,
+        event_id: &str,
+        assignee: Option<String>,
+        session: Arc<Session>,
+    ) -> Result<(), DatastoreError> {
+        let action = HistoryEntryBuilder::new_assign(assignee.clone())
+            .username(session.username.clone())
+            .build();
+        self.update_by_id(event_id, "assignee = ?", assignee, &action)
+            .await
+    }
+
+    pub async fn set_assignee_by_alert_group(
+        &self,
+        alert_group: AlertGroupSpec,
+        assignee: Option<String>,
+        session: Arc<Session>,
+    ) -> Result<(), DatastoreError> {
+        let action = HistoryEntryBuilder::new_assign(assignee.clone())
+            .username(session.username.clone())
+            .build();
+        let start = Instant::now();
+        let n = self
+            .update_by_alert_group(
+                &alert_group,
+                "assignee = ?",
+                "assignee IS NOT ?",
+                assignee.clone(),
+                &action,
+            )
+            .await?;
+        debug!(
+            "Set assignee of {n} alerts to {:?} in {:?}",
+            assignee,
+            start.elapsed()
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,135 @@
+// SPDX-FileCopyrightText: (C) 2024 Jason Ish <jason@codemonkey.net>
+// SPDX-License-Identifier: MIT
+
+use std::sync::Arc;
+use std::time::Instant;
+
+use sqlx::sqlite::SqliteArguments;
+use sqlx::{Arguments, Connection};
+use tracing::{info, instrument};
+
+use super::comments::index_comment;
+use super::SqliteEventRepo;
+use crate::elastic::HistoryEntryBuilder;
+use crate::eventrepo::{BulkAction, BulkActionParams, DatastoreError};
+use crate::server::session::Session;
+use crate::sqlite::builder::EventQueryBuilder;
+use crate::sqlite::log_query_plan;
+use crate::{LOG_QUERIES, LOG_QUERY_PLAN};
+
+impl SqliteEventRepo {
+    #[instrument(skip_all)]
+    pub async fn bulk_action(
+        &self,
+        params: &BulkActionParams,
+        action: &BulkAction,
+        session: Arc<Session>,
+    ) -> Result<u64, DatastoreError> {
+        let mut builder = EventQueryBuilder::new(self.fts().await);
+        let entry = action.history_entry(session.username.clone());
+
+        // The history entry is the first argument of the update, so
+        // must be added before any arguments of the where clause.
+        if !params.dry_run {
+            builder.push_arg(entry.to_json())?;
+        }
+
+        builder.select("events.rowid");
+        builder.from("events");
+        builder.left_join_from_query_string(&params.query_string)?;
+        builder.push_where("json_extract(events.source, '$.event_type') = 'alert'");
+        let set = match action {
+            BulkAction::Archive => {
+                builder.push_where("events.archived = 0");
+                "archived = 1,"
+            }
+            BulkAction::Unarchive => {
+                builder.push_where("events.archived = 1");
+                "archived = 0,"
+            }
+            BulkAction::Escalate => {
+                builder.push_where("events.escalated = 0");
+                "escalated = 1,"
+            }
+            BulkAction::Deescalate => {
+                builder.push_where("events.escalated = 1");
+                "escalated = 0,"
+            }
+            BulkAction::Comment(_) => "",
+        };
+        builder.apply_query_string(&params.query_string)?;
+        let (query, args) = builder.build()?;
+
+        if params.dry_run {
+            let sql = format!("SELECT count(*) FROM ({query})");
+            if *LOG_QUERY_PLAN {
+                log_query_plan(&self.pool, &sql, &args).await;
+            } else if *LOG_QUERIES {
+                info!("query={}; args={:?}", &sql, &args);
+            }
+            let count: i64 = sqlx::query_scalar_with(&sql, args)
+                .fetch_one(&self.pool)
+                .await?;
+            return Ok(count as u64);
+        }
+
+        let sql = format!(
+            "UPDATE events
+             SET {set} history = json_insert(history, '$[#]', json(?))
+             WHERE rowid IN ({query})
+             RETURNING rowid"
+        );
+
+        if *LOG_QUERY_PLAN {
+            log_query_plan(&self.pool, &sql, &args).await;
+        } else if *LOG_QUERIES {
+            info!("query={}; args={:?}", &sql, &args);
+        }
+
+        let start = Instant::now();
+        let mut conn = self.writer.lock().await;
+        let mut tx = conn.begin().await?;
+        let event_ids: Vec<i64> = sqlx::query_scalar_with(&sql, args)
+            .fetch_all(&mut *tx)
+            .await?;
+        if let BulkAction::Comment(_) = action {
+            index_comment(&mut tx, &entry, &event_ids).await?;
+        }
+        tx.commit().await?;
+        let n = event_ids.len() as u64;
+        info!(
+            ?action,
+            "Bulk action updated {n} events in {:?}",
+            start.elapsed()
+        );
+        Ok(n)
+    }
+
+    /// Archive the events with the given IDs in one update, returning
+    /// the number of events archived. Events already archived are not
+    /// updated.
+    pub async fn archive_events_by_id(&self, event_ids: &[String]) -> Result<u64, DatastoreError> {
+        if event_ids.is_empty() {
+            return Ok(0);
+        }
+        let action = HistoryEntryBuilder::new_archive().build();
+        let mut args = SqliteArguments::default();
+        args.add(action.to_json())?;
+        for event_id in event_ids {
+            args.add(event_id.parse::<i64>()?)?;
+        }
+        let sql = format!(
+            "UPDATE events
+             SET archived = 1, history = json_insert(history, '$[#]', json(?))
+             WHERE archived = 0 AND rowid IN ({})",
+            vec!["?"; event_ids.len()].join(", ")
+        );
+
+        let mut conn = self.writer.lock().await;
+        let n = sqlx::query_with(&sql, args)
+            .execute(&mut *conn)
+            .await?
+            .rows_affected();
+        Ok(n)
+    }
+}
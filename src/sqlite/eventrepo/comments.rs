@@ -2,13 +2,25 @@
 // SPDX-License-Identifier: MIT
 
 use std::sync::Arc;
+use std::time::Instant;
 
-use sqlx::Connection;
-use tracing::warn;
+use sqlx::sqlite::SqliteArguments;
+use sqlx::{Arguments, Connection, SqliteConnection};
+use tracing::{debug, info, warn};
 
-use crate::{elastic::HistoryEntryBuilder, eventrepo::DatastoreError, server::session::Session};
+use crate::datetime::DateTime;
+use crate::elastic::{HistoryEntry, HistoryEntryBuilder};
+use crate::server::api::AlertGroupSpec;
+use crate::sqlite::log_query_plan;
+use crate::{eventrepo::DatastoreError, server::session::Session};
+use crate::{LOG_QUERIES, LOG_QUERY_PLAN};
 
-use super::SqliteEventRepo;
+use super::{push_alert_group_filters, SqliteEventRepo};
+
+/// Matches a history entry with the comment ID and, if not null, the
+/// owning username. Takes the arguments: comment ID, owner, owner.
+const COMMENT_MATCH: &str = "(json_extract(value, '$.id') IS ?
+    AND (? IS NULL OR json_extract(value, '$.username') IS ?))";
 
 impl SqliteEventRepo {
     pub async fn comment_event_by_id(
@@ -37,13 +49,159 @@ impl SqliteEventRepo {
             .await?
             .rows_affected();
 
-        tx.commit().await?;
-
         if n == 0 {
             warn!("Archive by event ID request did not update any events");
-            Err(DatastoreError::EventNotFound)
+            return Err(DatastoreError::EventNotFound);
+        }
+
+        index_comment(&mut tx, &action, &[event_id]).await?;
+        tx.commit().await?;
+        Ok(())
+    }
+
+    pub async fn comment_by_alert_group(
+        &self,
+        alert_group: AlertGroupSpec,
+        comment: String,
+        session: Arc<Session>,
+    ) -> Result<(), DatastoreError> {
+        let action = HistoryEntryBuilder::new_comment()
+            .username(session.username.clone())
+            .comment(comment)
+            .build();
+        let sql = "
+            UPDATE events
+            SET history = json_insert(history, '$[#]', json(?))
+            WHERE %WHERE%
+            RETURNING rowid
+        ";
+
+        let mut args = SqliteArguments::default();
+        let mut filters: Vec<String> = Vec::new();
+
+        args.add(action.to_json())?;
+
+        filters.push("json_extract(events.source, '$.event_type') = 'alert'".to_string());
+
+        push_alert_group_filters(&alert_group, &mut filters, &mut args)?;
+
+        let mints = crate::datetime::parse(&alert_group.min_timestamp, None)?;
+        filters.push("timestamp >= ?".to_string());
+        args.add(mints.to_nanos())?;
+
+        let maxts = crate::datetime::parse(&alert_group.max_timestamp, None)?;
+        filters.push("timestamp <= ?".to_string());
+        args.add(maxts.to_nanos())?;
+
+        let sql = sql.replace("%WHERE%", &filters.join(" AND "));
+
+        if *LOG_QUERY_PLAN {
+            log_query_plan(&self.pool, &sql, &args).await;
+        } else if *LOG_QUERIES {
+            info!("sql={}", &sql);
+        }
+
+        let start = Instant::now();
+        let mut conn = self.writer.lock().await;
+        let mut tx = conn.begin().await?;
+        let event_ids: Vec<i64> = sqlx::query_scalar_with(&sql, args)
+            .fetch_all(&mut *tx)
+            .await?;
+        index_comment(&mut tx, &action, &event_ids).await?;
+        tx.commit().await?;
+        debug!(
+            "Commented on {} alerts in {:?}",
+            event_ids.len(),
+            start.elapsed()
+        );
+        Ok(())
+    }
+
+    /// Edit a comment, or delete it if `comment` is `None`. If an
+    /// owner is given, only comments made by that user are changed.
+    /// Returns the number of events updated.
+    pub async fn update_comment(
+        &self,
+        comment_id: &str,
+        comment: Option<String>,
+        owner: Option<&str>,
+    ) -> Result<u64, DatastoreError> {
+        let deleted = comment.is_none();
+        let mut args = SqliteArguments::default();
+        let history = if let Some(comment) = comment {
+            args.add(comment_id)?;
+            args.add(owner)?;
+            args.add(owner)?;
+            args.add(comment)?;
+            args.add(DateTime::now().to_rfc3339_utc())?;
+            format!(
+                "SELECT json_group_array(
+                   CASE WHEN {COMMENT_MATCH}
+                   THEN json(json_set(value, '$.comment', ?, '$.edited', ?))
+                   ELSE json(value) END)
+                 FROM json_each(events.history)"
+            )
         } else {
-            Ok(())
+            args.add(comment_id)?;
+            args.add(owner)?;
+            args.add(owner)?;
+            format!(
+                "SELECT json_group_array(json(value))
+                 FROM json_each(events.history)
+                 WHERE NOT {COMMENT_MATCH}"
+            )
+        };
+
+        args.add(comment_id)?;
+        args.add(comment_id)?;
+        args.add(owner)?;
+        args.add(owner)?;
+        let sql = format!(
+            "UPDATE events
+             SET history = ({history})
+             WHERE rowid IN (SELECT event_id FROM event_comments WHERE id = ?)
+               AND EXISTS (SELECT 1 FROM json_each(events.history) WHERE {COMMENT_MATCH})
+             RETURNING rowid"
+        );
+
+        let mut conn = self.writer.lock().await;
+        let mut tx = conn.begin().await?;
+        let event_ids: Vec<i64> = sqlx::query_scalar_with(&sql, args)
+            .fetch_all(&mut *tx)
+            .await?;
+        if deleted {
+            sqlx::query(
+                "DELETE FROM event_comments
+                 WHERE id = ? AND event_id IN (SELECT value FROM json_each(?))",
+            )
+            .bind(comment_id)
+            .bind(serde_json::to_string(&event_ids)?)
+            .execute(&mut *tx)
+            .await?;
         }
+        tx.commit().await?;
+        let n = event_ids.len() as u64;
+        Ok(n)
     }
 }
+
+/// Record the events a comment was added to, so the comment can later
+/// be found without scanning the history of every event.
+pub(super) async fn index_comment(
+    conn: &mut SqliteConnection,
+    entry: &HistoryEntry,
+    event_ids: &[i64],
+) -> Result<(), DatastoreError> {
+    let Some(id) = &entry.id else {
+        return Ok(());
+    };
+    sqlx::query(
+        "INSERT OR IGNORE INTO event_comments (id, event_id)
+         SELECT ?, value FROM json_each(?)",
+    )
+    .bind(id)
+    .bind(serde_json::to_string(event_ids)?)
+    .execute(conn)
+    .await?;
+    Ok(())
+}
@@ -3,7 +3,10 @@
 
 use super::SqliteEventRepo;
 use crate::{
-    eventrepo::{DatastoreError, EventQueryParams},
+    eventrepo::{
+        DatastoreError, EventCursor, EventExportParams, EventPage, EventQueryParams,
+        MAX_EVENTS_SIZE,
+    },
     sqlite::{builder::EventQueryBuilder, log_query_plan},
     LOG_QUERIES, LOG_QUERY_PLAN,
 };
@@ -18,15 +21,19 @@ impl SqliteEventRepo {
         &self,
         options: EventQueryParams,
     ) -> Result<serde_json::Value, DatastoreError> {
+        let size = options.size.unwrap_or(MAX_EVENTS_SIZE).max(1);
         let mut builder = EventQueryBuilder::new(self.fts().await);
         builder
             .select("DISTINCT(events.rowid) AS id")
             .select("events.archived AS archived")
             .select("events.escalated AS escalated")
-            .select("events.source AS source");
+            .select("events.source AS source")
+            .select("events.timestamp AS timestamp")
+            .select("events.status AS status")
+            .select("events.assignee AS assignee");
         builder.from("events");
         builder.left_join_from_query_string(&options.query_string)?;
-        builder.limit(500);
+        builder.limit(size as i64);
 
         if let Some(event_type) = options.event_type {
             builder
@@ -46,10 +53,24 @@ impl SqliteEventRepo {
 
         builder.apply_query_string(&options.query_string)?;
 
-        if let Some(order) = &options.order {
-            builder.order_by("events.timestamp", order);
-        } else {
-            builder.order_by("events.timestamp", "DESC");
+        let ascending = options
+            .order
+            .as_ref()
+            .map(|order| order.eq_ignore_ascii_case("asc"))
+            .unwrap_or(false);
+        let order = if ascending { "ASC" } else { "DESC" };
+
+        if let Some(cursor) = &options.cursor {
+            let op = if ascending { ">" } else { "<" };
+            builder
+                .push_where(format!("(events.timestamp, events.rowid) {op} (?, ?)"))
+                .push_arg(cursor.timestamp)?;
+            builder.push_arg(cursor.id)?;
+        }
+
+        builder.order_by("events.timestamp", order);
+        if options.paginate {
+            builder.order_by("events.rowid", order);
         }
 
         let (sql, params) = builder.build()?;
@@ -63,7 +84,11 @@ impl SqliteEventRepo {
         let now = Instant::now();
         let mut rows = sqlx::query_with(&sql, params).fetch(&self.pool);
         let mut events = vec![];
+        let mut last = None;
         while let Some(row) = rows.try_next().await? {
+            let id: i64 = row.try_get(0)?;
+            let timestamp: i64 = row.try_get(4)?;
+            last = Some((timestamp, id));
             events.push(row_mapper(row)?);
         }
 
@@ -73,12 +98,94 @@ impl SqliteEventRepo {
             now.elapsed().as_millis()
         );
 
-        let response = json!({
+        let full_page = events.len() as u64 == size;
+        let mut response = json!({
             "ecs": false,
             "events": events,
         });
+
+        if options.paginate && full_page {
+            if let Some((timestamp, id)) = last {
+                let cursor = EventCursor {
+                    timestamp,
+                    id,
+                    pit: None,
+                };
+                response["next_cursor"] = cursor.encode().into();
+            }
+        }
+
         Ok(response)
     }
+
+    /// Fetch a page of events for export using keyset paging on the
+    /// timestamp and rowid, so events with the same timestamp are not
+    /// skipped or duplicated across pages.
+    #[instrument(skip_all)]
+    pub async fn export_page(
+        &self,
+        params: &EventExportParams,
+        cursor: Option<EventCursor>,
+    ) -> Result<EventPage, DatastoreError> {
+        let mut builder = EventQueryBuilder::new(self.fts().await);
+        builder
+            .select("DISTINCT(events.rowid) AS id")
+            .select("events.archived AS archived")
+            .select("events.escalated AS escalated")
+            .select("events.source AS source")
+            .select("events.timestamp AS timestamp")
+            .select("events.status AS status")
+            .select("events.assignee AS assignee");
+        builder.from("events");
+        builder.left_join_from_query_string(&params.query_string)?;
+        builder.limit(params.size as i64);
+
+        if let Some(event_type) = &params.event_type {
+            builder
+                .push_where("json_extract(events.source, '$.event_type') = ?")
+                .push_arg(event_type.to_string())?;
+        }
+
+        builder.apply_query_string(&params.query_string)?;
+
+        if let Some(cursor) = &cursor {
+            builder
+                .push_where("(events.timestamp, events.rowid) > (?, ?)")
+                .push_arg(cursor.timestamp)?;
+            builder.push_arg(cursor.id)?;
+        }
+
+        builder.order_by("events.timestamp", "ASC");
+        builder.order_by("events.rowid", "ASC");
+
+        let (sql, args) = builder.build()?;
+
+        if *LOG_QUERY_PLAN {
+            log_query_plan(&self.pool, &sql, &args).await;
+        } else if *LOG_QUERIES {
+            info!("query={}; args={:?}", &sql.trim(), &args);
+        }
+
+        let mut rows = sqlx::query_with(&sql, args).fetch(&self.pool);
+        let mut page = EventPage::default();
+        let mut last = None;
+        while let Some(row) = rows.try_next().await? {
+            let id: i64 = row.try_get(0)?;
+            let timestamp: i64 = row.try_get(4)?;
+            last = Some((timestamp, id));
+            page.events.push(row_mapper(row)?);
+        }
+
+        if page.events.len() as u64 == params.size {
+            page.cursor = last.map(|(timestamp, id)| EventCursor {
+                timestamp,
+                id,
+                pit: None,
+            });
+        }
+
+        Ok(page)
+    }
 }
 
 fn row_mapper(row: SqliteRow) -> Result<serde_json::Value, sqlx::Error> {
@@ -86,6 +193,8 @@ fn row_mapper(row: SqliteRow) -> Result<serde_json::Value, sqlx::Error> {
     let archived: i8 = row.try_get(1)?;
     let escalated: i8 = row.try_get(2)?;
     let mut parsed: serde_json::Value = row.try_get(3)?;
+    let status: String = row.try_get(5)?;
+    let assignee: Option<String> = row.try_get(6)?;
 
     if let Some(timestamp) = parsed.get("timestamp") {
         parsed["@timestamp"] = timestamp.clone();
@@ -105,6 +214,12 @@ fn row_mapper(row: SqliteRow) -> Result<serde_json::Value, sqlx::Error> {
         }
     }
 
+    if let serde_json::Value::Null = &parsed["evebox"] {
+        parsed["evebox"] = json!({});
+    }
+    parsed["evebox"]["status"] = status.into();
+    parsed["evebox"]["assignee"] = assignee.into();
+
     let event = json!({
         "_id": id,
         "_source": parsed,
@@ -4,7 +4,9 @@
 use super::SqliteEventRepo;
 use crate::{
     datetime::DateTime,
-    eventrepo::{DatastoreError, StatsAggQueryParams},
+    eventrepo::{
+        DatastoreError, SensorStatsParams, StatsAggQueryParams, StatsCounters, StatsSample,
+    },
     queryparser::{QueryElement, QueryValue},
     sqlite::{builder::EventQueryBuilder, log_query_plan, log_query_plan2},
     util, LOG_QUERIES, LOG_QUERY_PLAN,
@@ -243,4 +245,74 @@ impl SqliteEventRepo {
             "data": response_data,
         }))
     }
+
+    /// Return the last stats event of each sensor per interval.
+    pub async fn stats_samples(
+        &self,
+        params: &SensorStatsParams,
+    ) -> Result<Vec<StatsSample>, DatastoreError> {
+        let interval = params.interval.max(1);
+        let mut args = SqliteArguments::default();
+
+        // With a single MAX aggregate, SQLite takes the bare columns
+        // from the row with the maximum value.
+        let sql = format!(
+            "
+            SELECT
+              COALESCE(json_extract(events.source, '$.host'), '') AS host,
+              timestamp / 1000000000 / {interval} AS bucket,
+              MAX(timestamp),
+              json_extract(events.source, '$.stats')
+              FROM events
+              WHERE %WHERE%
+              GROUP BY host, bucket
+              ORDER BY host, bucket
+            "
+        );
+
+        let mut filters = vec![
+            "json_extract(events.source, '$.event_type') = 'stats'",
+            "timestamp >= ?",
+        ];
+        args.add(params.start_time.to_nanos())?;
+
+        if let Some(sensor_name) = params.sensor_name.as_ref() {
+            filters.push("json_extract(events.source, '$.host') = ?");
+            args.add(sensor_name)?;
+        }
+
+        let sql = sql.replace("%WHERE%", &filters.join(" AND "));
+        if *LOG_QUERY_PLAN {
+            log_query_plan(&self.pool, &sql, &args).await;
+        }
+
+        if *LOG_QUERIES {
+            info!("sql={}, params={:?}", &sql, &args);
+        }
+
+        let timer = Instant::now();
+        let rows: Vec<(String, i64, i64, Option<String>)> = sqlx::query_as_with(&sql, args)
+            .fetch_all(&self.pool)
+            .await?;
+        let samples = rows
+            .into_iter()
+            .map(|(sensor, _bucket, timestamp, stats)| {
+                let stats = stats
+                    .and_then(|stats| serde_json::from_str(&stats).ok())
+                    .unwrap_or_default();
+                StatsSample {
+                    sensor,
+                    timestamp: DateTime::from_nanos(timestamp),
+                    counters: StatsCounters::from_stats(&stats),
+                }
+            })
+            .collect::<Vec<_>>();
+
+        debug!(
+            "Returning {} stats samples in {} ms",
+            samples.len(),
+            timer.elapsed().as_millis()
+        );
+        Ok(samples)
+    }
 }
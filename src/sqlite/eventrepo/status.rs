@@ -0,0 +1,56 @@
+// SPDX-FileCopyrightText: (C) 2024 Jason Ish <jason@codemonkey.net>
+// SPDX-License-Identifier: MIT
+
+use std::sync::Arc;
+use std::time::Instant;
+
+use tracing::debug;
+
+use super::SqliteEventRepo;
+use crate::elastic::HistoryEntryBuilder;
+use crate::eventrepo::{DatastoreError, TriageStatus};
+use crate::server::api::AlertGroupSpec;
+use crate::server::session::Session;
+
+impl SqliteEventRepo {
+    pub async fn set_status_by_id(
+        &self,
+        event_id: &str,
+        status: TriageStatus,
+        reason: Option<String>,
+        session: Arc<Session>,
+    ) -> Result<(), DatastoreError> {
+        let action = HistoryEntryBuilder::new_status(status, reason)
+            .username(session.username.clone())
+            .build();
+        self.update_by_id(event_id, "status = ?", status.as_str(), &action)
+            .await
+    }
+
+    pub async fn set_status_by_alert_group(
+        &self,
+        alert_group: AlertGroupSpec,
+        status: TriageStatus,
+        reason: Option<String>,
+        session: Arc<Session>,
+    ) -> Result<(), DatastoreError> {
+        let action = HistoryEntryBuilder::new_status(status, reason)
+            .username(session.username.clone())
+            .build();
+        let start = Instant::now();
+        let n = self
+            .update_by_alert_group(
+                &alert_group,
+                "status = ?",
+                "status != ?",
+                status.as_str(),
+                &action,
+            )
+            .await?;
+        debug!(
+            "Set status of {n} alerts to {status} in {:?}",
+            start.elapsed()
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,132 @@
+// SPDX-FileCopyrightText: (C) 2024 Jason Ish <jason@codemonkey.net>
+// SPDX-License-Identifier: MIT
+
+//! User tags on events.
+//!
+//! User tags are stored in the `tags` array of the event source, the
+//! same place as Elasticsearch, so they are returned with the event
+//! without any extra work.
+
+use std::sync::Arc;
+use std::time::Instant;
+
+use tracing::debug;
+
+use super::SqliteEventRepo;
+use crate::elastic::{HistoryEntry, HistoryEntryBuilder};
+use crate::eventrepo::DatastoreError;
+use crate::server::api::AlertGroupSpec;
+use crate::server::session::Session;
+
+/// Set expression adding the tags in the first argument, a JSON
+/// array, to the event tags.
+const ADD_TAGS: &str = "json_set(events.source, '$.tags', (
+    SELECT json_group_array(DISTINCT value) FROM (
+      SELECT value FROM json_each(events.source, '$.tags')
+      UNION ALL
+      SELECT value FROM json_each(?))))";
+
+/// Set expression removing the tags in the first argument from the
+/// event tags.
+const REMOVE_TAGS: &str = "json_set(events.source, '$.tags', (
+    SELECT json_group_array(value) FROM json_each(events.source, '$.tags')
+    WHERE value NOT IN (SELECT value FROM json_each(?))))";
+
+/// Filter for events missing at least one of the tags being added.
+const MISSING_TAGS: &str = "EXISTS (
+    SELECT 1 FROM json_each(?) AS t
+    WHERE t.value NOT IN (SELECT value FROM json_each(events.source, '$.tags')))";
+
+/// Filter for events having at least one of the tags being removed.
+const HAS_TAGS: &str = "EXISTS (
+    SELECT 1 FROM json_each(events.source, '$.tags')
+    WHERE value IN (SELECT value FROM json_each(?)))";
+
+impl SqliteEventRepo {
+    pub async fn add_user_tags_by_id(
+        &self,
+        event_id: &str,
+        tags: Vec<String>,
+        session: Arc<Session>,
+    ) -> Result<(), DatastoreError> {
+        let action = HistoryEntryBuilder::new_add_tags(tags.clone())
+            .username(session.username.clone())
+            .build();
+        self.update_tags_by_id(event_id, ADD_TAGS, &tags, &action)
+            .await
+    }
+
+    pub async fn remove_user_tags_by_id(
+        &self,
+        event_id: &str,
+        tags: Vec<String>,
+        session: Arc<Session>,
+    ) -> Result<(), DatastoreError> {
+        let action = HistoryEntryBuilder::new_remove_tags(tags.clone())
+            .username(session.username.clone())
+            .build();
+        self.update_tags_by_id(event_id, REMOVE_TAGS, &tags, &action)
+            .await
+    }
+
+    pub async fn add_user_tags_by_alert_group(
+        &self,
+        alert_group: AlertGroupSpec,
+        tags: Vec<String>,
+        session: Arc<Session>,
+    ) -> Result<(), DatastoreError> {
+        let action = HistoryEntryBuilder::new_add_tags(tags.clone())
+            .username(session.username.clone())
+            .build();
+        self.update_tags_by_alert_group(alert_group, ADD_TAGS, MISSING_TAGS, &tags, &action)
+            .await
+    }
+
+    pub async fn remove_user_tags_by_alert_group(
+        &self,
+        alert_group: AlertGroupSpec,
+        tags: Vec<String>,
+        session: Arc<Session>,
+    ) -> Result<(), DatastoreError> {
+        let action = HistoryEntryBuilder::new_remove_tags(tags.clone())
+            .username(session.username.clone())
+            .build();
+        self.update_tags_by_alert_group(alert_group, REMOVE_TAGS, HAS_TAGS, &tags, &action)
+            .await
+    }
+
+    async fn update_tags_by_id(
+        &self,
+        event_id: &str,
+        set: &str,
+        tags: &[String],
+        action: &HistoryEntry,
+    ) -> Result<(), DatastoreError> {
+        let tags = serde_json::to_string(tags)?;
+        self.update_by_id(event_id, &format!("source = {set}"), tags, action)
+            .await
+    }
+
+    async fn update_tags_by_alert_group(
+        &self,
+        alert_group: AlertGroupSpec,
+        set: &str,
+        filter: &str,
+        tags: &[String],
+        action: &HistoryEntry,
+    ) -> Result<(), DatastoreError> {
+        let tags = serde_json::to_string(tags)?;
+        let start = Instant::now();
+        let n = self
+            .update_by_alert_group(
+                &alert_group,
+                &format!("source = {set}"),
+                filter,
+                tags,
+                action,
+            )
+            .await?;
+        debug!("Updated tags on {n} alerts in {:?}", start.elapsed());
+        Ok(())
+    }
+}
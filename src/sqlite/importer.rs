@@ -65,6 +65,16 @@ impl SqliteEventSink {
         eve::eve::add_evebox_metadata(&mut event, None);
         AutoArchiveFilter::new().run(&mut event);
 
+        // Events may also have been archived by a filter, such as a
+        // suppression. The archived state is kept in its own column so
+        // the event can be unarchived.
+        if let serde_json::Value::Array(tags) = &mut event["tags"] {
+            if tags.iter().any(|tag| tag == "evebox.archived") {
+                archived = 1;
+                tags.retain(|tag| tag != "evebox.archived");
+            }
+        }
+
         let prepared = PreparedEvent {
             ts: ts.to_nanos(),
             source_values,
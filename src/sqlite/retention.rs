@@ -12,6 +12,7 @@ use tracing::{debug, error, info, trace, warn};
 use super::info::Info;
 use crate::config::Config;
 use crate::datetime::DateTime;
+use crate::metrics::METRICS;
 
 const DEFAULT_RANGE: usize = 7;
 
@@ -138,6 +139,7 @@ async fn retention_task(
                 }
                 Ok(n) => {
                     if n > 0 {
+                        METRICS.retention_deleted(n);
                         debug!(
                             "Deleted {n} events to reduce database size to {} bytes",
                             config.size
@@ -153,6 +155,7 @@ async fn retention_task(
             if range > 0 {
                 match delete_older_than(conn.clone(), range as u64, LIMIT as u64).await {
                     Ok(n) => {
+                        METRICS.retention_deleted(n);
                         count += n;
                         if n == LIMIT as u64 {
                             delay = Duration::from_secs(REPEAT_INTERVAL);
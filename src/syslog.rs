@@ -0,0 +1,533 @@
+// SPDX-FileCopyrightText: (C) 2024 Jason Ish <jason@codemonkey.net>
+// SPDX-License-Identifier: MIT
+
+//! Syslog output. Forwards events as RFC 5424 syslog messages over
+//! UDP, TCP or TLS with the JSON event as the message.
+//!
+//! Messages over TCP and TLS are framed with octet counting as
+//! described in RFC 6587 and RFC 5425, or optionally with a trailing
+//! newline.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use serde::Deserialize;
+use tokio::io::AsyncWriteExt;
+use tokio::net::{TcpStream, UdpSocket};
+use tokio::sync::mpsc;
+use tokio::sync::mpsc::error::TrySendError;
+use tokio::time::{timeout, Instant};
+use tokio_rustls::client::TlsStream;
+use tracing::{info, warn};
+
+use crate::eve::eve::Eve;
+use crate::queryparser::{self, QueryElement};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum Protocol {
+    Udp,
+    Tcp,
+    Tls,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub(crate) enum Framing {
+    OctetCounting,
+    Newline,
+}
+
+/// The `syslog` section of the server and agent configuration.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub(crate) struct SyslogConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// Address of the syslog server as host:port.
+    #[serde(default)]
+    pub address: String,
+    #[serde(default = "default_protocol")]
+    pub protocol: Protocol,
+    #[serde(default = "default_framing")]
+    pub framing: Framing,
+    /// Event types to forward, all if empty.
+    #[serde(default)]
+    pub event_types: Vec<String>,
+    /// Only forward events matching this query string.
+    #[serde(default)]
+    pub query: String,
+    #[serde(default = "default_facility")]
+    pub facility: String,
+    #[serde(default = "default_app_name")]
+    pub app_name: String,
+    #[serde(default)]
+    pub disable_certificate_check: bool,
+}
+
+fn default_protocol() -> Protocol {
+    Protocol::Udp
+}
+
+fn default_framing() -> Framing {
+    Framing::OctetCounting
+}
+
+fn default_facility() -> String {
+    "user".to_string()
+}
+
+fn default_app_name() -> String {
+    "evebox".to_string()
+}
+
+fn parse_facility(name: &str) -> anyhow::Result<u8> {
+    let facility = match name {
+        "kern" => 0,
+        "user" => 1,
+        "mail" => 2,
+        "daemon" => 3,
+        "auth" => 4,
+        "syslog" => 5,
+        "lpr" => 6,
+        "news" => 7,
+        "uucp" => 8,
+        "cron" => 9,
+        "authpriv" => 10,
+        "ftp" => 11,
+        "local0" => 16,
+        "local1" => 17,
+        "local2" => 18,
+        "local3" => 19,
+        "local4" => 20,
+        "local5" => 21,
+        "local6" => 22,
+        "local7" => 23,
+        _ => bail!("invalid syslog facility: {}", name),
+    };
+    Ok(facility)
+}
+
+/// Map the event to a syslog severity. Alerts are mapped from their
+/// Suricata severity, other events are informational.
+fn severity(event: &serde_json::Value) -> u8 {
+    if event["event_type"].as_str() != Some("alert") {
+        return 6;
+    }
+    match event["alert"]["severity"].as_u64() {
+        Some(1) => 3,
+        Some(2) => 4,
+        _ => 5,
+    }
+}
+
+/// Make a value usable as a syslog header field, which must be
+/// printable ASCII without spaces, and no longer than `max`.
+fn header_field(value: &str, max: usize) -> String {
+    let value: String = value
+        .chars()
+        .filter(|c| c.is_ascii_graphic())
+        .take(max)
+        .collect();
+    if value.is_empty() {
+        "-".to_string()
+    } else {
+        value
+    }
+}
+
+/// Messages queued for delivery. Messages are dropped if the queue is
+/// full, such as when the server is slow or unavailable.
+const QUEUE_SIZE: usize = 10000;
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+
+const WRITE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Initial delay before reconnecting after a failed connection,
+/// doubled on each failure up to `MAX_RECONNECT_DELAY`.
+const RECONNECT_DELAY: Duration = Duration::from_secs(1);
+
+const MAX_RECONNECT_DELAY: Duration = Duration::from_secs(60);
+
+enum Connection {
+    Udp(UdpSocket),
+    Tcp(TcpStream),
+    Tls(Box<TlsStream<TcpStream>>),
+}
+
+struct Inner {
+    config: SyslogConfig,
+    facility: u8,
+    query: Vec<QueryElement>,
+    hostname: String,
+}
+
+/// An event sink forwarding events to a syslog server. Clones share
+/// the connection to the server.
+///
+/// Messages are sent by a background task so the server being slow or
+/// unavailable does not hold up the import. Forwarding is best effort:
+/// messages are dropped if the queue is full or they fail to send.
+#[derive(Clone)]
+pub(crate) struct SyslogEventSink {
+    inner: Arc<Inner>,
+    tx: mpsc::Sender<String>,
+    queue: Vec<String>,
+}
+
+impl SyslogEventSink {
+    /// Create a sink and start the delivery task.
+    pub fn start(config: SyslogConfig) -> anyhow::Result<Self> {
+        if config.address.is_empty() {
+            bail!("syslog address not set");
+        }
+        let facility = parse_facility(&config.facility)?;
+        let query = queryparser::parse(&config.query, None)
+            .map_err(|err| anyhow!("bad syslog query: {}", err))?;
+        let tls = if config.protocol == Protocol::Tls {
+            Some(tls_connector(config.disable_certificate_check)?)
+        } else {
+            None
+        };
+        let hostname = gethostname::gethostname().into_string().unwrap_or_default();
+        let (tx, rx) = mpsc::channel(QUEUE_SIZE);
+        let delivery = Delivery {
+            config: config.clone(),
+            tls,
+            connection: None,
+            reconnect_delay: RECONNECT_DELAY,
+            reconnect_at: None,
+            dropped: 0,
+        };
+        tokio::spawn(delivery_task(delivery, rx));
+        Ok(Self {
+            inner: Arc::new(Inner {
+                config,
+                facility,
+                query,
+                hostname,
+            }),
+            tx,
+            queue: Vec::new(),
+        })
+    }
+
+    fn is_selected(&self, event: &serde_json::Value) -> bool {
+        let event_types = &self.inner.config.event_types;
+        if !event_types.is_empty()
+            && !event["event_type"]
+                .as_str()
+                .is_some_and(|event_type| event_types.iter().any(|t| t == event_type))
+        {
+            return false;
+        }
+        queryparser::matches(&self.inner.query, event)
+    }
+
+    /// Format an event as an RFC 5424 message.
+    fn format(&self, event: &serde_json::Value) -> String {
+        let pri = self.inner.facility * 8 + severity(event);
+        let timestamp = event
+            .datetime()
+            .map(|dt| dt.to_rfc3339_utc())
+            .unwrap_or_else(|| "-".to_string());
+        let hostname = header_field(event["host"].as_str().unwrap_or(&self.inner.hostname), 255);
+        let app_name = header_field(&self.inner.config.app_name, 48);
+        let msgid = header_field(event["event_type"].as_str().unwrap_or_default(), 32);
+        format!("<{pri}>1 {timestamp} {hostname} {app_name} - {msgid} - {event}")
+    }
+
+    pub async fn submit(
+        &mut self,
+        event: serde_json::Value,
+    ) -> Result<bool, Box<dyn std::error::Error + Send + Sync>> {
+        if self.is_selected(&event) {
+            self.queue.push(self.format(&event));
+        }
+        Ok(false)
+    }
+
+    pub fn pending(&self) -> usize {
+        self.queue.len()
+    }
+
+    /// Hand the queued messages to the delivery task, returning the
+    /// number of messages queued for delivery.
+    pub async fn commit(&mut self) -> anyhow::Result<usize> {
+        let mut queued = 0;
+        let mut dropped = 0;
+        for message in self.queue.drain(..) {
+            match self.tx.try_send(message) {
+                Ok(()) => queued += 1,
+                Err(TrySendError::Full(_)) => dropped += 1,
+                Err(TrySendError::Closed(_)) => bail!("syslog delivery task has stopped"),
+            }
+        }
+        if dropped > 0 {
+            warn!("Syslog queue full, dropped {} messages", dropped);
+        }
+        Ok(queued)
+    }
+}
+
+/// State of the delivery task.
+struct Delivery {
+    config: SyslogConfig,
+    tls: Option<tokio_rustls::TlsConnector>,
+    connection: Option<Connection>,
+    reconnect_delay: Duration,
+    /// While disconnected, messages are dropped until this time.
+    reconnect_at: Option<Instant>,
+    /// Messages dropped since the last successful connection.
+    dropped: u64,
+}
+
+async fn delivery_task(mut delivery: Delivery, mut rx: mpsc::Receiver<String>) {
+    while let Some(message) = rx.recv().await {
+        delivery.send(&message).await;
+    }
+}
+
+impl Delivery {
+    async fn send(&mut self, message: &str) {
+        if self.connection.is_none() {
+            if self.reconnect_at.is_some_and(|at| Instant::now() < at) {
+                self.dropped += 1;
+                return;
+            }
+            let connection = match timeout(CONNECT_TIMEOUT, self.connect()).await {
+                Ok(result) => result,
+                Err(_) => Err(anyhow!("connection timed out")),
+            };
+            match connection {
+                Ok(connection) => {
+                    if self.dropped > 0 {
+                        warn!(
+                            "Dropped {} syslog messages while disconnected",
+                            self.dropped
+                        );
+                    }
+                    self.connection = Some(connection);
+                    self.reconnect_delay = RECONNECT_DELAY;
+                    self.reconnect_at = None;
+                    self.dropped = 0;
+                }
+                Err(err) => {
+                    warn!(
+                        "Failed to connect to syslog server {}, will retry in {:?}: {}",
+                        self.config.address, self.reconnect_delay, err
+                    );
+                    self.reconnect_at = Some(Instant::now() + self.reconnect_delay);
+                    self.reconnect_delay = (self.reconnect_delay * 2).min(MAX_RECONNECT_DELAY);
+                    self.dropped += 1;
+                    return;
+                }
+            }
+        }
+
+        let framing = self.config.framing;
+        let result = match self.connection.as_mut().unwrap() {
+            Connection::Udp(socket) => {
+                // Datagrams are sent or lost individually, so keep the
+                // socket.
+                if let Err(err) = socket.send(message.as_bytes()).await {
+                    warn!("Failed to send syslog message: {}", err);
+                }
+                return;
+            }
+            Connection::Tcp(stream) => {
+                timeout(WRITE_TIMEOUT, write_framed(stream, framing, message)).await
+            }
+            Connection::Tls(stream) => {
+                timeout(WRITE_TIMEOUT, write_framed(stream, framing, message)).await
+            }
+        };
+        let err = match result {
+            Ok(Ok(())) => return,
+            Ok(Err(err)) => err.to_string(),
+            Err(_) => "write timed out".to_string(),
+        };
+        warn!(
+            "Failed to send to syslog server {}, reconnecting: {}",
+            self.config.address, err
+        );
+        self.connection = None;
+        self.dropped += 1;
+    }
+
+    async fn connect(&self) -> anyhow::Result<Connection> {
+        let address = &self.config.address;
+        let connection = match self.config.protocol {
+            Protocol::Udp => {
+                let socket = UdpSocket::bind(if address.starts_with('[') {
+                    "[::]:0"
+                } else {
+                    "0.0.0.0:0"
+                })
+                .await?;
+                socket.connect(address).await?;
+                Connection::Udp(socket)
+            }
+            Protocol::Tcp => Connection::Tcp(TcpStream::connect(address).await?),
+            Protocol::Tls => {
+                let stream = TcpStream::connect(address).await?;
+                let host = address
+                    .rsplit_once(':')
+                    .map(|(host, _)| host)
+                    .unwrap_or(address)
+                    .trim_start_matches('[')
+                    .trim_end_matches(']');
+                let server_name = rustls::ServerName::try_from(host)?;
+                let connector = self.tls.as_ref().unwrap();
+                Connection::Tls(Box::new(connector.connect(server_name, stream).await?))
+            }
+        };
+        info!(
+            "Connected to syslog server {} ({:?})",
+            address, self.config.protocol
+        );
+        Ok(connection)
+    }
+}
+
+async fn write_framed<W: AsyncWriteExt + Unpin>(
+    stream: &mut W,
+    framing: Framing,
+    message: &str,
+) -> std::io::Result<()> {
+    match framing {
+        Framing::OctetCounting => {
+            stream
+                .write_all(format!("{} {}", message.len(), message).as_bytes())
+                .await?;
+        }
+        Framing::Newline => {
+            stream.write_all(message.as_bytes()).await?;
+            stream.write_all(b"\n").await?;
+        }
+    }
+    stream.flush().await
+}
+
+/// Certificate verifier that accepts any certificate.
+struct NoCertificateVerification;
+
+impl rustls::client::ServerCertVerifier for NoCertificateVerification {
+    fn verify_server_cert(
+        &self,
+        _end_entity: &rustls::Certificate,
+        _intermediates: &[rustls::Certificate],
+        _server_name: &rustls::ServerName,
+        _scts: &mut dyn Iterator<Item = &[u8]>,
+        _ocsp_response: &[u8],
+        _now: std::time::SystemTime,
+    ) -> Result<rustls::client::ServerCertVerified, rustls::Error> {
+        Ok(rustls::client::ServerCertVerified::assertion())
+    }
+}
+
+fn tls_connector(disable_certificate_check: bool) -> anyhow::Result<tokio_rustls::TlsConnector> {
+    let builder = rustls::ClientConfig::builder().with_safe_defaults();
+    let config = if disable_certificate_check {
+        builder
+            .with_custom_certificate_verifier(Arc::new(NoCertificateVerification))
+            .with_no_client_auth()
+    } else {
+        let mut roots = rustls::RootCertStore::empty();
+        for cert in rustls_native_certs::load_native_certs()? {
+            if let Err(err) = roots.add(&rustls::Certificate(cert.0)) {
+                warn!("Failed to load system certificate: {}", err);
+            }
+        }
+        builder.with_root_certificates(roots).with_no_client_auth()
+    };
+    Ok(tokio_rustls::TlsConnector::from(Arc::new(config)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::AsyncReadExt;
+
+    fn config(address: &str, protocol: Protocol) -> SyslogConfig {
+        SyslogConfig {
+            enabled: true,
+            address: address.to_string(),
+            protocol,
+            framing: Framing::OctetCounting,
+            event_types: vec![],
+            query: String::new(),
+            facility: "local0".to_string(),
+            app_name: "evebox".to_string(),
+            disable_certificate_check: false,
+        }
+    }
+
+    fn alert() -> serde_json::Value {
+        json!({
+            "timestamp": "2024-05-16T09:48:44.123456-0600",
+            "event_type": "alert",
+            "host": "sensor one",
+            "alert": {"severity": 2, "signature_id": 1},
+        })
+    }
+
+    #[tokio::test]
+    async fn test_format() {
+        let sink = SyslogEventSink::start(config("127.0.0.1:514", Protocol::Udp)).unwrap();
+        let event = alert();
+        assert_eq!(
+            sink.format(&event),
+            format!("<132>1 2024-05-16T15:48:44.123456Z sensorone evebox - alert - {event}")
+        );
+        assert!(sink
+            .format(&json!({"event_type": "dns"}))
+            .starts_with("<134>1 - "));
+    }
+
+    #[tokio::test]
+    async fn test_is_selected() {
+        let mut config = config("127.0.0.1:514", Protocol::Udp);
+        config.event_types = vec!["alert".to_string()];
+        config.query = "alert.signature_id:1".to_string();
+        let sink = SyslogEventSink::start(config).unwrap();
+        assert!(sink.is_selected(&alert()));
+        assert!(!sink.is_selected(&json!({"event_type": "dns"})));
+        let mut event = alert();
+        event["alert"]["signature_id"] = 2.into();
+        assert!(!sink.is_selected(&event));
+    }
+
+    #[tokio::test]
+    async fn test_udp() {
+        let server = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let address = server.local_addr().unwrap().to_string();
+        let mut sink = SyslogEventSink::start(config(&address, Protocol::Udp)).unwrap();
+        sink.submit(alert()).await.unwrap();
+        assert_eq!(sink.commit().await.unwrap(), 1);
+        let mut buf = [0; 4096];
+        let n = server.recv(&mut buf).await.unwrap();
+        let message = std::str::from_utf8(&buf[..n]).unwrap();
+        assert!(message.starts_with("<132>1 "));
+        assert!(message.ends_with(&alert().to_string()));
+    }
+
+    #[tokio::test]
+    async fn test_tcp() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap().to_string();
+        let mut sink = SyslogEventSink::start(config(&address, Protocol::Tcp)).unwrap();
+        sink.submit(alert()).await.unwrap();
+        sink.submit(alert()).await.unwrap();
+        let expected = sink.format(&alert());
+        assert_eq!(sink.commit().await.unwrap(), 2);
+        drop(sink);
+
+        let (mut stream, _) = listener.accept().await.unwrap();
+        let mut received = String::new();
+        stream.read_to_string(&mut received).await.unwrap();
+        let framed = format!("{} {}", expected.len(), expected);
+        assert_eq!(received, format!("{framed}{framed}"));
+    }
+}
@@ -104,6 +104,42 @@ export async function postComment(
   return postJson(`api/event/${eventId}/comment`, body);
 }
 
+export async function postAggregateAlertComment(
+  alert: EventWrapper,
+  comment: string
+): Promise<any> {
+  let body = {
+    signature_id: alert._source.alert!.signature_id,
+    src_ip: alert._source.src_ip,
+    dest_ip: alert._source.dest_ip,
+    min_timestamp: alert._metadata?.min_timestamp,
+    max_timestamp: alert._metadata?.max_timestamp,
+    comment: comment,
+  };
+  return postJson("api/1/alert-group/comment", body);
+}
+
+export async function editComment(
+  commentId: string,
+  comment: string
+): Promise<any> {
+  let headers = {
+    "x-evebox-session-id": SESSION_ID,
+  };
+  return axios.put(
+    `api/1/comment/${commentId}`,
+    { comment: comment },
+    { headers: headers }
+  );
+}
+
+export async function deleteComment(commentId: string): Promise<any> {
+  let headers = {
+    "x-evebox-session-id": SESSION_ID,
+  };
+  return axios.delete(`api/1/comment/${commentId}`, { headers: headers });
+}
+
 export async function getUser(): Promise<UserResponse> {
   let response = await get("api/1/user");
   return response.data;
@@ -145,6 +181,8 @@ export interface AlertsResponse {
   ecs: boolean;
   took: number;
   timed_out: boolean;
+  // Set if there were more alert groups than returned.
+  truncated: boolean;
 }
 
 export async function alerts(options?: {
@@ -213,6 +251,141 @@ export async function archiveEvent(event: EventWrapper): Promise<any> {
   });
 }
 
+export async function unarchiveAggregateAlert(alert: EventWrapper) {
+  const params = {
+    signature_id: alert._source.alert!.signature_id,
+    src_ip: alert._source.src_ip,
+    dest_ip: alert._source.dest_ip,
+    min_timestamp: alert._metadata?.min_timestamp,
+    max_timestamp: alert._metadata?.max_timestamp,
+  };
+  return queueAdd(() => {
+    return post("api/1/alert-group/unarchive", params);
+  });
+}
+
+export async function unarchiveEvent(event: EventWrapper): Promise<any> {
+  return queueAdd(() => {
+    return post(`api/1/event/${event._id}/unarchive`);
+  });
+}
+
+export type TriageStatus =
+  | "new"
+  | "in-progress"
+  | "true-positive"
+  | "false-positive"
+  | "benign"
+  | "closed";
+
+export async function setAggregateAlertStatus(
+  alert: EventWrapper,
+  status: TriageStatus,
+  reason?: string
+) {
+  const params = {
+    signature_id: alert._source.alert!.signature_id,
+    src_ip: alert._source.src_ip,
+    dest_ip: alert._source.dest_ip,
+    min_timestamp: alert._metadata?.min_timestamp,
+    max_timestamp: alert._metadata?.max_timestamp,
+    status: status,
+    reason: reason,
+  };
+  return queueAdd(() => {
+    return post("api/1/alert-group/status", params);
+  });
+}
+
+export async function setEventStatus(
+  event: EventWrapper,
+  status: TriageStatus,
+  reason?: string
+): Promise<any> {
+  return queueAdd(() => {
+    return post(`api/1/event/${event._id}/status`, { status, reason });
+  });
+}
+
+export async function assignAggregateAlert(
+  alert: EventWrapper,
+  username?: string
+) {
+  const params = {
+    signature_id: alert._source.alert!.signature_id,
+    src_ip: alert._source.src_ip,
+    dest_ip: alert._source.dest_ip,
+    min_timestamp: alert._metadata?.min_timestamp,
+    max_timestamp: alert._metadata?.max_timestamp,
+    username: username,
+  };
+  return queueAdd(() => {
+    return post("api/1/alert-group/assign", params);
+  });
+}
+
+export async function unassignAggregateAlert(alert: EventWrapper) {
+  const params = {
+    signature_id: alert._source.alert!.signature_id,
+    src_ip: alert._source.src_ip,
+    dest_ip: alert._source.dest_ip,
+    min_timestamp: alert._metadata?.min_timestamp,
+    max_timestamp: alert._metadata?.max_timestamp,
+  };
+  return queueAdd(() => {
+    return post("api/1/alert-group/unassign", params);
+  });
+}
+
+export async function assignEvent(
+  event: EventWrapper,
+  username?: string
+): Promise<any> {
+  return queueAdd(() => {
+    return post(`api/1/event/${event._id}/assign`, { username });
+  });
+}
+
+export async function unassignEvent(event: EventWrapper): Promise<any> {
+  return queueAdd(() => {
+    return post(`api/1/event/${event._id}/unassign`);
+  });
+}
+
+export async function tagAggregateAlert(
+  alert: EventWrapper,
+  tags: string[],
+  remove = false
+) {
+  const params = {
+    signature_id: alert._source.alert!.signature_id,
+    src_ip: alert._source.src_ip,
+    dest_ip: alert._source.dest_ip,
+    min_timestamp: alert._metadata?.min_timestamp,
+    max_timestamp: alert._metadata?.max_timestamp,
+    tags: tags,
+  };
+  const action = remove ? "untag" : "tag";
+  return queueAdd(() => {
+    return post(`api/1/alert-group/${action}`, params);
+  });
+}
+
+export async function tagEvent(
+  event: EventWrapper,
+  tags: string[],
+  remove = false
+): Promise<any> {
+  const action = remove ? "untag" : "tag";
+  return queueAdd(() => {
+    return post(`api/1/event/${event._id}/${action}`, { tags });
+  });
+}
+
+export async function getUsers(): Promise<{ users: string[] }> {
+  return get("api/1/users").then((response) => response.data);
+}
+
 export async function escalateAggregateAlert(alert: EventWrapper) {
   const params = {
     signature_id: alert._source.alert!.signature_id,